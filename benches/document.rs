@@ -0,0 +1,116 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::hint::black_box;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use wappu::document::Document;
+use wappu::html::HtmlParser;
+use wappu::selector::Selector;
+
+// Counts live heap bytes so the two representations' footprints can be compared
+struct CountingAllocator;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATED.fetch_add(new_size, Ordering::Relaxed);
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+// A listing page with a few thousand product cards
+fn big_page() -> String {
+    let mut html = String::from("<!DOCTYPE html><html><head><title>Products</title></head><body>");
+    html.push_str("<nav><ul>");
+    for index in 0..50 {
+        html.push_str(&format!(
+            "<li><a href=\"/category/{index}\">Category {index}</a></li>"
+        ));
+    }
+    html.push_str("</ul></nav><main id=\"products\">");
+    for index in 0..5000 {
+        html.push_str(&format!(
+            "<div class=\"card product\" data-id=\"{index}\">\
+             <a href=\"/products/{index}\"><img src=\"/images/{index}.jpg\" alt=\"Product {index}\"></a>\
+             <h2 class=\"title\">Product {index}</h2>\
+             <p class=\"description\">A <b>great</b> product, number {index}.</p>\
+             <span class=\"price\">${index}.99</span>\
+             </div>"
+        ));
+    }
+    html.push_str("</main></body></html>");
+    html
+}
+
+fn allocated_by<T>(build: impl FnOnce() -> T) -> (T, usize) {
+    let before = ALLOCATED.load(Ordering::Relaxed);
+    let value = build();
+    (value, ALLOCATED.load(Ordering::Relaxed) - before)
+}
+
+fn report_memory(html: &str) {
+    let (element, element_bytes) = allocated_by(|| HtmlParser::new().parse_html(html));
+    let (document, document_bytes) = allocated_by(|| Document::parse_html(html));
+    println!(
+        "memory for a {} KiB page: HtmlElement {} KiB, Document {} KiB ({} nodes)",
+        html.len() / 1024,
+        element_bytes / 1024,
+        document_bytes / 1024,
+        document.len()
+    );
+    drop((element, document));
+}
+
+fn bench_document(c: &mut Criterion) {
+    let html = big_page();
+    report_memory(&html);
+
+    let element = HtmlParser::new().parse_html(&html);
+    let document = Document::parse_html(&html);
+    let mut by_class = Selector::new();
+    by_class.from_class_name("price");
+    let mut by_tag_name = Selector::new();
+    by_tag_name.from_tag_name("h2");
+
+    let mut parse = c.benchmark_group("parse");
+    parse.sample_size(10);
+    parse.bench_function("html_element", |b| {
+        b.iter(|| HtmlParser::new().parse_html(black_box(&html)))
+    });
+    parse.bench_function("document", |b| {
+        b.iter(|| Document::parse_html(black_box(&html)))
+    });
+    parse.finish();
+
+    let mut select = c.benchmark_group("select");
+    select.bench_function("html_element_by_class", |b| {
+        b.iter(|| by_class.select(black_box(&element)).elements.len())
+    });
+    select.bench_function("document_by_class", |b| {
+        b.iter(|| document.select(black_box(&by_class)).len())
+    });
+    select.bench_function("html_element_by_tag_name", |b| {
+        b.iter(|| by_tag_name.select(black_box(&element)).elements.len())
+    });
+    select.bench_function("document_by_tag_name", |b| {
+        b.iter(|| document.select(black_box(&by_tag_name)).len())
+    });
+    select.finish();
+}
+
+criterion_group!(benches, bench_document);
+criterion_main!(benches);
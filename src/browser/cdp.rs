@@ -0,0 +1,549 @@
+use crate::engine::client::{WappuClient, WappuError};
+use crate::engine::cookies::{Cookie, SameSite};
+use crate::engine::html::{HtmlElement, HtmlParser};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use futures_util::stream::SplitSink;
+use futures_util::{SinkExt, StreamExt};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, UNIX_EPOCH};
+use tokio::net::TcpStream;
+use tokio::sync::{broadcast, oneshot};
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+const SELECTOR_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+type PendingCalls = Arc<Mutex<HashMap<u64, oneshot::Sender<Result<Value, String>>>>>;
+
+// An event sent by the browser, `session_id` tells which page it belongs to
+#[derive(Debug, Clone)]
+pub struct CdpEvent {
+    pub method: String,
+    pub params: Value,
+    pub session_id: Option<String>,
+}
+
+// A request held back by the browser after `CdpPage::intercept`, it waits until it is
+// continued, fulfilled or failed
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InterceptedRequest {
+    pub request_id: String,
+    pub resource_type: String,
+    pub request: RequestInfo,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RequestInfo {
+    pub url: String,
+    pub method: String,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    #[serde(rename = "postData")]
+    pub post_data: Option<String>,
+}
+
+struct Connection {
+    sink: tokio::sync::Mutex<SplitSink<Socket, Message>>,
+    pending: PendingCalls,
+    events: broadcast::Sender<CdpEvent>,
+    next_id: AtomicU64,
+}
+
+impl Connection {
+    async fn call(
+        &self,
+        session_id: Option<&str>,
+        method: &str,
+        params: Value,
+        timeout: Duration,
+    ) -> Result<Value, WappuError> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let mut message = json!({ "id": id, "method": method, "params": params });
+        if let Some(session_id) = session_id {
+            message["sessionId"] = json!(session_id);
+        }
+
+        let (sender, receiver) = oneshot::channel();
+        self.pending.lock().unwrap().insert(id, sender);
+        let sent = self
+            .sink
+            .lock()
+            .await
+            .send(Message::Text(message.to_string()))
+            .await;
+        if let Err(err) = sent {
+            self.pending.lock().unwrap().remove(&id);
+            return Err(cdp_error(err));
+        }
+
+        match tokio::time::timeout(timeout, receiver).await {
+            Ok(Ok(result)) => result.map_err(|err| cdp_error(format!("{}: {}", method, err))),
+            Ok(Err(_)) => Err(cdp_error("the browser closed the connection")),
+            Err(_) => {
+                self.pending.lock().unwrap().remove(&id);
+                Err(cdp_error(format!("{} timed out", method)))
+            }
+        }
+    }
+}
+
+// Drives an externally launched Chromium over the DevTools protocol, e.g. one started with
+// `chromium --headless --remote-debugging-port=9222`
+pub struct CdpBrowser {
+    connection: Arc<Connection>,
+    timeout: Duration,
+}
+
+impl CdpBrowser {
+    // Connects to a browser from its `ws://` debugger URL, or from the `http://` debugging
+    // endpoint, in which case the debugger URL is looked up from `/json/version`
+    pub async fn connect(endpoint: &str) -> Result<Self, WappuError> {
+        let ws_url = if endpoint.starts_with("http://") || endpoint.starts_with("https://") {
+            let version: Value = WappuClient::new()
+                .get(
+                    &format!("{}/json/version", endpoint.trim_end_matches('/')),
+                    None,
+                )
+                .await?
+                .json()
+                .await?;
+            version["webSocketDebuggerUrl"]
+                .as_str()
+                .ok_or_else(|| cdp_error("the endpoint didn't report a debugger URL"))?
+                .to_string()
+        } else {
+            endpoint.to_string()
+        };
+
+        let (socket, _) = connect_async(ws_url.as_str()).await.map_err(cdp_error)?;
+        let (sink, stream) = socket.split();
+        let pending: PendingCalls = Arc::new(Mutex::new(HashMap::new()));
+        let (events, _) = broadcast::channel(1024);
+
+        tokio::spawn(read_messages(stream, pending.clone(), events.clone()));
+
+        Ok(CdpBrowser {
+            connection: Arc::new(Connection {
+                sink: tokio::sync::Mutex::new(sink),
+                pending,
+                events,
+                next_id: AtomicU64::new(1),
+            }),
+            timeout: DEFAULT_TIMEOUT,
+        })
+    }
+
+    // How long to wait for the browser to answer a command or finish loading a page
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    // Opens a new tab and attaches to it
+    pub async fn new_page(&self) -> Result<CdpPage, WappuError> {
+        let target = self
+            .call("Target.createTarget", json!({ "url": "about:blank" }))
+            .await?;
+        let target_id = string_field(&target, "targetId")?;
+        let attached = self
+            .call(
+                "Target.attachToTarget",
+                json!({ "targetId": target_id, "flatten": true }),
+            )
+            .await?;
+
+        let page = CdpPage {
+            connection: self.connection.clone(),
+            session_id: string_field(&attached, "sessionId")?,
+            target_id,
+            timeout: self.timeout,
+        };
+        for domain in ["Page", "Runtime", "Network"] {
+            page.call(&format!("{}.enable", domain), json!({})).await?;
+        }
+        Ok(page)
+    }
+
+    // Sends a raw protocol command to the browser itself
+    pub async fn call(&self, method: &str, params: Value) -> Result<Value, WappuError> {
+        self.connection
+            .call(None, method, params, self.timeout)
+            .await
+    }
+
+    // Every event the browser sends from now on, for all pages
+    pub fn events(&self) -> broadcast::Receiver<CdpEvent> {
+        self.connection.events.subscribe()
+    }
+
+    pub async fn close(self) -> Result<(), WappuError> {
+        let mut sink = self.connection.sink.lock().await;
+        sink.send(Message::Close(None)).await.map_err(cdp_error)
+    }
+}
+
+// A tab of the browser
+pub struct CdpPage {
+    connection: Arc<Connection>,
+    session_id: String,
+    target_id: String,
+    timeout: Duration,
+}
+
+impl CdpPage {
+    pub fn target_id(&self) -> &str {
+        &self.target_id
+    }
+
+    pub fn session_id(&self) -> &str {
+        &self.session_id
+    }
+
+    // Sends a raw protocol command to this page
+    pub async fn call(&self, method: &str, params: Value) -> Result<Value, WappuError> {
+        self.connection
+            .call(Some(&self.session_id), method, params, self.timeout)
+            .await
+    }
+
+    // Events sent for this page from now on
+    pub fn events(&self) -> broadcast::Receiver<CdpEvent> {
+        self.connection.events.subscribe()
+    }
+
+    // Loads `url` and waits until the page has loaded. Navigations that stay on the same
+    // document, e.g. to a fragment, finish without a load event, and so do downloads and
+    // navigations the page cancels, which only stop the frame loading.
+    pub async fn navigate(&self, url: &str) -> Result<(), WappuError> {
+        // Subscribe first so a fast load event can't be missed
+        let mut events = self.events();
+        let result = self.call("Page.navigate", json!({ "url": url })).await?;
+        if let Some(error) = result["errorText"].as_str() {
+            return Err(cdp_error(format!(
+                "navigating to {} failed: {}",
+                url, error
+            )));
+        }
+        let frame_id = result["frameId"].clone();
+        self.wait_for(&mut events, "the page to load", |event| {
+            match event.method.as_str() {
+                "Page.loadEventFired" => true,
+                "Page.navigatedWithinDocument" | "Page.frameStoppedLoading" => {
+                    event.params["frameId"] == frame_id
+                }
+                _ => false,
+            }
+        })
+        .await
+        .map(|_| ())
+    }
+
+    // Waits until an element matches the CSS `selector`, for content rendered after load
+    pub async fn wait_for_selector(&self, selector: &str) -> Result<(), WappuError> {
+        let expression = format!("document.querySelector({}) !== null", json!(selector));
+        let deadline = tokio::time::Instant::now() + self.timeout;
+        loop {
+            if self.evaluate(&expression).await? == Value::Bool(true) {
+                return Ok(());
+            }
+            if tokio::time::Instant::now() >= deadline {
+                return Err(cdp_error(format!("no element matched {}", selector)));
+            }
+            tokio::time::sleep(SELECTOR_POLL_INTERVAL).await;
+        }
+    }
+
+    // Evaluates a JavaScript expression in the page, awaiting it if it is a promise
+    pub async fn evaluate(&self, expression: &str) -> Result<Value, WappuError> {
+        let result = self
+            .call(
+                "Runtime.evaluate",
+                json!({ "expression": expression, "returnByValue": true, "awaitPromise": true }),
+            )
+            .await?;
+        if let Some(exception) = result.get("exceptionDetails") {
+            let message = exception["exception"]["description"]
+                .as_str()
+                .or_else(|| exception["text"].as_str())
+                .unwrap_or("exception thrown");
+            return Err(WappuError::ScriptError(message.to_string()));
+        }
+        Ok(result["result"]["value"].clone())
+    }
+
+    // The page's current markup, after scripts have run
+    pub async fn content(&self) -> Result<String, WappuError> {
+        match self.evaluate("document.documentElement.outerHTML").await? {
+            Value::String(html) => Ok(html),
+            _ => Err(cdp_error("the page has no document")),
+        }
+    }
+
+    pub async fn document(&self) -> Result<HtmlElement, WappuError> {
+        Ok(HtmlParser::new().parse_html(&self.content().await?))
+    }
+
+    // Holds back requests whose URL matches one of the wildcard `patterns`, e.g. "*/api/*",
+    // until they are handled with `continue_request`, `fulfill_request` or `fail_request`
+    pub async fn intercept(&self, patterns: &[&str]) -> Result<(), WappuError> {
+        let patterns: Vec<Value> = patterns
+            .iter()
+            .map(|pattern| json!({ "urlPattern": pattern }))
+            .collect();
+        self.call("Fetch.enable", json!({ "patterns": patterns }))
+            .await
+            .map(|_| ())
+    }
+
+    // Waits for the next request held back by `intercept`, from the given event receiver so
+    // requests paused before this call aren't lost
+    pub async fn paused_request(
+        &self,
+        events: &mut broadcast::Receiver<CdpEvent>,
+    ) -> Result<InterceptedRequest, WappuError> {
+        let params = self.wait_for_event(events, "Fetch.requestPaused").await?;
+        serde_json::from_value(params).map_err(cdp_error)
+    }
+
+    pub async fn continue_request(&self, request: &InterceptedRequest) -> Result<(), WappuError> {
+        self.call(
+            "Fetch.continueRequest",
+            json!({ "requestId": request.request_id }),
+        )
+        .await
+        .map(|_| ())
+    }
+
+    // Answers the request without it reaching the network
+    pub async fn fulfill_request(
+        &self,
+        request: &InterceptedRequest,
+        status: u16,
+        headers: &[(&str, &str)],
+        body: &str,
+    ) -> Result<(), WappuError> {
+        let headers: Vec<Value> = headers
+            .iter()
+            .map(|(name, value)| json!({ "name": name, "value": value }))
+            .collect();
+        self.call(
+            "Fetch.fulfillRequest",
+            json!({
+                "requestId": request.request_id,
+                "responseCode": status,
+                "responseHeaders": headers,
+                "body": BASE64.encode(body),
+            }),
+        )
+        .await
+        .map(|_| ())
+    }
+
+    // Fails the request with a network error reason such as "Failed" or "BlockedByClient"
+    pub async fn fail_request(
+        &self,
+        request: &InterceptedRequest,
+        reason: &str,
+    ) -> Result<(), WappuError> {
+        self.call(
+            "Fetch.failRequest",
+            json!({ "requestId": request.request_id, "errorReason": reason }),
+        )
+        .await
+        .map(|_| ())
+    }
+
+    // Gives the page the cookies `client` holds for `url`, with their domain, path, expiry,
+    // SameSite and Secure and HttpOnly flags
+    pub async fn import_cookies(&self, client: &WappuClient, url: &str) -> Result<(), WappuError> {
+        let cookies: Vec<Value> = client
+            .cookies(url)
+            .iter()
+            .map(|cookie| {
+                let mut param = json!({
+                    "name": cookie.name,
+                    "value": cookie.value,
+                    "path": cookie.path,
+                    "secure": cookie.secure,
+                    "httpOnly": cookie.http_only,
+                });
+                // Giving the browser a domain makes a domain cookie, host-only cookies are set
+                // for the URL instead
+                if cookie.host_only {
+                    param["url"] = json!(url);
+                } else {
+                    param["domain"] = json!(format!(".{}", cookie.domain));
+                }
+                if let Some(expires) = cookie.expires {
+                    let since = expires.duration_since(UNIX_EPOCH).unwrap_or_default();
+                    param["expires"] = json!(since.as_secs_f64());
+                }
+                if let Some(same_site) = cookie.same_site {
+                    param["sameSite"] = json!(match same_site {
+                        SameSite::Strict => "Strict",
+                        SameSite::Lax => "Lax",
+                        SameSite::None => "None",
+                    });
+                }
+                param
+            })
+            .collect();
+        if cookies.is_empty() {
+            return Ok(());
+        }
+        self.call("Network.setCookies", json!({ "cookies": cookies }))
+            .await
+            .map(|_| ())
+    }
+
+    // Stores the cookies the browser holds for `url` in `client`'s cookie store, keeping their
+    // attributes
+    pub async fn export_cookies(&self, client: &WappuClient, url: &str) -> Result<(), WappuError> {
+        let result = self
+            .call("Network.getCookies", json!({ "urls": [url] }))
+            .await?;
+        for cookie in result["cookies"].as_array().into_iter().flatten() {
+            let (Some(name), Some(value), Some(domain)) = (
+                cookie["name"].as_str(),
+                cookie["value"].as_str(),
+                cookie["domain"].as_str(),
+            ) else {
+                continue;
+            };
+            // Session cookies report an expiry of -1
+            let expires = cookie["expires"]
+                .as_f64()
+                .filter(|expires| *expires >= 0.0 && cookie["session"] != true)
+                .map(|expires| UNIX_EPOCH + Duration::from_secs_f64(expires));
+            let cookie = Cookie {
+                name: name.to_string(),
+                value: value.to_string(),
+                // Host-only cookies carry the bare host as their domain, domain cookies a
+                // leading dot
+                domain: domain.trim_start_matches('.').to_string(),
+                host_only: !domain.starts_with('.'),
+                path: cookie["path"].as_str().unwrap_or("/").to_string(),
+                secure: cookie["secure"].as_bool().unwrap_or(false),
+                http_only: cookie["httpOnly"].as_bool().unwrap_or(false),
+                expires,
+                same_site: match cookie["sameSite"].as_str() {
+                    Some("Strict") => Some(SameSite::Strict),
+                    Some("Lax") => Some(SameSite::Lax),
+                    Some("None") => Some(SameSite::None),
+                    _ => None,
+                },
+            };
+            client.set_cookie(&cookie, url);
+        }
+        Ok(())
+    }
+
+    pub async fn close(self) -> Result<(), WappuError> {
+        self.connection
+            .call(
+                None,
+                "Target.closeTarget",
+                json!({ "targetId": self.target_id }),
+                self.timeout,
+            )
+            .await
+            .map(|_| ())
+    }
+
+    async fn wait_for_event(
+        &self,
+        events: &mut broadcast::Receiver<CdpEvent>,
+        method: &str,
+    ) -> Result<Value, WappuError> {
+        self.wait_for(events, method, |event| event.method == method)
+            .await
+    }
+
+    // Waits for the first event of this page `matches` accepts, `description` names it in the
+    // timeout error
+    async fn wait_for(
+        &self,
+        events: &mut broadcast::Receiver<CdpEvent>,
+        description: &str,
+        matches: impl Fn(&CdpEvent) -> bool,
+    ) -> Result<Value, WappuError> {
+        let wait = async {
+            loop {
+                match events.recv().await {
+                    Ok(event)
+                        if event.session_id.as_deref() == Some(&self.session_id)
+                            && matches(&event) =>
+                    {
+                        return Ok(event.params);
+                    }
+                    Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => {
+                        return Err(cdp_error("the browser closed the connection"));
+                    }
+                }
+            }
+        };
+        tokio::time::timeout(self.timeout, wait)
+            .await
+            .map_err(|_| cdp_error(format!("timed out waiting for {}", description)))?
+    }
+}
+
+// Routes command responses to their callers and everything else to the event subscribers
+async fn read_messages(
+    mut stream: futures_util::stream::SplitStream<Socket>,
+    pending: PendingCalls,
+    events: broadcast::Sender<CdpEvent>,
+) {
+    while let Some(Ok(message)) = stream.next().await {
+        let text = match message {
+            Message::Text(text) => text,
+            Message::Close(_) => break,
+            _ => continue,
+        };
+        let Ok(message) = serde_json::from_str::<Value>(&text) else {
+            continue;
+        };
+
+        if let Some(id) = message["id"].as_u64() {
+            let Some(sender) = pending.lock().unwrap().remove(&id) else {
+                continue;
+            };
+            let result = match message.get("error") {
+                Some(error) => Err(error["message"]
+                    .as_str()
+                    .unwrap_or("unknown error")
+                    .to_string()),
+                None => Ok(message["result"].clone()),
+            };
+            let _ = sender.send(result);
+        } else if let Some(method) = message["method"].as_str() {
+            let _ = events.send(CdpEvent {
+                method: method.to_string(),
+                params: message["params"].clone(),
+                session_id: message["sessionId"].as_str().map(str::to_string),
+            });
+        }
+    }
+    // Dropping the senders fails the calls still waiting for an answer
+    pending.lock().unwrap().clear();
+}
+
+fn string_field(value: &Value, field: &str) -> Result<String, WappuError> {
+    value[field]
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| cdp_error(format!("missing {} in the browser's answer", field)))
+}
+
+fn cdp_error(err: impl std::fmt::Display) -> WappuError {
+    WappuError::CdpError(err.to_string())
+}
@@ -0,0 +1,1590 @@
+// Minimal DOM and browser environment for scripts run by the web engine. The document is loaded
+// from the parsed HtmlElement tree and serialized back once the scripts are done. Network access
+// and HTML parsing are delegated to the engine, everything else lives here.
+(function (global) {
+  "use strict";
+
+  var wappu = {
+    console: [],
+    errors: [],
+    clock: 0,
+  };
+
+  var VOID_ELEMENTS = [
+    "area", "base", "br", "col", "embed", "hr", "img", "input",
+    "link", "meta", "param", "source", "track", "wbr",
+  ];
+
+  function stringify(value) {
+    if (typeof value === "string") {
+      return value;
+    }
+    if (value instanceof Error) {
+      return value.name + ": " + value.message;
+    }
+    try {
+      var json = JSON.stringify(value);
+      return json === undefined ? String(value) : json;
+    } catch (e) {
+      return String(value);
+    }
+  }
+
+  function reportError(error) {
+    wappu.errors.push(stringify(error));
+  }
+
+  function escapeText(text) {
+    return text.replace(/&/g, "&amp;").replace(/</g, "&lt;").replace(/>/g, "&gt;");
+  }
+
+  function escapeAttribute(value) {
+    return value.replace(/&/g, "&amp;").replace(/"/g, "&quot;");
+  }
+
+  // Events
+
+  class Event {
+    constructor(type, options) {
+      options = options || {};
+      this.type = type;
+      this.bubbles = !!options.bubbles;
+      this.cancelable = !!options.cancelable;
+      this.detail = options.detail === undefined ? null : options.detail;
+      this.target = null;
+      this.currentTarget = null;
+      this.defaultPrevented = false;
+      this._stopped = false;
+    }
+    preventDefault() {
+      if (this.cancelable) {
+        this.defaultPrevented = true;
+      }
+    }
+    stopPropagation() {
+      this._stopped = true;
+    }
+    stopImmediatePropagation() {
+      this._stopped = true;
+    }
+  }
+
+  class CustomEvent extends Event {}
+
+  class EventTarget {
+    addEventListener(type, listener) {
+      if (!this._listeners) {
+        this._listeners = {};
+      }
+      var listeners = this._listeners[type] || (this._listeners[type] = []);
+      if (listener && listeners.indexOf(listener) < 0) {
+        listeners.push(listener);
+      }
+    }
+    removeEventListener(type, listener) {
+      var listeners = this._listeners && this._listeners[type];
+      if (listeners && listeners.indexOf(listener) >= 0) {
+        listeners.splice(listeners.indexOf(listener), 1);
+      }
+    }
+    _invoke(event) {
+      event.currentTarget = this;
+      var handler = this["on" + event.type];
+      var listeners = ((this._listeners && this._listeners[event.type]) || []).slice();
+      if (typeof handler === "function") {
+        listeners.unshift(handler);
+      }
+      for (var i = 0; i < listeners.length; i++) {
+        try {
+          var listener = listeners[i];
+          if (typeof listener === "function") {
+            listener.call(this, event);
+          } else if (listener && typeof listener.handleEvent === "function") {
+            listener.handleEvent(event);
+          }
+        } catch (e) {
+          reportError(e);
+        }
+      }
+    }
+    dispatchEvent(event) {
+      event.target = this;
+      var target = this;
+      while (target && !event._stopped) {
+        EventTarget.prototype._invoke.call(target, event);
+        if (!event.bubbles) {
+          break;
+        }
+        target = target === global.document ? global : target.parentNode;
+      }
+      return !event.defaultPrevented;
+    }
+  }
+
+  // Selectors: type, universal, #id, .class and attribute selectors, combined with descendant,
+  // child and sibling combinators and grouped with commas.
+
+  function parseCompound(source) {
+    var compound = { tag: null, id: null, classes: [], attributes: [] };
+    var pattern = /^(\*|[\w-]+)|#([\w-]+)|\.([\w-]+)|\[\s*([\w-]+)\s*(?:([~^$*|]?=)\s*(?:"([^"]*)"|'([^']*)'|([^\]\s]*))\s*)?\]/g;
+    var match;
+    var consumed = 0;
+    while ((match = pattern.exec(source)) !== null) {
+      if (match.index !== consumed) {
+        break;
+      }
+      consumed = pattern.lastIndex;
+      if (match[1]) {
+        compound.tag = match[1] === "*" ? null : match[1].toLowerCase();
+      } else if (match[2]) {
+        compound.id = match[2];
+      } else if (match[3]) {
+        compound.classes.push(match[3]);
+      } else if (match[4]) {
+        var value = match[6] !== undefined ? match[6] : match[7] !== undefined ? match[7] : match[8];
+        compound.attributes.push({ name: match[4].toLowerCase(), op: match[5] || null, value: value });
+      }
+      if (match[0] === "") {
+        break;
+      }
+    }
+    if (consumed !== source.length) {
+      throw new SyntaxError("Unsupported selector: " + source);
+    }
+    return compound;
+  }
+
+  function parseSelector(selector) {
+    return selector.split(",").map(function (group) {
+      var tokens = group.trim().replace(/\s*([>+~])\s*/g, " $1 ").split(/\s+/);
+      var parts = [];
+      var combinator = " ";
+      for (var i = 0; i < tokens.length; i++) {
+        var token = tokens[i];
+        if (token === ">" || token === "+" || token === "~") {
+          combinator = token;
+        } else if (token) {
+          parts.push({ combinator: combinator, compound: parseCompound(token) });
+          combinator = " ";
+        }
+      }
+      if (!parts.length) {
+        throw new SyntaxError("Empty selector: " + selector);
+      }
+      return parts;
+    });
+  }
+
+  function matchesAttribute(element, attribute) {
+    var actual = element.getAttribute(attribute.name);
+    if (actual === null) {
+      return false;
+    }
+    var expected = attribute.value;
+    switch (attribute.op) {
+      case null:
+        return true;
+      case "=":
+        return actual === expected;
+      case "~=":
+        return actual.split(/\s+/).indexOf(expected) >= 0;
+      case "^=":
+        return expected !== "" && actual.indexOf(expected) === 0;
+      case "$=":
+        return expected !== "" && actual.slice(-expected.length) === expected;
+      case "*=":
+        return expected !== "" && actual.indexOf(expected) >= 0;
+      case "|=":
+        return actual === expected || actual.indexOf(expected + "-") === 0;
+    }
+    return false;
+  }
+
+  function matchesCompound(element, compound) {
+    if (compound.tag && element.localName !== compound.tag) {
+      return false;
+    }
+    if (compound.id && element.id !== compound.id) {
+      return false;
+    }
+    for (var i = 0; i < compound.classes.length; i++) {
+      if (!element.classList.contains(compound.classes[i])) {
+        return false;
+      }
+    }
+    for (var j = 0; j < compound.attributes.length; j++) {
+      if (!matchesAttribute(element, compound.attributes[j])) {
+        return false;
+      }
+    }
+    return true;
+  }
+
+  function matchesParts(element, parts, index) {
+    if (!matchesCompound(element, parts[index].compound)) {
+      return false;
+    }
+    if (index === 0) {
+      return true;
+    }
+    var combinator = parts[index].combinator;
+    var candidate;
+    if (combinator === ">") {
+      candidate = element.parentElement;
+      return !!candidate && matchesParts(candidate, parts, index - 1);
+    }
+    if (combinator === "+") {
+      candidate = element.previousElementSibling;
+      return !!candidate && matchesParts(candidate, parts, index - 1);
+    }
+    if (combinator === "~") {
+      for (candidate = element.previousElementSibling; candidate; candidate = candidate.previousElementSibling) {
+        if (matchesParts(candidate, parts, index - 1)) {
+          return true;
+        }
+      }
+      return false;
+    }
+    for (candidate = element.parentElement; candidate; candidate = candidate.parentElement) {
+      if (matchesParts(candidate, parts, index - 1)) {
+        return true;
+      }
+    }
+    return false;
+  }
+
+  function matchesSelector(element, groups) {
+    for (var i = 0; i < groups.length; i++) {
+      if (matchesParts(element, groups[i], groups[i].length - 1)) {
+        return true;
+      }
+    }
+    return false;
+  }
+
+  function descendants(node, predicate, first) {
+    var found = [];
+    (function walk(parent) {
+      for (var i = 0; i < parent.childNodes.length; i++) {
+        var child = parent.childNodes[i];
+        if (child.nodeType === 1) {
+          if (predicate(child)) {
+            found.push(child);
+            if (first) {
+              return true;
+            }
+          }
+          if (walk(child)) {
+            return true;
+          }
+        }
+      }
+      return false;
+    })(node);
+    return found;
+  }
+
+  // Nodes
+
+  class Node extends EventTarget {
+    constructor(nodeType, nodeName) {
+      super();
+      this.nodeType = nodeType;
+      this.nodeName = nodeName;
+      this.parentNode = null;
+      this.childNodes = [];
+    }
+    get ownerDocument() {
+      return this.nodeType === 9 ? null : global.document;
+    }
+    get parentElement() {
+      return this.parentNode && this.parentNode.nodeType === 1 ? this.parentNode : null;
+    }
+    get firstChild() {
+      return this.childNodes[0] || null;
+    }
+    get lastChild() {
+      return this.childNodes[this.childNodes.length - 1] || null;
+    }
+    get nextSibling() {
+      if (!this.parentNode) {
+        return null;
+      }
+      var siblings = this.parentNode.childNodes;
+      return siblings[siblings.indexOf(this) + 1] || null;
+    }
+    get previousSibling() {
+      if (!this.parentNode) {
+        return null;
+      }
+      var siblings = this.parentNode.childNodes;
+      return siblings[siblings.indexOf(this) - 1] || null;
+    }
+    get isConnected() {
+      var node = this;
+      while (node.parentNode) {
+        node = node.parentNode;
+      }
+      return node === global.document;
+    }
+    get nodeValue() {
+      return null;
+    }
+    get textContent() {
+      return this.childNodes
+        .filter(function (child) {
+          return child.nodeType !== 8;
+        })
+        .map(function (child) {
+          return child.textContent;
+        })
+        .join("");
+    }
+    set textContent(value) {
+      this._replaceChildren(value === null || value === "" ? [] : [new Text(String(value))]);
+    }
+    hasChildNodes() {
+      return this.childNodes.length > 0;
+    }
+    contains(node) {
+      for (; node; node = node.parentNode) {
+        if (node === this) {
+          return true;
+        }
+      }
+      return false;
+    }
+    _adopt(nodes) {
+      var adopted = [];
+      for (var i = 0; i < nodes.length; i++) {
+        var node = nodes[i];
+        if (typeof node === "string") {
+          node = new Text(node);
+        }
+        if (node.nodeType === 11) {
+          adopted = adopted.concat(node.childNodes.slice());
+          node.childNodes.slice().forEach(function (child) {
+            node.removeChild(child);
+          });
+        } else {
+          if (node.parentNode) {
+            node.parentNode.removeChild(node);
+          }
+          adopted.push(node);
+        }
+      }
+      return adopted;
+    }
+    _insert(nodes, index) {
+      var adopted = this._adopt(nodes);
+      var parent = this;
+      this.childNodes.splice.apply(this.childNodes, [index, 0].concat(adopted));
+      adopted.forEach(function (node) {
+        node.parentNode = parent;
+        node._inserted();
+      });
+    }
+    _inserted() {
+      for (var i = 0; i < this.childNodes.length; i++) {
+        this.childNodes[i]._inserted();
+      }
+    }
+    _replaceChildren(nodes) {
+      this.childNodes.forEach(function (child) {
+        child.parentNode = null;
+      });
+      this.childNodes = [];
+      this._insert(nodes, 0);
+    }
+    appendChild(node) {
+      this._insert([node], this.childNodes.length);
+      return node;
+    }
+    insertBefore(node, reference) {
+      if (!reference) {
+        return this.appendChild(node);
+      }
+      if (node.parentNode === this) {
+        this.removeChild(node);
+      }
+      var index = this.childNodes.indexOf(reference);
+      if (index < 0) {
+        throw new Error("NotFoundError: reference node is not a child of this node");
+      }
+      this._insert([node], index);
+      return node;
+    }
+    removeChild(node) {
+      var index = this.childNodes.indexOf(node);
+      if (index < 0) {
+        throw new Error("NotFoundError: node is not a child of this node");
+      }
+      this.childNodes.splice(index, 1);
+      node.parentNode = null;
+      return node;
+    }
+    replaceChild(node, old) {
+      this.insertBefore(node, old);
+      return this.removeChild(old);
+    }
+    append() {
+      this._insert(Array.prototype.slice.call(arguments), this.childNodes.length);
+    }
+    prepend() {
+      this._insert(Array.prototype.slice.call(arguments), 0);
+    }
+    replaceChildren() {
+      this._replaceChildren(Array.prototype.slice.call(arguments));
+    }
+    remove() {
+      if (this.parentNode) {
+        this.parentNode.removeChild(this);
+      }
+    }
+    before() {
+      if (this.parentNode) {
+        this.parentNode._insert(Array.prototype.slice.call(arguments), this.parentNode.childNodes.indexOf(this));
+      }
+    }
+    after() {
+      if (this.parentNode) {
+        this.parentNode._insert(Array.prototype.slice.call(arguments), this.parentNode.childNodes.indexOf(this) + 1);
+      }
+    }
+    replaceWith() {
+      var parent = this.parentNode;
+      if (parent) {
+        var index = parent.childNodes.indexOf(this);
+        parent.removeChild(this);
+        parent._insert(Array.prototype.slice.call(arguments), index);
+      }
+    }
+    cloneNode(deep) {
+      var clone = this._shallowClone();
+      if (deep) {
+        for (var i = 0; i < this.childNodes.length; i++) {
+          clone.appendChild(this.childNodes[i].cloneNode(true));
+        }
+      }
+      return clone;
+    }
+    get children() {
+      return this.childNodes.filter(function (child) {
+        return child.nodeType === 1;
+      });
+    }
+    get childElementCount() {
+      return this.children.length;
+    }
+    get firstElementChild() {
+      return this.children[0] || null;
+    }
+    get lastElementChild() {
+      var children = this.children;
+      return children[children.length - 1] || null;
+    }
+    getElementById(id) {
+      return descendants(this, function (element) {
+        return element.id === id;
+      }, true)[0] || null;
+    }
+    getElementsByTagName(tag) {
+      tag = String(tag).toLowerCase();
+      return descendants(this, function (element) {
+        return tag === "*" || element.localName === tag;
+      });
+    }
+    getElementsByClassName(names) {
+      names = String(names).split(/\s+/).filter(Boolean);
+      return descendants(this, function (element) {
+        return names.every(function (name) {
+          return element.classList.contains(name);
+        });
+      });
+    }
+    getElementsByName(name) {
+      return descendants(this, function (element) {
+        return element.getAttribute("name") === name;
+      });
+    }
+    querySelector(selector) {
+      var groups = parseSelector(selector);
+      return descendants(this, function (element) {
+        return matchesSelector(element, groups);
+      }, true)[0] || null;
+    }
+    querySelectorAll(selector) {
+      var groups = parseSelector(selector);
+      return descendants(this, function (element) {
+        return matchesSelector(element, groups);
+      });
+    }
+  }
+
+  class CharacterData extends Node {
+    constructor(nodeType, nodeName, data) {
+      super(nodeType, nodeName);
+      this.data = String(data);
+    }
+    get nodeValue() {
+      return this.data;
+    }
+    get textContent() {
+      return this.data;
+    }
+    set textContent(value) {
+      this.data = String(value);
+    }
+    get length() {
+      return this.data.length;
+    }
+  }
+
+  class Text extends CharacterData {
+    constructor(data) {
+      super(3, "#text", data === undefined ? "" : data);
+    }
+    _shallowClone() {
+      return new Text(this.data);
+    }
+  }
+
+  class Comment extends CharacterData {
+    constructor(data) {
+      super(8, "#comment", data === undefined ? "" : data);
+    }
+    _shallowClone() {
+      return new Comment(this.data);
+    }
+  }
+
+  class DocumentFragment extends Node {
+    constructor() {
+      super(11, "#document-fragment");
+    }
+    _shallowClone() {
+      return new DocumentFragment();
+    }
+  }
+
+  class DOMTokenList {
+    constructor(element) {
+      this._element = element;
+    }
+    _tokens() {
+      return (this._element.getAttribute("class") || "").split(/\s+/).filter(Boolean);
+    }
+    _set(tokens) {
+      this._element.setAttribute("class", tokens.join(" "));
+    }
+    get length() {
+      return this._tokens().length;
+    }
+    get value() {
+      return this._element.getAttribute("class") || "";
+    }
+    item(index) {
+      return this._tokens()[index] || null;
+    }
+    contains(token) {
+      return this._tokens().indexOf(token) >= 0;
+    }
+    add() {
+      var tokens = this._tokens();
+      for (var i = 0; i < arguments.length; i++) {
+        if (tokens.indexOf(arguments[i]) < 0) {
+          tokens.push(arguments[i]);
+        }
+      }
+      this._set(tokens);
+    }
+    remove() {
+      var removed = Array.prototype.slice.call(arguments);
+      this._set(this._tokens().filter(function (token) {
+        return removed.indexOf(token) < 0;
+      }));
+    }
+    toggle(token, force) {
+      var present = this.contains(token);
+      var wanted = force === undefined ? !present : !!force;
+      if (wanted && !present) {
+        this.add(token);
+      } else if (!wanted && present) {
+        this.remove(token);
+      }
+      return wanted;
+    }
+    forEach(callback) {
+      this._tokens().forEach(callback);
+    }
+    toString() {
+      return this.value;
+    }
+  }
+
+  function camelToKebab(name) {
+    return name.replace(/[A-Z]/g, function (letter) {
+      return "-" + letter.toLowerCase();
+    });
+  }
+
+  function kebabToCamel(name) {
+    return name.replace(/-([a-z])/g, function (_, letter) {
+      return letter.toUpperCase();
+    });
+  }
+
+  function parseStyle(text) {
+    var declarations = {};
+    (text || "").split(";").forEach(function (declaration) {
+      var colon = declaration.indexOf(":");
+      if (colon > 0) {
+        declarations[declaration.slice(0, colon).trim().toLowerCase()] = declaration.slice(colon + 1).trim();
+      }
+    });
+    return declarations;
+  }
+
+  // Inline styles are kept in the style attribute so they survive serialization
+  function styleProxy(element) {
+    function write(declarations) {
+      var text = Object.keys(declarations)
+        .filter(function (name) {
+          return declarations[name] !== "";
+        })
+        .map(function (name) {
+          return name + ": " + declarations[name] + ";";
+        })
+        .join(" ");
+      if (text) {
+        element.setAttribute("style", text);
+      } else {
+        element.removeAttribute("style");
+      }
+    }
+    var methods = {
+      getPropertyValue: function (name) {
+        return parseStyle(element.getAttribute("style"))[name] || "";
+      },
+      setProperty: function (name, value) {
+        var declarations = parseStyle(element.getAttribute("style"));
+        declarations[name] = value === null ? "" : String(value);
+        write(declarations);
+      },
+      removeProperty: function (name) {
+        var declarations = parseStyle(element.getAttribute("style"));
+        var old = declarations[name] || "";
+        delete declarations[name];
+        write(declarations);
+        return old;
+      },
+    };
+    return new Proxy({}, {
+      get: function (_, name) {
+        if (typeof name !== "string") {
+          return undefined;
+        }
+        if (methods[name]) {
+          return methods[name];
+        }
+        if (name === "cssText") {
+          return element.getAttribute("style") || "";
+        }
+        return parseStyle(element.getAttribute("style"))[camelToKebab(name)] || "";
+      },
+      set: function (_, name, value) {
+        if (name === "cssText") {
+          element.setAttribute("style", String(value));
+        } else {
+          methods.setProperty(camelToKebab(String(name)), value);
+        }
+        return true;
+      },
+    });
+  }
+
+  function datasetProxy(element) {
+    return new Proxy({}, {
+      get: function (_, name) {
+        if (typeof name !== "string") {
+          return undefined;
+        }
+        var value = element.getAttribute("data-" + camelToKebab(name));
+        return value === null ? undefined : value;
+      },
+      set: function (_, name, value) {
+        element.setAttribute("data-" + camelToKebab(String(name)), String(value));
+        return true;
+      },
+      deleteProperty: function (_, name) {
+        element.removeAttribute("data-" + camelToKebab(String(name)));
+        return true;
+      },
+      has: function (_, name) {
+        return element.hasAttribute("data-" + camelToKebab(String(name)));
+      },
+      ownKeys: function () {
+        return element
+          .getAttributeNames()
+          .filter(function (name) {
+            return name.indexOf("data-") === 0;
+          })
+          .map(function (name) {
+            return kebabToCamel(name.slice(5));
+          });
+      },
+      getOwnPropertyDescriptor: function (_, name) {
+        var value = element.getAttribute("data-" + camelToKebab(String(name)));
+        return value === null ? undefined : { value: value, enumerable: true, configurable: true, writable: true };
+      },
+    });
+  }
+
+  function reflect(name) {
+    return {
+      get: function () {
+        return this.getAttribute(name) || "";
+      },
+      set: function (value) {
+        this.setAttribute(name, value);
+      },
+    };
+  }
+
+  function reflectBoolean(name) {
+    return {
+      get: function () {
+        return this.hasAttribute(name);
+      },
+      set: function (value) {
+        if (value) {
+          this.setAttribute(name, "");
+        } else {
+          this.removeAttribute(name);
+        }
+      },
+    };
+  }
+
+  class Element extends Node {
+    constructor(localName) {
+      super(1, String(localName).toUpperCase());
+      this.localName = String(localName).toLowerCase();
+      this._attributes = new Map();
+      this._executed = false;
+    }
+    get tagName() {
+      return this.nodeName;
+    }
+    get attributes() {
+      var list = [];
+      this._attributes.forEach(function (value, name) {
+        list.push({ name: name, value: value });
+      });
+      return list;
+    }
+    getAttributeNames() {
+      return Array.from(this._attributes.keys());
+    }
+    getAttribute(name) {
+      name = String(name).toLowerCase();
+      return this._attributes.has(name) ? this._attributes.get(name) : null;
+    }
+    setAttribute(name, value) {
+      this._attributes.set(String(name).toLowerCase(), String(value));
+    }
+    removeAttribute(name) {
+      this._attributes.delete(String(name).toLowerCase());
+    }
+    hasAttribute(name) {
+      return this._attributes.has(String(name).toLowerCase());
+    }
+    toggleAttribute(name, force) {
+      var wanted = force === undefined ? !this.hasAttribute(name) : !!force;
+      if (wanted) {
+        this.setAttribute(name, "");
+      } else {
+        this.removeAttribute(name);
+      }
+      return wanted;
+    }
+    get classList() {
+      return new DOMTokenList(this);
+    }
+    get style() {
+      return styleProxy(this);
+    }
+    set style(value) {
+      this.setAttribute("style", String(value));
+    }
+    get dataset() {
+      return datasetProxy(this);
+    }
+    get nextElementSibling() {
+      for (var node = this.nextSibling; node; node = node.nextSibling) {
+        if (node.nodeType === 1) {
+          return node;
+        }
+      }
+      return null;
+    }
+    get previousElementSibling() {
+      for (var node = this.previousSibling; node; node = node.previousSibling) {
+        if (node.nodeType === 1) {
+          return node;
+        }
+      }
+      return null;
+    }
+    get value() {
+      if (this.localName === "textarea") {
+        return this.textContent;
+      }
+      if (this.localName === "select") {
+        var selected = this.querySelector("option[selected]") || this.querySelector("option");
+        return selected ? selected.value : "";
+      }
+      if (this.localName === "option" && !this.hasAttribute("value")) {
+        return this.textContent;
+      }
+      return this.getAttribute("value") || "";
+    }
+    set value(value) {
+      if (this.localName === "textarea") {
+        this.textContent = value;
+      } else {
+        this.setAttribute("value", value);
+      }
+    }
+    get innerHTML() {
+      return this.childNodes.map(serializeNode).join("");
+    }
+    set innerHTML(html) {
+      this._replaceChildren(parseHtml(String(html), this.localName));
+    }
+    get outerHTML() {
+      return serializeNode(this);
+    }
+    set outerHTML(html) {
+      if (this.parentNode) {
+        this.replaceWith.apply(
+          this,
+          parseHtml(String(html), this.parentNode.localName)
+        );
+      }
+    }
+    get innerText() {
+      return this.textContent;
+    }
+    set innerText(value) {
+      this.textContent = value;
+    }
+    insertAdjacentHTML(position, html) {
+      var where = String(position).toLowerCase();
+      var outside = where === "beforebegin" || where === "afterend";
+      var context = outside && this.parentNode ? this.parentNode : this;
+      var nodes = parseHtml(String(html), context.localName);
+      this._insertAdjacent(position, nodes);
+    }
+    insertAdjacentElement(position, element) {
+      this._insertAdjacent(position, [element]);
+      return element;
+    }
+    insertAdjacentText(position, text) {
+      this._insertAdjacent(position, [new Text(text)]);
+    }
+    _insertAdjacent(position, nodes) {
+      switch (String(position).toLowerCase()) {
+        case "beforebegin":
+          this.before.apply(this, nodes);
+          break;
+        case "afterbegin":
+          this.prepend.apply(this, nodes);
+          break;
+        case "beforeend":
+          this.append.apply(this, nodes);
+          break;
+        case "afterend":
+          this.after.apply(this, nodes);
+          break;
+      }
+    }
+    matches(selector) {
+      return matchesSelector(this, parseSelector(selector));
+    }
+    closest(selector) {
+      var groups = parseSelector(selector);
+      for (var element = this; element; element = element.parentElement) {
+        if (matchesSelector(element, groups)) {
+          return element;
+        }
+      }
+      return null;
+    }
+    click() {
+      this.dispatchEvent(new Event("click", { bubbles: true, cancelable: true }));
+    }
+    focus() {}
+    blur() {}
+    getBoundingClientRect() {
+      return { x: 0, y: 0, top: 0, left: 0, right: 0, bottom: 0, width: 0, height: 0 };
+    }
+    _shallowClone() {
+      var clone = new Element(this.localName);
+      this._attributes.forEach(function (value, name) {
+        clone._attributes.set(name, value);
+      });
+      return clone;
+    }
+    _inserted() {
+      if (this.localName === "script" && !this._executed && this.isConnected) {
+        this._executed = true;
+        pendingScripts.push(this);
+      }
+      super._inserted();
+    }
+  }
+
+  ["id", "name", "type", "href", "src", "title", "lang", "alt", "rel", "action", "method", "target", "placeholder"].forEach(function (name) {
+    Object.defineProperty(Element.prototype, name, reflect(name));
+  });
+  ["hidden", "disabled", "checked", "selected", "required", "async", "defer", "multiple", "readOnly"].forEach(function (name) {
+    Object.defineProperty(Element.prototype, name, reflectBoolean(name.toLowerCase()));
+  });
+  Object.defineProperty(Element.prototype, "className", reflect("class"));
+  Object.defineProperty(Element.prototype, "text", {
+    get: function () {
+      return this.textContent;
+    },
+    set: function (value) {
+      this.textContent = value;
+    },
+  });
+
+  class Document extends Node {
+    constructor() {
+      super(9, "#document");
+      this.readyState = "loading";
+      this._cookies = {};
+    }
+    get documentElement() {
+      return this.children[0] || null;
+    }
+    get head() {
+      return this.getElementsByTagName("head")[0] || null;
+    }
+    get body() {
+      return this.getElementsByTagName("body")[0] || null;
+    }
+    get title() {
+      var title = this.getElementsByTagName("title")[0];
+      return title ? title.textContent.trim() : "";
+    }
+    set title(value) {
+      var title = this.getElementsByTagName("title")[0];
+      if (!title && this.head) {
+        title = this.head.appendChild(this.createElement("title"));
+      }
+      if (title) {
+        title.textContent = value;
+      }
+    }
+    get defaultView() {
+      return global;
+    }
+    get location() {
+      return global.location;
+    }
+    get URL() {
+      return global.location.href;
+    }
+    get cookie() {
+      var cookies = this._cookies;
+      return Object.keys(cookies)
+        .map(function (name) {
+          return name + "=" + cookies[name];
+        })
+        .join("; ");
+    }
+    set cookie(value) {
+      var pair = String(value).split(";")[0];
+      var equals = pair.indexOf("=");
+      if (equals > 0) {
+        this._cookies[pair.slice(0, equals).trim()] = pair.slice(equals + 1).trim();
+      }
+    }
+    createElement(name) {
+      return new Element(name);
+    }
+    createElementNS(_, name) {
+      return new Element(name);
+    }
+    createTextNode(data) {
+      return new Text(data);
+    }
+    createComment(data) {
+      return new Comment(data);
+    }
+    createDocumentFragment() {
+      return new DocumentFragment();
+    }
+    createEvent() {
+      var event = new Event("");
+      event.initEvent = function (type, bubbles, cancelable) {
+        this.type = type;
+        this.bubbles = !!bubbles;
+        this.cancelable = !!cancelable;
+      };
+      return event;
+    }
+    write() {
+      var html = Array.prototype.slice.call(arguments).join("");
+      var target = this._currentScript && this._currentScript.parentNode;
+      var nodes = parseHtml(html, target ? target.localName : "body");
+      if (target && this._currentScript) {
+        this._currentScript.after.apply(this._currentScript, nodes);
+      } else if (this.body) {
+        this.body.append.apply(this.body, nodes);
+      }
+    }
+    writeln() {
+      this.write(Array.prototype.slice.call(arguments).join("") + "\n");
+    }
+    get currentScript() {
+      return this._currentScript || null;
+    }
+    hasFocus() {
+      return true;
+    }
+  }
+
+  // Serialization
+
+  function serializeNode(node) {
+    if (node.nodeType === 3) {
+      var parent = node.parentNode;
+      if (parent && (parent.localName === "script" || parent.localName === "style")) {
+        return node.data;
+      }
+      return escapeText(node.data);
+    }
+    if (node.nodeType === 8) {
+      return "<!--" + node.data + "-->";
+    }
+    if (node.nodeType !== 1) {
+      return node.childNodes.map(serializeNode).join("");
+    }
+    var html = "<" + node.localName;
+    node._attributes.forEach(function (value, name) {
+      html += " " + name + '="' + escapeAttribute(value) + '"';
+    });
+    html += ">";
+    if (VOID_ELEMENTS.indexOf(node.localName) >= 0) {
+      return html;
+    }
+    return html + node.childNodes.map(serializeNode).join("") + "</" + node.localName + ">";
+  }
+
+  function fromJson(json) {
+    if (json.comment !== undefined) {
+      return new Comment(json.comment);
+    }
+    if (json.tag === undefined || json.tag === null) {
+      return new Text(json.text || "");
+    }
+    var element = new Element(json.tag);
+    var attributes = json.attributes || {};
+    Object.keys(attributes).forEach(function (name) {
+      element._attributes.set(name, attributes[name]);
+    });
+    (json.children || []).forEach(function (child) {
+      var node = fromJson(child);
+      node.parentNode = element;
+      element.childNodes.push(node);
+    });
+    // Scripts from the page itself are run by the engine in document order
+    element._executed = element.localName === "script";
+    return element;
+  }
+
+  function toJson(node) {
+    if (node.nodeType === 3) {
+      return { text: node.data };
+    }
+    if (node.nodeType === 8) {
+      return { comment: node.data };
+    }
+    var json = { children: [] };
+    if (node.nodeType === 1) {
+      json.tag = node.localName;
+      json.attributes = {};
+      node._attributes.forEach(function (value, name) {
+        json.attributes[name] = value;
+      });
+    }
+    node.childNodes.forEach(function (child) {
+      var childJson = toJson(child);
+      if (childJson) {
+        json.children.push(childJson);
+      }
+    });
+    return json;
+  }
+
+  // Parses markup as the content of a `context` element, "body" when it has no tag name
+  function parseHtml(html, context) {
+    return JSON.parse(__wappuParseHtml(html, context || "body")).map(fromJson);
+  }
+
+  // Scripts inserted by other scripts, run by the engine once the current task is done
+
+  var pendingScripts = [];
+
+  var scriptRegistry = [];
+
+  wappu.takeScripts = function () {
+    var scripts = pendingScripts;
+    pendingScripts = [];
+    return JSON.stringify(scripts.map(function (script) {
+      scriptRegistry.push(script);
+      return {
+        id: scriptRegistry.length - 1,
+        src: script.getAttribute("src"),
+        type: script.getAttribute("type"),
+        text: script.textContent,
+      };
+    }));
+  };
+
+  wappu.beginScript = function (id) {
+    global.document._currentScript = scriptRegistry[id];
+  };
+
+  wappu.endScript = function (id, succeeded) {
+    var script = scriptRegistry[id];
+    global.document._currentScript = null;
+    if (script.hasAttribute("src")) {
+      script.dispatchEvent(new Event(succeeded ? "load" : "error"));
+    }
+  };
+
+  // Timers run on a virtual clock so pages don't need to wait for real time to pass
+
+  var timers = [];
+  var timerSequence = 0;
+
+  function addTimer(callback, delay, args, repeat) {
+    var id = ++timerSequence;
+    delay = Math.max(0, Number(delay) || 0);
+    timers.push({ id: id, at: wappu.clock + delay, callback: callback, args: args, repeat: repeat ? Math.max(1, delay) : 0 });
+    return id;
+  }
+
+  function clearTimer(id) {
+    timers = timers.filter(function (timer) {
+      return timer.id !== id;
+    });
+  }
+
+  wappu.runNextTimer = function (limit) {
+    if (!timers.length) {
+      return false;
+    }
+    timers.sort(function (a, b) {
+      return a.at - b.at || a.id - b.id;
+    });
+    var timer = timers[0];
+    if (timer.at > limit) {
+      return false;
+    }
+    timers.shift();
+    wappu.clock = timer.at;
+    if (timer.repeat) {
+      timer.at += timer.repeat;
+      timers.push(timer);
+    }
+    try {
+      if (typeof timer.callback === "function") {
+        timer.callback.apply(global, timer.args);
+      } else {
+        (0, eval)(String(timer.callback));
+      }
+    } catch (e) {
+      reportError(e);
+    }
+    return true;
+  };
+
+  // Network requests are queued here and performed by the engine through WappuClient
+
+  var pendingRequests = [];
+  var requestCallbacks = {};
+  var requestSequence = 0;
+
+  function queueRequest(method, url, headers, body, callback) {
+    var id = ++requestSequence;
+    pendingRequests.push({
+      id: id,
+      method: String(method || "GET").toUpperCase(),
+      url: String(url),
+      headers: headers || {},
+      body: body === undefined || body === null ? "" : String(body),
+    });
+    requestCallbacks[id] = callback;
+  }
+
+  wappu.takeRequests = function () {
+    var requests = pendingRequests;
+    pendingRequests = [];
+    return JSON.stringify(requests);
+  };
+
+  wappu.completeRequest = function (id, error, response) {
+    var callback = requestCallbacks[id];
+    delete requestCallbacks[id];
+    if (callback) {
+      try {
+        callback(error, response);
+      } catch (e) {
+        reportError(e);
+      }
+    }
+  };
+
+  class Headers {
+    constructor(init) {
+      this._map = {};
+      var map = this._map;
+      if (init instanceof Headers) {
+        init.forEach(function (value, name) {
+          map[name] = value;
+        });
+      } else if (Array.isArray(init)) {
+        init.forEach(function (pair) {
+          map[String(pair[0]).toLowerCase()] = String(pair[1]);
+        });
+      } else if (init) {
+        Object.keys(init).forEach(function (name) {
+          map[name.toLowerCase()] = String(init[name]);
+        });
+      }
+    }
+    get(name) {
+      var value = this._map[String(name).toLowerCase()];
+      return value === undefined ? null : value;
+    }
+    set(name, value) {
+      this._map[String(name).toLowerCase()] = String(value);
+    }
+    append(name, value) {
+      var existing = this.get(name);
+      this.set(name, existing === null ? value : existing + ", " + value);
+    }
+    has(name) {
+      return this.get(name) !== null;
+    }
+    delete(name) {
+      delete this._map[String(name).toLowerCase()];
+    }
+    forEach(callback) {
+      var map = this._map;
+      Object.keys(map).forEach(function (name) {
+        callback(map[name], name);
+      });
+    }
+    _object() {
+      return Object.assign({}, this._map);
+    }
+  }
+
+  class Response {
+    constructor(body, init) {
+      init = init || {};
+      this._body = body === undefined || body === null ? "" : String(body);
+      this.status = init.status === undefined ? 200 : init.status;
+      this.statusText = init.statusText || "";
+      this.url = init.url || "";
+      this.headers = new Headers(init.headers);
+      this.ok = this.status >= 200 && this.status < 300;
+      this.redirected = false;
+      this.type = "basic";
+      this.bodyUsed = false;
+    }
+    text() {
+      this.bodyUsed = true;
+      return Promise.resolve(this._body);
+    }
+    json() {
+      var body = this._body;
+      this.bodyUsed = true;
+      return new Promise(function (resolve) {
+        resolve(JSON.parse(body));
+      });
+    }
+    clone() {
+      return new Response(this._body, { status: this.status, statusText: this.statusText, url: this.url, headers: this.headers });
+    }
+  }
+
+  function fetch(input, init) {
+    init = init || {};
+    var url = typeof input === "string" ? input : input.url;
+    var headers = new Headers(init.headers)._object();
+    return new Promise(function (resolve, reject) {
+      queueRequest(init.method || "GET", url, headers, init.body, function (error, response) {
+        if (error) {
+          reject(new TypeError("Failed to fetch: " + error));
+        } else {
+          resolve(new Response(response.body, response));
+        }
+      });
+    });
+  }
+
+  class XMLHttpRequest extends EventTarget {
+    constructor() {
+      super();
+      this.readyState = 0;
+      this.status = 0;
+      this.statusText = "";
+      this.responseText = "";
+      this.response = "";
+      this.responseType = "";
+      this.responseURL = "";
+      this._headers = {};
+      this._responseHeaders = {};
+    }
+    open(method, url) {
+      this._method = method;
+      this._url = url;
+      this._setReadyState(1);
+    }
+    setRequestHeader(name, value) {
+      this._headers[name] = value;
+    }
+    getResponseHeader(name) {
+      var value = this._responseHeaders[String(name).toLowerCase()];
+      return value === undefined ? null : value;
+    }
+    getAllResponseHeaders() {
+      var headers = this._responseHeaders;
+      return Object.keys(headers)
+        .map(function (name) {
+          return name + ": " + headers[name] + "\r\n";
+        })
+        .join("");
+    }
+    overrideMimeType() {}
+    abort() {
+      this._aborted = true;
+    }
+    send(body) {
+      var request = this;
+      queueRequest(this._method, this._url, this._headers, body, function (error, response) {
+        if (request._aborted) {
+          return;
+        }
+        if (error) {
+          request._setReadyState(4);
+          request.dispatchEvent(new Event("error"));
+          request.dispatchEvent(new Event("loadend"));
+          return;
+        }
+        request.status = response.status;
+        request.statusText = response.statusText;
+        request.responseURL = response.url;
+        request._responseHeaders = response.headers;
+        request.responseText = response.body;
+        if (request.responseType === "json") {
+          try {
+            request.response = JSON.parse(response.body);
+          } catch (e) {
+            request.response = null;
+          }
+        } else {
+          request.response = response.body;
+        }
+        request._setReadyState(4);
+        request.dispatchEvent(new Event("load"));
+        request.dispatchEvent(new Event("loadend"));
+      });
+    }
+    _setReadyState(state) {
+      this.readyState = state;
+      this.dispatchEvent(new Event("readystatechange"));
+    }
+  }
+
+  class Storage {
+    constructor() {
+      this._items = {};
+    }
+    get length() {
+      return Object.keys(this._items).length;
+    }
+    key(index) {
+      return Object.keys(this._items)[index] || null;
+    }
+    getItem(key) {
+      return Object.prototype.hasOwnProperty.call(this._items, key) ? this._items[key] : null;
+    }
+    setItem(key, value) {
+      this._items[key] = String(value);
+    }
+    removeItem(key) {
+      delete this._items[key];
+    }
+    clear() {
+      this._items = {};
+    }
+  }
+
+  function consoleMethod(level) {
+    return function () {
+      var message = Array.prototype.slice.call(arguments).map(stringify).join(" ");
+      wappu.console.push(level === "log" ? message : "[" + level + "] " + message);
+    };
+  }
+
+  // Entry points used by the engine
+
+  wappu.load = function (documentJson, locationJson, userAgent, cookies) {
+    var location = JSON.parse(locationJson);
+    location.toString = function () {
+      return this.href;
+    };
+    location.assign = location.replace = function (url) {
+      wappu.navigation = String(url);
+    };
+    location.reload = function () {};
+
+    var document = new Document();
+    var root = JSON.parse(documentJson);
+    (root.children || []).forEach(function (child) {
+      var node = fromJson(child);
+      node.parentNode = document;
+      document.childNodes.push(node);
+    });
+    (cookies ? cookies.split(";") : []).forEach(function (cookie) {
+      document.cookie = cookie;
+    });
+    pendingScripts = descendants(document, function (element) {
+      return element.localName === "script";
+    });
+
+    global.window = global;
+    global.self = global;
+    global.top = global;
+    global.parent = global;
+    global.document = document;
+    global.location = location;
+    global.navigator = {
+      userAgent: userAgent,
+      language: "en-US",
+      languages: ["en-US", "en"],
+      platform: "",
+      cookieEnabled: true,
+      onLine: true,
+      webdriver: false,
+    };
+    global.history = {
+      length: 1,
+      state: null,
+      pushState: function (state, _, url) {
+        this.state = state;
+        if (url) {
+          wappu.navigation = String(url);
+        }
+      },
+      replaceState: function (state) {
+        this.state = state;
+      },
+      back: function () {},
+      forward: function () {},
+      go: function () {},
+    };
+    global.screen = { width: 1920, height: 1080, availWidth: 1920, availHeight: 1080, colorDepth: 24 };
+    global.innerWidth = 1920;
+    global.innerHeight = 1080;
+    global.devicePixelRatio = 1;
+    global.localStorage = new Storage();
+    global.sessionStorage = new Storage();
+    global.console = {
+      log: consoleMethod("log"),
+      info: consoleMethod("info"),
+      warn: consoleMethod("warn"),
+      error: consoleMethod("error"),
+      debug: consoleMethod("debug"),
+    };
+    global.setTimeout = function (callback, delay) {
+      return addTimer(callback, delay, Array.prototype.slice.call(arguments, 2), false);
+    };
+    global.setInterval = function (callback, delay) {
+      return addTimer(callback, delay, Array.prototype.slice.call(arguments, 2), true);
+    };
+    global.clearTimeout = clearTimer;
+    global.clearInterval = clearTimer;
+    global.requestAnimationFrame = function (callback) {
+      return addTimer(function () {
+        callback(wappu.clock);
+      }, 16, [], false);
+    };
+    global.cancelAnimationFrame = clearTimer;
+    global.queueMicrotask = function (callback) {
+      Promise.resolve().then(callback);
+    };
+    global.getComputedStyle = function (element) {
+      return element.style;
+    };
+    global.matchMedia = function (query) {
+      return { matches: false, media: query, addListener: function () {}, removeListener: function () {}, addEventListener: function () {}, removeEventListener: function () {} };
+    };
+    global.scrollTo = function () {};
+    global.alert = consoleMethod("alert");
+    global.fetch = fetch;
+    global.XMLHttpRequest = XMLHttpRequest;
+    global.Headers = Headers;
+    global.Response = Response;
+    global.Event = Event;
+    global.CustomEvent = CustomEvent;
+    global.EventTarget = EventTarget;
+    global.Node = Node;
+    global.Element = Element;
+    global.HTMLElement = Element;
+    global.Text = Text;
+    global.Comment = Comment;
+    global.Document = Document;
+    global.DocumentFragment = DocumentFragment;
+    global.addEventListener = function (type, listener) {
+      EventTarget.prototype.addEventListener.call(global, type, listener);
+    };
+    global.removeEventListener = function (type, listener) {
+      EventTarget.prototype.removeEventListener.call(global, type, listener);
+    };
+    global.dispatchEvent = function (event) {
+      event.target = global;
+      EventTarget.prototype._invoke.call(global, event);
+      return !event.defaultPrevented;
+    };
+  };
+
+  wappu.fireDocumentEvent = function (type) {
+    if (type === "DOMContentLoaded") {
+      global.document.readyState = "interactive";
+      global.document.dispatchEvent(new Event("readystatechange"));
+      global.document.dispatchEvent(new Event(type, { bubbles: true }));
+    } else {
+      global.document.readyState = "complete";
+      global.document.dispatchEvent(new Event("readystatechange"));
+      global.dispatchEvent(new Event(type));
+    }
+  };
+
+  wappu.serialize = function () {
+    return JSON.stringify(toJson(global.document));
+  };
+
+  wappu.reportError = reportError;
+
+  Object.defineProperty(global, "__wappu", { value: wappu });
+})(globalThis);
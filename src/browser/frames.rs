@@ -0,0 +1,269 @@
+use crate::engine::client::{WappuClient, WappuError};
+use crate::engine::html::{HtmlElement, HtmlParser};
+use crate::engine::selector::{Selection, Selector};
+use crate::utils::{document_base_url, resolve_url};
+use reqwest::header::{HeaderMap, HeaderValue, REFERER};
+use std::future::Future;
+use std::pin::Pin;
+use url::Url;
+
+// URL browsers give documents loaded from an iframe's `srcdoc`
+const SRCDOC_URL: &str = "about:srcdoc";
+
+// A document and the frames loaded inside it
+pub struct Frame {
+    pub url: String,
+    // The frame element's name attribute, None for the top-level document
+    pub name: Option<String>,
+    pub document: HtmlElement,
+    pub frames: Vec<Frame>,
+    // Why the frame's document couldn't be loaded, its document is empty then
+    pub error: Option<String>,
+    base_url: String,
+}
+
+impl Frame {
+    fn new(url: String, name: Option<String>, document: HtmlElement, base_url: String) -> Self {
+        Frame {
+            url,
+            name,
+            document,
+            frames: Vec::new(),
+            error: None,
+            base_url,
+        }
+    }
+
+    fn failed(url: String, name: Option<String>, error: String) -> Self {
+        let mut frame = Frame::new(url.clone(), name, HtmlParser::new().parse_html(""), url);
+        frame.error = Some(error);
+        frame
+    }
+
+    // Elements matching `selector` in this document, followed by those in each of its frames in
+    // turn, depth first. A frame's elements don't sit where its frame element is in the page.
+    pub fn select<'a>(&'a self, selector: &Selector) -> Selection<'a> {
+        self.select_to_depth(selector, usize::MAX)
+    }
+
+    // Like `select`, descending at most `depth` frames deep, 0 only searches this document
+    pub fn select_to_depth<'a>(&'a self, selector: &Selector, depth: usize) -> Selection<'a> {
+        let mut elements = selector.select(&self.document).elements;
+        if depth > 0 {
+            for frame in &self.frames {
+                elements.extend(frame.select_to_depth(selector, depth - 1).elements);
+            }
+        }
+        Selection::new(elements)
+    }
+
+    // This frame and all frames below it, depth first
+    pub fn all_frames(&self) -> Vec<&Frame> {
+        let mut frames = vec![self];
+        for frame in &self.frames {
+            frames.extend(frame.all_frames());
+        }
+        frames
+    }
+
+    // The first frame below this one with the given name
+    pub fn frame(&self, name: &str) -> Option<&Frame> {
+        self.all_frames()
+            .into_iter()
+            .skip(1)
+            .find(|frame| frame.name.as_deref() == Some(name))
+    }
+}
+
+// Loads the documents of `<iframe>` and `<frame>` elements, which the parser leaves empty,
+// following `srcdoc` and `src` like a browser would
+pub struct FrameLoader {
+    client: WappuClient,
+    max_depth: usize,
+    max_frames: usize,
+    same_origin_only: bool,
+}
+
+impl Default for FrameLoader {
+    fn default() -> Self {
+        Self::new(WappuClient::new())
+    }
+}
+
+impl FrameLoader {
+    pub fn new(client: WappuClient) -> Self {
+        FrameLoader {
+            client,
+            max_depth: 3,
+            max_frames: 50,
+            same_origin_only: false,
+        }
+    }
+
+    // How many levels of nested frames to load, 0 loads none
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    // Upper bound on the number of frames loaded for one page
+    pub fn max_frames(mut self, max_frames: usize) -> Self {
+        self.max_frames = max_frames;
+        self
+    }
+
+    // Only load frames from the same origin as the top-level page
+    pub fn same_origin_only(mut self, same_origin_only: bool) -> Self {
+        self.same_origin_only = same_origin_only;
+        self
+    }
+
+    // Fetches the page at `url` and its frames
+    pub async fn load(&self, url: &str) -> Result<Frame, WappuError> {
+        let response = self.client.get(url, None).await?;
+        let document = HtmlParser::new().parse_html(response.text());
+        Ok(self.load_frames(document, response.url()).await)
+    }
+
+    // Loads the frames of an already fetched document
+    pub async fn load_frames(&self, document: HtmlElement, page_url: &str) -> Frame {
+        let base_url = document_base_url(&document, page_url);
+        let mut top = Frame::new(page_url.to_string(), None, document, base_url);
+        let origin = Url::parse(page_url).ok().map(|url| url.origin());
+        let mut budget = self.max_frames;
+        self.load_children(&mut top, origin.as_ref(), 1, &mut budget)
+            .await;
+        top
+    }
+
+    fn load_children<'a>(
+        &'a self,
+        frame: &'a mut Frame,
+        origin: Option<&'a url::Origin>,
+        depth: usize,
+        budget: &'a mut usize,
+    ) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+        Box::pin(async move {
+            if depth > self.max_depth {
+                return;
+            }
+            let mut elements = Vec::new();
+            collect_frame_elements(&frame.document, &mut elements);
+            let sources: Vec<FrameSource> = elements
+                .into_iter()
+                .filter_map(|element| FrameSource::from_element(element, &frame.base_url))
+                .collect();
+
+            for source in sources {
+                if *budget == 0 {
+                    return;
+                }
+                let mut child = match self.load_source(source, frame, origin).await {
+                    Some(child) => child,
+                    None => continue,
+                };
+                *budget -= 1;
+                if child.error.is_none() {
+                    self.load_children(&mut child, origin, depth + 1, budget)
+                        .await;
+                }
+                frame.frames.push(child);
+            }
+        })
+    }
+
+    async fn load_source(
+        &self,
+        source: FrameSource,
+        parent: &Frame,
+        origin: Option<&url::Origin>,
+    ) -> Option<Frame> {
+        let FrameSource { name, kind } = source;
+        match kind {
+            // A srcdoc document shares its parent's origin and base URL
+            FrameKind::Srcdoc(html) => Some(Frame::new(
+                SRCDOC_URL.to_string(),
+                name,
+                HtmlParser::new().parse_html(&html),
+                parent.base_url.clone(),
+            )),
+            FrameKind::Url(url) => {
+                let parsed = Url::parse(&url).ok()?;
+                if !matches!(parsed.scheme(), "http" | "https") {
+                    return None;
+                }
+                if self.same_origin_only && origin != Some(&parsed.origin()) {
+                    return None;
+                }
+                // Documents loaded from srcdoc refer with the URL of the page around them
+                let referer = if parent.url == SRCDOC_URL {
+                    &parent.base_url
+                } else {
+                    &parent.url
+                };
+                Some(self.fetch(url, name, referer).await)
+            }
+        }
+    }
+
+    async fn fetch(&self, url: String, name: Option<String>, referer: &str) -> Frame {
+        let mut headers = HeaderMap::new();
+        if let Ok(referer) = HeaderValue::from_str(referer) {
+            headers.insert(REFERER, referer);
+        }
+        match self.client.get(&url, Some(headers)).await {
+            Ok(response) => {
+                let document = HtmlParser::new().parse_html(response.text());
+                let base_url = document_base_url(&document, response.url());
+                Frame::new(response.url().to_string(), name, document, base_url)
+            }
+            // The client turns error statuses into errors, leave the error page's body out
+            Err(WappuError::UnexpectedStatusCode(status, _)) => {
+                Frame::failed(url, name, format!("Unexpected status code: {}", status))
+            }
+            Err(err) => Frame::failed(url, name, err.to_string()),
+        }
+    }
+}
+
+struct FrameSource {
+    name: Option<String>,
+    kind: FrameKind,
+}
+
+enum FrameKind {
+    Srcdoc(String),
+    Url(String),
+}
+
+impl FrameSource {
+    // `srcdoc` wins over `src` on iframes, frames without either stay empty
+    fn from_element(element: &HtmlElement, base_url: &str) -> Option<Self> {
+        let name = element.attributes.get("name").cloned();
+        let is_iframe = element.tag_name.as_deref() == Some("iframe");
+        if let Some(srcdoc) = element.attributes.get("srcdoc").filter(|_| is_iframe) {
+            return Some(FrameSource {
+                name,
+                kind: FrameKind::Srcdoc(srcdoc.clone()),
+            });
+        }
+        let src = element
+            .attributes
+            .get("src")
+            .map(|src| src.trim())
+            .filter(|src| !src.is_empty())?;
+        Some(FrameSource {
+            name,
+            kind: FrameKind::Url(resolve_url(base_url, src)?),
+        })
+    }
+}
+
+fn collect_frame_elements<'a>(element: &'a HtmlElement, frames: &mut Vec<&'a HtmlElement>) {
+    for child in &element.children {
+        if matches!(child.tag_name.as_deref(), Some("iframe" | "frame")) {
+            frames.push(child);
+        }
+        collect_frame_elements(child, frames);
+    }
+}
@@ -1 +1,6 @@
-pub mod web_engine;
\ No newline at end of file
+#[cfg(feature = "js")]
+pub mod web_engine;
+pub mod session;
+pub mod frames;
+#[cfg(feature = "cdp")]
+pub mod cdp;
@@ -0,0 +1,296 @@
+use crate::engine::client::{WappuClient, WappuError, WappuResponse};
+use crate::engine::form::{Form, FormRequest};
+use crate::engine::html::{HtmlElement, HtmlParser};
+use crate::engine::metadata::PageMetadata;
+use crate::engine::selector::Selector;
+use crate::utils::{document_base_url, find_element, resolve_url};
+use reqwest::header::{HeaderMap, HeaderValue, REFERER};
+use reqwest::StatusCode;
+use url::Url;
+
+pub struct Page {
+    url: String,
+    status_code: StatusCode,
+    headers: HeaderMap,
+    text: String,
+    document: HtmlElement,
+}
+
+impl Page {
+    fn from_response(response: WappuResponse) -> Self {
+        Page {
+            url: response.url().to_string(),
+            status_code: response.status_code(),
+            headers: response.headers().clone(),
+            document: HtmlParser::new().parse_html(response.text()),
+            text: response.text().to_string(),
+        }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn status_code(&self) -> StatusCode {
+        self.status_code
+    }
+
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn document(&self) -> &HtmlElement {
+        &self.document
+    }
+
+    pub fn metadata(&self) -> PageMetadata {
+        PageMetadata::from_document(&self.document, &self.url)
+    }
+
+    // URL relative links on this page resolve against, honouring `<base href>`
+    pub fn base_url(&self) -> String {
+        document_base_url(&self.document, &self.url)
+    }
+
+    // Target of a `<meta http-equiv="refresh" content="0; url=...">` redirect, if any
+    pub fn refresh_url(&self) -> Option<String> {
+        let meta = find_element(&self.document, &|element| {
+            element.tag_name.as_deref() == Some("meta")
+                && element
+                    .attributes
+                    .get("http-equiv")
+                    .is_some_and(|value| value.eq_ignore_ascii_case("refresh"))
+        })?;
+        let content = meta.attributes.get("content")?;
+
+        // content is "<delay>; url=<target>", where the "url=" prefix and quotes are optional
+        let (_, target) = content.split_once([';', ','])?;
+        let target = target.trim();
+        let target = match target.get(..4) {
+            Some(prefix) if prefix.eq_ignore_ascii_case("url=") => target[4..].trim(),
+            _ => target,
+        };
+        let target = target.trim_matches(|c| c == '\'' || c == '"');
+        if target.is_empty() {
+            return None;
+        }
+        resolve_url(&self.base_url(), target)
+    }
+}
+
+// Browser-like navigation on top of `WappuClient`: keeps the current page and a back/forward
+// history, follows links and meta refreshes, and sends the Referer header a browser would.
+// Cookies persist across pages through the client's cookie store, which the session turns on.
+pub struct BrowserSession {
+    client: WappuClient,
+    history: Vec<Page>,
+    position: usize,
+    max_refreshes: usize,
+}
+
+impl Default for BrowserSession {
+    fn default() -> Self {
+        Self::new(WappuClient::new())
+    }
+}
+
+impl BrowserSession {
+    pub fn new(client: WappuClient) -> Self {
+        BrowserSession {
+            client: client.cookie_store(true),
+            history: Vec::new(),
+            position: 0,
+            max_refreshes: 5,
+        }
+    }
+
+    // How many `<meta http-equiv="refresh">` redirects to follow in a row, 0 disables them
+    pub fn max_refreshes(mut self, max_refreshes: usize) -> Self {
+        self.max_refreshes = max_refreshes;
+        self
+    }
+
+    pub fn client(&self) -> &WappuClient {
+        &self.client
+    }
+
+    pub fn current(&self) -> Option<&Page> {
+        self.history.get(self.position)
+    }
+
+    // URLs of the pages in the history, oldest first
+    pub fn history(&self) -> Vec<&str> {
+        self.history.iter().map(Page::url).collect()
+    }
+
+    pub fn can_go_back(&self) -> bool {
+        !self.history.is_empty() && self.position > 0
+    }
+
+    pub fn can_go_forward(&self) -> bool {
+        self.position + 1 < self.history.len()
+    }
+
+    // Loads `url`, resolved against the current page, as if typed into the address bar or
+    // reached from the current page
+    pub async fn navigate(&mut self, url: &str) -> Result<&Page, WappuError> {
+        let url = self.resolve(url)?;
+        let page = self.request(&url, None).await?;
+        Ok(self.push(page))
+    }
+
+    // Follows the link of the first element matching `selector` that has an href
+    pub async fn click(&mut self, selector: &Selector) -> Result<&Page, WappuError> {
+        let page = self.current_page()?;
+        let href = selector
+            .select(page.document())
+            .into_iter()
+            .find_map(|element| element.attributes.get("href"))
+            .ok_or_else(|| {
+                WappuError::NavigationError("No link matches the selector".to_string())
+            })?;
+        let url = resolve_url(&page.base_url(), href)
+            .ok_or_else(|| WappuError::NavigationError(format!("Invalid link: {}", href)))?;
+
+        let page = self.request(&url, None).await?;
+        Ok(self.push(page))
+    }
+
+    // Submits the first form matching `selector` with its current values, overridden by `fields`.
+    // Fields not present in the form are added as hidden fields.
+    pub async fn submit(
+        &mut self,
+        selector: &Selector,
+        fields: &[(&str, &str)],
+    ) -> Result<&Page, WappuError> {
+        let page = self.current_page()?;
+        let mut form = Form::select(page.document(), selector, page.url()).ok_or_else(|| {
+            WappuError::NavigationError("No form matches the selector".to_string())
+        })?;
+        for (name, value) in fields {
+            if form.control(name).is_some() {
+                form.set(name, value)?;
+            } else {
+                form.add(name, value);
+            }
+        }
+        self.submit_form(&form.request()).await
+    }
+
+    // Sends a form request built from the current page, e.g. with `Form::request_with`
+    pub async fn submit_form(&mut self, request: &FormRequest) -> Result<&Page, WappuError> {
+        let page = self.request(&request.url, Some(request)).await?;
+        Ok(self.push(page))
+    }
+
+    // Steps back in history without reloading the page
+    pub fn back(&mut self) -> Option<&Page> {
+        if !self.can_go_back() {
+            return None;
+        }
+        self.position -= 1;
+        self.current()
+    }
+
+    pub fn forward(&mut self) -> Option<&Page> {
+        if !self.can_go_forward() {
+            return None;
+        }
+        self.position += 1;
+        self.current()
+    }
+
+    // Fetches the current page again, replacing it in the history
+    pub async fn reload(&mut self) -> Result<&Page, WappuError> {
+        let url = self.current_page()?.url().to_string();
+        let referer = self
+            .position
+            .checked_sub(1)
+            .map(|index| self.history[index].url.clone());
+        let page = self.fetch(&url, None, referer.as_deref()).await?;
+        self.history[self.position] = page;
+        Ok(&self.history[self.position])
+    }
+
+    fn current_page(&self) -> Result<&Page, WappuError> {
+        self.current()
+            .ok_or_else(|| WappuError::NavigationError("No page has been loaded yet".to_string()))
+    }
+
+    fn resolve(&self, url: &str) -> Result<String, WappuError> {
+        match self.current() {
+            Some(page) => resolve_url(page.url(), url),
+            None => Url::parse(url).ok().map(|url| url.to_string()),
+        }
+        .ok_or_else(|| WappuError::NavigationError(format!("Invalid URL: {}", url)))
+    }
+
+    // Loads a page from the current one, then follows any meta refreshes it has
+    async fn request(&self, url: &str, form: Option<&FormRequest>) -> Result<Page, WappuError> {
+        let referer = self.current().map(|page| page.url().to_string());
+        let mut page = self.fetch(url, form, referer.as_deref()).await?;
+
+        for _ in 0..self.max_refreshes {
+            match page.refresh_url() {
+                Some(target) if target != page.url => {
+                    page = self.fetch(&target, None, Some(&page.url)).await?;
+                }
+                _ => break,
+            }
+        }
+        Ok(page)
+    }
+
+    async fn fetch(
+        &self,
+        url: &str,
+        form: Option<&FormRequest>,
+        referer: Option<&str>,
+    ) -> Result<Page, WappuError> {
+        let mut headers = HeaderMap::new();
+        if let Some(referer) = referer.and_then(|referer| referer_header(referer, url)) {
+            headers.insert(REFERER, referer);
+        }
+
+        let response = match form {
+            Some(form) => form.send(&self.client, Some(headers)).await?,
+            None => self.client.get(url, Some(headers)).await?,
+        };
+        Ok(Page::from_response(response))
+    }
+
+    // Navigating away from a page drops the pages ahead of it, like in a browser
+    fn push(&mut self, page: Page) -> &Page {
+        if !self.history.is_empty() {
+            self.history.truncate(self.position + 1);
+        }
+        self.history.push(page);
+        self.position = self.history.len() - 1;
+        &self.history[self.position]
+    }
+}
+
+// The Referer a browser sends from `referrer` to `target` with its default
+// strict-origin-when-cross-origin policy: the full URL within an origin, only the origin to
+// other origins and nothing from https to http. The fragment and credentials are never sent.
+pub(crate) fn referer_header(referrer: &str, target: &str) -> Option<HeaderValue> {
+    let mut referrer = Url::parse(referrer).ok()?;
+    let target = Url::parse(target).ok()?;
+    if !matches!(referrer.scheme(), "http" | "https")
+        || (referrer.scheme() == "https" && target.scheme() != "https")
+    {
+        return None;
+    }
+    if referrer.origin() != target.origin() {
+        return HeaderValue::from_str(&format!("{}/", referrer.origin().ascii_serialization()))
+            .ok();
+    }
+    referrer.set_fragment(None);
+    referrer.set_username("").ok()?;
+    referrer.set_password(None).ok()?;
+    HeaderValue::from_str(referrer.as_str()).ok()
+}
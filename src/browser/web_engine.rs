@@ -0,0 +1,458 @@
+use crate::engine::client::{WappuClient, WappuError};
+use crate::engine::html::{HtmlElement, HtmlParser, NodeKind};
+use boa_engine::{
+    js_string, Context, JsError, JsResult, JsString, JsValue, NativeFunction, Source,
+};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::Url;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::time::Duration;
+
+// DOM, timers, fetch and XMLHttpRequest implemented in JavaScript on top of the interpreter
+const DOM_PRELUDE: &str = include_str!("dom.js");
+
+const DEFAULT_USER_AGENT: &str =
+    "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0 Safari/537.36";
+
+#[derive(Deserialize)]
+struct PendingScript {
+    id: usize,
+    src: Option<String>,
+    #[serde(rename = "type")]
+    script_type: Option<String>,
+    text: String,
+}
+
+#[derive(Deserialize)]
+struct PendingRequest {
+    id: u64,
+    method: String,
+    url: String,
+    headers: HashMap<String, String>,
+    body: String,
+}
+
+#[derive(Debug)]
+pub struct RenderedPage {
+    pub url: String,
+    // The document as the scripts left it, ready to be queried with `Selector`
+    pub document: HtmlElement,
+    // Messages scripts wrote with console.log and friends
+    pub console: Vec<String>,
+    // Uncaught exceptions and scripts that failed to load
+    pub errors: Vec<String>,
+}
+
+// Headless, GPU-free page renderer. Runs a page's inline and external scripts with the Boa
+// JavaScript interpreter against a DOM built from the `HtmlParser` output, with `document`,
+// `window`, timers, `fetch` and `XMLHttpRequest` bindings. Every network request goes through
+// the wrapped `WappuClient`, so its proxy, user agent and cookies apply.
+//
+// Timers run on a virtual clock: a `setTimeout(fn, 5000)` fires as soon as nothing else is left
+// to do, as long as it falls within the timer budget.
+//
+// The JavaScript context is not thread safe, so the futures returned by `load` and `render`
+// are not `Send` and have to be awaited on the task that created them.
+pub struct WebEngine {
+    client: WappuClient,
+    timer_budget: Duration,
+    max_tasks: usize,
+}
+
+impl Default for WebEngine {
+    fn default() -> Self {
+        Self::new(WappuClient::new())
+    }
+}
+
+impl WebEngine {
+    pub fn new(client: WappuClient) -> Self {
+        WebEngine {
+            client,
+            timer_budget: Duration::from_secs(10),
+            max_tasks: 10_000,
+        }
+    }
+
+    // Timers scheduled further than this into the page's life never fire
+    pub fn timer_budget(mut self, timer_budget: Duration) -> Self {
+        self.timer_budget = timer_budget;
+        self
+    }
+
+    // Upper bound on scripts, requests and timers run per page, to stop runaway intervals
+    pub fn max_tasks(mut self, max_tasks: usize) -> Self {
+        self.max_tasks = max_tasks;
+        self
+    }
+
+    pub async fn load(&self, url: &str) -> Result<RenderedPage, WappuError> {
+        let response = self.client.get(url, None).await?;
+        self.render(response.text(), response.url()).await
+    }
+
+    pub async fn render(&self, html: &str, page_url: &str) -> Result<RenderedPage, WappuError> {
+        let base = Url::parse(page_url).map_err(|err| {
+            WappuError::ScriptError(format!("Invalid page URL {}: {}", page_url, err))
+        })?;
+        let document = HtmlParser::new().parse_html(html);
+
+        let mut context = Context::default();
+        context
+            .register_global_callable(
+                js_string!("__wappuParseHtml"),
+                1,
+                NativeFunction::from_fn_ptr(parse_html_fragment),
+            )
+            .map_err(script_error)?;
+        context
+            .eval(Source::from_bytes(DOM_PRELUDE))
+            .map_err(script_error)?;
+
+        let user_agent = self
+            .client
+            .current_user_agent()
+            .unwrap_or(DEFAULT_USER_AGENT);
+        let cookies = self.client.cookies_for(page_url).unwrap_or_default();
+        eval_string(
+            &mut context,
+            &format!(
+                "__wappu.load({}, {}, {}, {})",
+                js_literal(&element_to_json(&document).to_string()),
+                js_literal(&location_json(&base).to_string()),
+                js_literal(user_agent),
+                js_literal(&cookies),
+            ),
+        )?;
+
+        let doctype = document
+            .children
+            .into_iter()
+            .find(|child| matches!(child.kind, NodeKind::Doctype(_)));
+
+        self.run_scripts(&mut context, &base).await?;
+        eval_string(
+            &mut context,
+            "__wappu.fireDocumentEvent('DOMContentLoaded')",
+        )?;
+        context.run_jobs();
+        eval_string(&mut context, "__wappu.fireDocumentEvent('load')")?;
+        self.run_event_loop(&mut context, &base).await?;
+
+        let document =
+            serde_json::from_str::<Value>(&eval_string(&mut context, "__wappu.serialize()")?)
+                .map_err(|err| WappuError::ScriptError(format!("Invalid DOM snapshot: {}", err)))?;
+        let console = eval_json(&mut context, "JSON.stringify(__wappu.console)")?;
+        let errors = eval_json(&mut context, "JSON.stringify(__wappu.errors)")?;
+
+        // Scripts can't change the doctype, carry over the parsed one
+        let mut rendered = element_from_json(&document);
+        if let Some(doctype) = doctype {
+            rendered.children.insert(0, doctype);
+        }
+
+        Ok(RenderedPage {
+            url: base.to_string(),
+            document: rendered,
+            console,
+            errors,
+        })
+    }
+
+    async fn run_event_loop(&self, context: &mut Context, base: &Url) -> Result<(), WappuError> {
+        let limit = self.timer_budget.as_millis();
+        for _ in 0..self.max_tasks {
+            context.run_jobs();
+            if self.run_scripts(context, base).await? || self.run_requests(context, base).await? {
+                continue;
+            }
+            if eval_string(context, &format!("__wappu.runNextTimer({})", limit))? != "true" {
+                break;
+            }
+        }
+        context.run_jobs();
+        Ok(())
+    }
+
+    // Runs the scripts queued by the page, in document order. Returns whether there were any.
+    async fn run_scripts(&self, context: &mut Context, base: &Url) -> Result<bool, WappuError> {
+        let scripts: Vec<PendingScript> = eval_json(context, "__wappu.takeScripts()")?;
+
+        for script in &scripts {
+            if !is_javascript(script.script_type.as_deref()) {
+                continue;
+            }
+
+            let code = match &script.src {
+                Some(src) => match self.fetch_script(base, src).await {
+                    Ok(code) => code,
+                    Err(err) => {
+                        report_error(context, &format!("Failed to load script {}: {}", src, err))?;
+                        eval_string(context, &format!("__wappu.endScript({}, false)", script.id))?;
+                        continue;
+                    }
+                },
+                None => script.text.clone(),
+            };
+
+            eval_string(context, &format!("__wappu.beginScript({})", script.id))?;
+            let succeeded = match context.eval(Source::from_bytes(&code)) {
+                Ok(_) => true,
+                Err(err) => {
+                    let message = describe_error(err, context);
+                    report_error(context, &message)?;
+                    false
+                }
+            };
+            context.run_jobs();
+            eval_string(
+                context,
+                &format!("__wappu.endScript({}, {})", script.id, succeeded),
+            )?;
+        }
+
+        Ok(!scripts.is_empty())
+    }
+
+    async fn fetch_script(&self, base: &Url, src: &str) -> Result<String, WappuError> {
+        let url = base
+            .join(src)
+            .map_err(|err| WappuError::ScriptError(format!("Invalid script URL: {}", err)))?;
+        let response = self.client.get(url.as_str(), None).await?;
+        Ok(response.text().to_string())
+    }
+
+    // Performs the fetch and XMLHttpRequest calls queued by scripts. Returns whether there were any.
+    async fn run_requests(&self, context: &mut Context, base: &Url) -> Result<bool, WappuError> {
+        let requests: Vec<PendingRequest> = eval_json(context, "__wappu.takeRequests()")?;
+
+        for request in &requests {
+            let completion = match self.perform_request(request, base).await {
+                Ok(response) => format!(
+                    "__wappu.completeRequest({}, null, {})",
+                    request.id, response
+                ),
+                Err(err) => format!(
+                    "__wappu.completeRequest({}, {}, null)",
+                    request.id,
+                    js_literal(&err.to_string())
+                ),
+            };
+            eval_string(context, &completion)?;
+            context.run_jobs();
+        }
+
+        Ok(!requests.is_empty())
+    }
+
+    async fn perform_request(
+        &self,
+        request: &PendingRequest,
+        base: &Url,
+    ) -> Result<Value, WappuError> {
+        let url = base
+            .join(&request.url)
+            .map_err(|err| WappuError::ScriptError(format!("Invalid request URL: {}", err)))?;
+        let mut headers = HeaderMap::new();
+        for (name, value) in &request.headers {
+            if let (Ok(name), Ok(value)) = (
+                HeaderName::from_bytes(name.as_bytes()),
+                HeaderValue::from_str(value),
+            ) {
+                headers.insert(name, value);
+            }
+        }
+
+        let url = url.as_str();
+        let headers = Some(headers);
+        let result = match request.method.as_str() {
+            "POST" => self.client.post(url, &request.body, headers).await,
+            "PUT" => self.client.put(url, &request.body, headers).await,
+            "PATCH" => self.client.patch(url, &request.body, headers).await,
+            "DELETE" => self.client.delete(url, headers).await,
+            "HEAD" => self.client.head(url, headers).await,
+            _ => self.client.get(url, headers).await,
+        };
+
+        match result {
+            Ok(response) => {
+                let headers: HashMap<&str, &str> = response
+                    .headers()
+                    .iter()
+                    .filter_map(|(name, value)| Some((name.as_str(), value.to_str().ok()?)))
+                    .collect();
+                Ok(json!({
+                    "status": response.status_code().as_u16(),
+                    "statusText": response.status_code().canonical_reason().unwrap_or(""),
+                    "url": response.url(),
+                    "body": response.text(),
+                    "headers": headers,
+                }))
+            }
+            // Error statuses are a normal response as far as scripts are concerned
+            Err(WappuError::UnexpectedStatusCode(status, body)) => Ok(json!({
+                "status": status.as_u16(),
+                "statusText": status.canonical_reason().unwrap_or(""),
+                "url": url,
+                "body": body,
+                "headers": {},
+            })),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+fn is_javascript(script_type: Option<&str>) -> bool {
+    match script_type.map(|t| t.trim().to_ascii_lowercase()) {
+        None => true,
+        Some(t) => {
+            t.is_empty()
+                || t.contains("javascript")
+                || t.contains("ecmascript")
+                || t == "text/jscript"
+        }
+    }
+}
+
+fn script_error(err: JsError) -> WappuError {
+    WappuError::ScriptError(err.to_string())
+}
+
+fn describe_error(err: JsError, context: &mut Context) -> String {
+    match err.try_native(context) {
+        Ok(native) => native.to_string(),
+        Err(_) => err.to_string(),
+    }
+}
+
+fn report_error(context: &mut Context, message: &str) -> Result<(), WappuError> {
+    eval_string(
+        context,
+        &format!("__wappu.reportError({})", js_literal(message)),
+    )
+    .map(|_| ())
+}
+
+fn eval_string(context: &mut Context, code: &str) -> Result<String, WappuError> {
+    let value = context
+        .eval(Source::from_bytes(code))
+        .and_then(|value| value.to_string(context))
+        .map_err(script_error)?;
+    Ok(value.to_std_string_escaped())
+}
+
+fn eval_json<T: serde::de::DeserializeOwned>(
+    context: &mut Context,
+    code: &str,
+) -> Result<T, WappuError> {
+    serde_json::from_str(&eval_string(context, code)?)
+        .map_err(|err| WappuError::ScriptError(format!("Invalid engine state: {}", err)))
+}
+
+// JSON strings are valid JavaScript string literals
+fn js_literal(value: &str) -> String {
+    Value::from(value).to_string()
+}
+
+fn location_json(url: &Url) -> Value {
+    let host = match url.port() {
+        Some(port) => format!("{}:{}", url.host_str().unwrap_or(""), port),
+        None => url.host_str().unwrap_or("").to_string(),
+    };
+    json!({
+        "href": url.as_str(),
+        "protocol": format!("{}:", url.scheme()),
+        "host": host,
+        "hostname": url.host_str().unwrap_or(""),
+        "port": url.port().map(|port| port.to_string()).unwrap_or_default(),
+        "pathname": url.path(),
+        "search": url.query().map(|query| format!("?{}", query)).unwrap_or_default(),
+        "hash": url.fragment().map(|fragment| format!("#{}", fragment)).unwrap_or_default(),
+        "origin": url.origin().ascii_serialization(),
+    })
+}
+
+// Backs `innerHTML`, `outerHTML`, `insertAdjacentHTML` and `document.write`, the second
+// argument is the tag name of the element the markup is parsed into
+fn parse_html_fragment(_: &JsValue, args: &[JsValue], context: &mut Context) -> JsResult<JsValue> {
+    let mut strings = Vec::new();
+    for index in 0..2 {
+        strings.push(match args.get(index) {
+            Some(value) if !value.is_undefined() => {
+                value.to_string(context)?.to_std_string_escaped()
+            }
+            _ => String::new(),
+        });
+    }
+    let context_tag = match strings[1].as_str() {
+        "" => "body",
+        tag => tag,
+    };
+    let fragment = HtmlParser::new().parse_fragment(&strings[0], context_tag);
+
+    let nodes: Vec<Value> = fragment.children.iter().filter_map(node_to_json).collect();
+
+    Ok(JsValue::from(JsString::from(
+        Value::from(nodes).to_string().as_str(),
+    )))
+}
+
+fn element_to_json(element: &HtmlElement) -> Value {
+    let children: Vec<Value> = element.children.iter().filter_map(node_to_json).collect();
+    match &element.tag_name {
+        Some(tag_name) => json!({
+            "tag": tag_name,
+            "attributes": element.attributes,
+            "children": children,
+        }),
+        None => json!({ "children": children }),
+    }
+}
+
+// The DOM prelude has no doctype or processing instruction nodes, those are left out
+fn node_to_json(node: &HtmlElement) -> Option<Value> {
+    match node.kind {
+        NodeKind::Element => Some(element_to_json(node)),
+        NodeKind::Text if !node.text.is_empty() => Some(json!({ "text": node.text })),
+        NodeKind::Comment => Some(json!({ "comment": node.text })),
+        _ => None,
+    }
+}
+
+fn element_from_json(value: &Value) -> HtmlElement {
+    let mut text = String::new();
+    let mut children = Vec::new();
+    for child in value["children"].as_array().into_iter().flatten() {
+        match (child.get("tag"), child.get("comment").and_then(Value::as_str)) {
+            (Some(_), _) => children.push(element_from_json(child)),
+            (None, Some(comment)) => children.push(HtmlElement::new_comment(comment)),
+            (None, None) => {
+                let data = child["text"].as_str().unwrap_or("");
+                text += data;
+                children.push(HtmlElement::new_text(data));
+            }
+        }
+    }
+
+    HtmlElement {
+        tag_name: value["tag"].as_str().map(str::to_string),
+        text,
+        children,
+        attributes: value["attributes"]
+            .as_object()
+            .map(|attributes| {
+                attributes
+                    .iter()
+                    .map(|(name, value)| (name.clone(), value.as_str().unwrap_or("").to_string()))
+                    .collect()
+            })
+            .unwrap_or_default(),
+        kind: match value.get("tag") {
+            Some(_) => NodeKind::Element,
+            None => NodeKind::Document,
+        },
+        namespace: None,
+    }
+}
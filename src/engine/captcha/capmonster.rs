@@ -1,7 +1,11 @@
 use serde::{Deserialize, Serialize};
-use crate::engine::client::{WappuClient, WappuError};
+use super::detect::{detect_captcha, has_token_form, inject_token, DetectedCaptcha};
+use crate::engine::client::{ProxyConfig, WappuClient, WappuError};
+use crate::engine::html::HtmlElement;
 use std::time::Duration;
 
+const CAPMONSTER_API_URL: &str = "https://api.capmonster.cloud";
+
 #[derive(Serialize)]
 struct CreateTaskRequest {
     #[serde(rename = "clientKey")]
@@ -10,13 +14,45 @@ struct CreateTaskRequest {
 }
 
 #[derive(Serialize)]
-struct Task {
+pub(crate) struct Task {
     #[serde(rename = "type")]
     task_type: String,
     #[serde(rename = "websiteURL")]
     website_url: String,
     #[serde(rename = "websiteKey")]
     website_key: String,
+    #[serde(flatten)]
+    proxy: Option<TaskProxy>,
+    #[serde(rename = "userAgent", skip_serializing_if = "Option::is_none")]
+    user_agent: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cookies: Option<String>,
+}
+
+#[derive(Serialize)]
+struct TaskProxy {
+    #[serde(rename = "proxyType")]
+    proxy_type: String,
+    #[serde(rename = "proxyAddress")]
+    proxy_address: String,
+    #[serde(rename = "proxyPort")]
+    proxy_port: u16,
+    #[serde(rename = "proxyLogin", skip_serializing_if = "Option::is_none")]
+    proxy_login: Option<String>,
+    #[serde(rename = "proxyPassword", skip_serializing_if = "Option::is_none")]
+    proxy_password: Option<String>,
+}
+
+impl From<&ProxyConfig> for TaskProxy {
+    fn from(proxy: &ProxyConfig) -> Self {
+        TaskProxy {
+            proxy_type: proxy.proxy_type.as_str().to_string(),
+            proxy_address: proxy.address.clone(),
+            proxy_port: proxy.port,
+            proxy_login: proxy.login.clone(),
+            proxy_password: proxy.password.clone(),
+        }
+    }
 }
 
 #[derive(Deserialize, Debug)]
@@ -43,36 +79,169 @@ struct GetTaskResultResponse {
 
 #[derive(Deserialize)]
 struct Solution {
-    #[serde(rename = "gRecaptchaResponse")]
+    // Turnstile solutions carry the token under a different name
+    #[serde(rename = "gRecaptchaResponse", alias = "token")]
     g_recaptcha_response: String,
 }
 
+#[derive(Clone)]
 pub struct CaptchaClient {
     client: WappuClient,
+    api_url: String,
     api_key: String,
     task_type: String,
+    proxy: Option<ProxyConfig>,
+    user_agent: Option<String>,
+    cookies: Option<String>,
 }
 
 impl CaptchaClient {
     pub fn new(api_key: String, task_type: String) -> Self {
         CaptchaClient {
             client: WappuClient::new(),
+            api_url: CAPMONSTER_API_URL.to_string(),
             api_key,
             task_type,
+            proxy: None,
+            user_agent: None,
+            cookies: None,
         }
     }
 
+    // Base URL of a Capmonster compatible API, defaults to https://api.capmonster.cloud
+    pub fn api_url(mut self, api_url: &str) -> Self {
+        self.api_url = api_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    // Has Capmonster solve the captcha through this proxy instead of its own IPs
+    pub fn proxy(mut self, proxy: ProxyConfig) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
+
+    pub fn user_agent(mut self, user_agent: &str) -> Self {
+        self.user_agent = Some(user_agent.to_string());
+        self
+    }
+
+    // Cookies in Cookie header format, e.g. "name1=value1; name2=value2"
+    pub fn cookies(mut self, cookies: &str) -> Self {
+        self.cookies = Some(cookies.to_string());
+        self
+    }
+
     pub async fn solve_captcha(
         &self,
         website_url: String,
         website_key: String,
     ) -> Result<String, WappuError> {
-        let task = Task {
-            task_type: self.task_type.clone(),
+        let task = self.build_task(&self.task_type, website_url, website_key, None);
+        self.run_task(task).await
+    }
+
+    // Solves the captcha from the same proxy, user agent and cookies as `session`, for targets
+    // that check the token was obtained from the scraping session's IP.
+    pub async fn solve_captcha_for_session(
+        &self,
+        session: &WappuClient,
+        website_url: String,
+        website_key: String,
+    ) -> Result<String, WappuError> {
+        let task = self.build_task(&self.task_type, website_url, website_key, Some(session));
+        self.run_task(task).await
+    }
+
+    // Detects the reCAPTCHA, hCaptcha or Turnstile widget on a page, solves it and injects the
+    // token into the form holding the widget, ready to be submitted. The task type is chosen from
+    // the detected widget rather than the one this client was created with. Returns the detected
+    // captcha, or None if the page has none. Fails before creating a task when the page has no
+    // form to take the token. reCAPTCHA v3 is not detected, see `detect_captcha`.
+    pub async fn solve_page(
+        &self,
+        document: &mut HtmlElement,
+        page_url: &str,
+    ) -> Result<Option<DetectedCaptcha>, WappuError> {
+        self.solve_detected(document, page_url, None).await
+    }
+
+    // Same as `solve_page`, solving from the proxy, user agent and cookies of `session`
+    pub async fn solve_page_for_session(
+        &self,
+        session: &WappuClient,
+        document: &mut HtmlElement,
+        page_url: &str,
+    ) -> Result<Option<DetectedCaptcha>, WappuError> {
+        self.solve_detected(document, page_url, Some(session)).await
+    }
+
+    async fn solve_detected(
+        &self,
+        document: &mut HtmlElement,
+        page_url: &str,
+        session: Option<&WappuClient>,
+    ) -> Result<Option<DetectedCaptcha>, WappuError> {
+        let Some(captcha) = detect_captcha(document) else {
+            return Ok(None);
+        };
+        // Checked up front, a token nothing can be done with is still paid for
+        if !has_token_form(document) {
+            return Err(WappuError::CapmonsterError(
+                "No form found to inject the captcha token into".to_string(),
+            ));
+        }
+
+        let task = self.build_task(
+            captcha.kind.task_type(),
+            page_url.to_string(),
+            captcha.site_key.clone(),
+            session,
+        );
+        let token = self.run_task(task).await?;
+        inject_token(document, captcha.kind, &token);
+        Ok(Some(captcha))
+    }
+
+    // Settings from the session take precedence over the ones configured on this client
+    pub(crate) fn build_task(
+        &self,
+        task_type: &str,
+        website_url: String,
+        website_key: String,
+        session: Option<&WappuClient>,
+    ) -> Task {
+        let proxy = session
+            .and_then(WappuClient::proxy_config)
+            .or(self.proxy.as_ref())
+            .map(TaskProxy::from);
+        let user_agent = session
+            .and_then(WappuClient::current_user_agent)
+            .map(str::to_string)
+            .or_else(|| self.user_agent.clone());
+        let cookies = session
+            .and_then(|client| client.cookies_for(&website_url))
+            .or_else(|| self.cookies.clone());
+
+        // Capmonster names proxy-bound tasks like their proxyless variants without the suffix
+        let task_type = match proxy {
+            Some(_) => task_type
+                .strip_suffix("Proxyless")
+                .unwrap_or(task_type)
+                .to_string(),
+            None => task_type.to_string(),
+        };
+
+        Task {
+            task_type,
             website_url,
             website_key,
-        };
+            proxy,
+            user_agent,
+            cookies,
+        }
+    }
 
+    async fn run_task(&self, task: Task) -> Result<String, WappuError> {
         let create_task_request = CreateTaskRequest {
             client_key: self.api_key.clone(),
             task,
@@ -81,7 +250,7 @@ impl CaptchaClient {
         let create_task_response: CreateTaskResponse = self
             .client
             .post(
-                "https://api.capmonster.cloud/createTask",
+                &format!("{}/createTask", self.api_url),
                 &serde_json::to_string(&create_task_request)?,
                 None,
             )
@@ -107,7 +276,7 @@ impl CaptchaClient {
             let get_task_result_response: GetTaskResultResponse = self
                 .client
                 .post(
-                    "https://api.capmonster.cloud/getTaskResult",
+                    &format!("{}/getTaskResult", self.api_url),
                     &serde_json::to_string(&get_task_result_request)?,
                     None,
                 )
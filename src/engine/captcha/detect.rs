@@ -0,0 +1,157 @@
+use crate::engine::html::HtmlElement;
+use crate::utils::{find_element, find_element_mut};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaptchaKind {
+    ReCaptcha,
+    HCaptcha,
+    Turnstile,
+}
+
+impl CaptchaKind {
+    // Capmonster task type used to solve this kind of captcha without a proxy
+    pub fn task_type(&self) -> &'static str {
+        match self {
+            CaptchaKind::ReCaptcha => "NoCaptchaTaskProxyless",
+            CaptchaKind::HCaptcha => "HCaptchaTaskProxyless",
+            CaptchaKind::Turnstile => "TurnstileTaskProxyless",
+        }
+    }
+
+    // Form fields the widget fills in with the solved token
+    pub fn response_fields(&self) -> &'static [&'static str] {
+        match self {
+            CaptchaKind::ReCaptcha => &["g-recaptcha-response"],
+            CaptchaKind::HCaptcha => &["h-captcha-response", "g-recaptcha-response"],
+            CaptchaKind::Turnstile => &["cf-turnstile-response"],
+        }
+    }
+
+    fn from_widget_class(class: &str) -> Option<Self> {
+        class.split_whitespace().find_map(|class| match class {
+            "g-recaptcha" => Some(CaptchaKind::ReCaptcha),
+            "h-captcha" => Some(CaptchaKind::HCaptcha),
+            "cf-turnstile" => Some(CaptchaKind::Turnstile),
+            _ => None,
+        })
+    }
+
+    fn from_script_src(src: &str) -> Option<Self> {
+        if src.contains("google.com/recaptcha")
+            || src.contains("recaptcha.net/recaptcha")
+            || src.contains("gstatic.com/recaptcha")
+        {
+            Some(CaptchaKind::ReCaptcha)
+        } else if src.contains("hcaptcha.com") {
+            Some(CaptchaKind::HCaptcha)
+        } else if src.contains("challenges.cloudflare.com/turnstile") {
+            Some(CaptchaKind::Turnstile)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DetectedCaptcha {
+    pub kind: CaptchaKind,
+    pub site_key: String,
+}
+
+// Finds the first reCAPTCHA, hCaptcha or Turnstile widget in the document. Widgets are
+// recognised by their `data-sitekey` attribute; when the widget's class does not tell which
+// provider it belongs to, the captcha scripts loaded by the page decide.
+//
+// Invisible reCAPTCHA v3 is not detected: it has no widget element, only a script loaded with
+// `api.js?render=KEY`, and its token is requested from script with a page action this can't see
+// and posted however the page chooses. Solve it with `CaptchaClient::solve_captcha` and a
+// `RecaptchaV3TaskProxyless` client instead.
+pub fn detect_captcha(document: &HtmlElement) -> Option<DetectedCaptcha> {
+    let widget = find_element(document, &|element| {
+        element.attributes.contains_key("data-sitekey")
+    })?;
+    let site_key = widget.attributes.get("data-sitekey")?.trim().to_string();
+    if site_key.is_empty() {
+        return None;
+    }
+
+    let kind = widget
+        .attributes
+        .get("class")
+        .and_then(|class| CaptchaKind::from_widget_class(class))
+        .or_else(|| {
+            find_element(document, &|element| {
+                element.tag_name.as_deref() == Some("script")
+                    && element
+                        .attributes
+                        .get("src")
+                        .is_some_and(|src| CaptchaKind::from_script_src(src).is_some())
+            })
+            .and_then(|script| CaptchaKind::from_script_src(script.attributes.get("src")?))
+        })?;
+
+    Some(DetectedCaptcha { kind, site_key })
+}
+
+// Whether `inject_token` has a form to write a token into
+pub fn has_token_form(document: &HtmlElement) -> bool {
+    find_element(document, &is_form).is_some()
+}
+
+// Writes the solved token into the response fields of the form holding the widget, or of the
+// first form when the widget sits outside of one. Missing fields are added to the form the same
+// way the widget's script would. Returns false if the document has no form.
+pub fn inject_token(document: &mut HtmlElement, kind: CaptchaKind, token: &str) -> bool {
+    let widget_form =
+        |element: &HtmlElement| is_form(element) && find_element(element, &is_widget).is_some();
+
+    let form = if find_element(document, &widget_form).is_some() {
+        find_element_mut(document, &widget_form)
+    } else {
+        find_element_mut(document, &is_form)
+    };
+
+    let Some(form) = form else {
+        return false;
+    };
+
+    for field in kind.response_fields() {
+        set_field(form, field, token);
+    }
+    true
+}
+
+fn set_field(form: &mut HtmlElement, name: &str, value: &str) {
+    let is_field = |element: &HtmlElement| {
+        matches!(
+            element.tag_name.as_deref(),
+            Some("textarea") | Some("input")
+        ) && element.attributes.get("name").map(String::as_str) == Some(name)
+    };
+
+    match find_element_mut(form, &is_field) {
+        Some(field) if field.tag_name.as_deref() == Some("textarea") => {
+            field.set_text(value);
+        }
+        Some(field) => {
+            field
+                .attributes
+                .insert("value".to_string(), value.to_string());
+        }
+        None => {
+            let mut textarea = HtmlElement::new_element("textarea");
+            textarea.set_attribute("name", name);
+            textarea.set_attribute("style", "display: none;");
+            textarea.set_text(value);
+            form.append_child(textarea);
+        }
+    }
+}
+
+fn is_form(element: &HtmlElement) -> bool {
+    element.tag_name.as_deref() == Some("form")
+}
+
+fn is_widget(element: &HtmlElement) -> bool {
+    element.attributes.contains_key("data-sitekey")
+}
@@ -1,2 +1,4 @@
 pub mod capmonster;
+pub mod detect;
+pub mod pool;
 //will add more captcha clients here
\ No newline at end of file
@@ -0,0 +1,318 @@
+use super::capmonster::CaptchaClient;
+use crate::engine::client::WappuError;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+use tokio::sync::oneshot;
+
+// reCAPTCHA and hCaptcha tokens are accepted for about two minutes after being solved
+pub const DEFAULT_TOKEN_TTL: Duration = Duration::from_secs(110);
+
+// How long a handed out token has left at least, so it doesn't expire while the form is submitted
+pub const DEFAULT_MIN_LIFETIME: Duration = Duration::from_secs(10);
+
+// Background solving pauses after a failure, doubling up to the maximum while failures continue
+const MIN_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+
+type Site = (String, String);
+
+struct PooledToken {
+    token: String,
+    solved_at: Instant,
+}
+
+#[derive(Default)]
+struct SiteTokens {
+    tokens: VecDeque<PooledToken>,
+    solving: usize,
+    // Callers that found the pool empty, served by the next solves to finish
+    waiters: VecDeque<oneshot::Sender<Result<String, WappuError>>>,
+    // Failed solves since the last successful one
+    failures: u32,
+    retry_at: Option<Instant>,
+}
+
+impl SiteTokens {
+    fn backing_off(&self) -> bool {
+        self.retry_at
+            .is_some_and(|retry_at| Instant::now() < retry_at)
+    }
+}
+
+enum Lookup {
+    Pooled(String),
+    Waiting(oneshot::Receiver<Result<String, WappuError>>),
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PoolMetrics {
+    // Tokens handed out straight from the pool
+    pub hits: u64,
+    // Requests that found the pool empty and had to wait for a solve
+    pub misses: u64,
+    // Tokens that expired before anyone asked for them
+    pub wasted: u64,
+    pub solved: u64,
+    pub failed: u64,
+}
+
+struct PoolInner {
+    client: CaptchaClient,
+    size: usize,
+    ttl: Duration,
+    min_lifetime: Duration,
+    sites: Mutex<HashMap<Site, SiteTokens>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    wasted: AtomicU64,
+    solved: AtomicU64,
+    failed: AtomicU64,
+}
+
+// Keeps `size` tokens per (site URL, site key) solved ahead of time in the background, so
+// callers get a fresh token instantly instead of waiting 10-60 seconds for a solve.
+// Cloning the pool is cheap and shares the tokens; background solving stops once every clone
+// has been dropped.
+//
+// When solves fail, say with a bad API key or during an outage, background solving backs off
+// exponentially from 1 second to 5 minutes and then retries with a single solve until one
+// succeeds, rather than paying for `size` new tasks every time.
+#[derive(Clone)]
+pub struct TokenPool {
+    inner: Arc<PoolInner>,
+}
+
+impl TokenPool {
+    pub fn new(client: CaptchaClient, size: usize, ttl: Duration) -> Self {
+        TokenPool {
+            inner: Arc::new(PoolInner {
+                client,
+                size,
+                ttl,
+                min_lifetime: DEFAULT_MIN_LIFETIME.min(ttl / 4),
+                sites: Mutex::new(HashMap::new()),
+                hits: AtomicU64::new(0),
+                misses: AtomicU64::new(0),
+                wasted: AtomicU64::new(0),
+                solved: AtomicU64::new(0),
+                failed: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    // How long a token must still be valid for to be handed out, older ones are thrown away.
+    // Defaults to `DEFAULT_MIN_LIFETIME`, or a quarter of the TTL if that's shorter. Must be
+    // shorter than the TTL, or every token would be thrown away as soon as it's solved, and be
+    // set before the pool is cloned or used.
+    pub fn min_lifetime(mut self, min_lifetime: Duration) -> Self {
+        assert!(
+            min_lifetime < self.inner.ttl,
+            "the minimum lifetime must be shorter than the token TTL"
+        );
+        Arc::get_mut(&mut self.inner)
+            .expect("the pool is configured before it's cloned or used")
+            .min_lifetime = min_lifetime;
+        self
+    }
+
+    // Starts keeping tokens for a site before the first one is needed. Must be called from
+    // within a tokio runtime.
+    pub fn warm(&self, website_url: &str, website_key: &str) {
+        let site = (website_url.to_string(), website_key.to_string());
+        let is_new = {
+            let mut sites = self.inner.sites.lock().unwrap();
+            let is_new = !sites.contains_key(&site);
+            sites.entry(site.clone()).or_default();
+            is_new
+        };
+
+        if is_new {
+            PoolInner::refill(&self.inner, &site);
+            tokio::spawn(PoolInner::maintain(Arc::downgrade(&self.inner), site));
+        }
+    }
+
+    // Hands out a pooled token if one is still fresh, otherwise waits for the next background
+    // solve to finish. While background solving is backing off after failures, a single solve is
+    // started for the caller right away, and its outcome counts like any other solve's. Either
+    // way the site is kept warm afterwards.
+    pub async fn token(&self, website_url: &str, website_key: &str) -> Result<String, WappuError> {
+        self.warm(website_url, website_key);
+        let site = (website_url.to_string(), website_key.to_string());
+
+        let (lookup, needed) = {
+            let mut sites = self.inner.sites.lock().unwrap();
+            let tokens = sites.entry(site.clone()).or_default();
+            self.inner.discard_expired(tokens);
+            match tokens.tokens.pop_front() {
+                Some(pooled) => (Lookup::Pooled(pooled.token), self.inner.reserve(tokens)),
+                None => {
+                    let (sender, receiver) = oneshot::channel();
+                    let needed = if tokens.backing_off() && tokens.solving == 0 {
+                        // The first waiter gets the next solved token, so this caller gets
+                        // the one solved for it
+                        tokens.waiters.push_front(sender);
+                        tokens.solving += 1;
+                        1
+                    } else {
+                        tokens.waiters.push_back(sender);
+                        self.inner.reserve(tokens)
+                    };
+                    (Lookup::Waiting(receiver), needed)
+                }
+            }
+        };
+        PoolInner::spawn_solves(&self.inner, &site, needed);
+
+        match lookup {
+            Lookup::Pooled(token) => {
+                self.inner.hits.fetch_add(1, Ordering::Relaxed);
+                Ok(token)
+            }
+            Lookup::Waiting(receiver) => {
+                self.inner.misses.fetch_add(1, Ordering::Relaxed);
+                receiver.await.unwrap_or_else(|_| {
+                    Err(WappuError::CapmonsterError(
+                        "The token pool stopped before a token was solved".to_string(),
+                    ))
+                })
+            }
+        }
+    }
+
+    // Number of fresh tokens currently pooled for a site
+    pub fn available(&self, website_url: &str, website_key: &str) -> usize {
+        let site = (website_url.to_string(), website_key.to_string());
+        let mut sites = self.inner.sites.lock().unwrap();
+        sites.get_mut(&site).map_or(0, |tokens| {
+            self.inner.discard_expired(tokens);
+            tokens.tokens.len()
+        })
+    }
+
+    pub fn metrics(&self) -> PoolMetrics {
+        PoolMetrics {
+            hits: self.inner.hits.load(Ordering::Relaxed),
+            misses: self.inner.misses.load(Ordering::Relaxed),
+            wasted: self.inner.wasted.load(Ordering::Relaxed),
+            solved: self.inner.solved.load(Ordering::Relaxed),
+            failed: self.inner.failed.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl PoolInner {
+    fn discard_expired(&self, tokens: &mut SiteTokens) {
+        let before = tokens.tokens.len();
+        let usable_for = self.ttl.saturating_sub(self.min_lifetime);
+        tokens
+            .tokens
+            .retain(|pooled| pooled.solved_at.elapsed() < usable_for);
+        let expired = before - tokens.tokens.len();
+        self.wasted.fetch_add(expired as u64, Ordering::Relaxed);
+    }
+
+    // Counts the solves needed to bring the site back to `size` tokens, or to serve every waiting
+    // caller if there are more of them, and marks them as running. Waiting callers take the
+    // tokens of solves already running, and the pool is topped up again as those finish. No
+    // solves are started while backing off, and after a failure only one runs at a time until
+    // a solve succeeds.
+    fn reserve(&self, tokens: &mut SiteTokens) -> usize {
+        tokens.waiters.retain(|waiter| !waiter.is_closed());
+        if tokens.backing_off() {
+            return 0;
+        }
+        let wanted = match tokens.failures {
+            0 => self.size.max(tokens.waiters.len()),
+            _ => 1,
+        };
+        let needed = wanted.saturating_sub(tokens.tokens.len() + tokens.solving);
+        tokens.solving += needed;
+        needed
+    }
+
+    fn refill(inner: &Arc<PoolInner>, site: &Site) {
+        let needed = {
+            let mut sites = inner.sites.lock().unwrap();
+            let tokens = sites.entry(site.clone()).or_default();
+            inner.discard_expired(tokens);
+            inner.reserve(tokens)
+        };
+        PoolInner::spawn_solves(inner, site, needed);
+    }
+
+    fn spawn_solves(inner: &Arc<PoolInner>, site: &Site, count: usize) {
+        for _ in 0..count {
+            let inner = inner.clone();
+            let site = site.clone();
+            tokio::spawn(async move {
+                let result = inner
+                    .client
+                    .solve_captcha(site.0.clone(), site.1.clone())
+                    .await;
+                let needed = {
+                    let mut sites = inner.sites.lock().unwrap();
+                    let tokens = sites.entry(site.clone()).or_default();
+                    inner.finish_solve(tokens, result);
+                    inner.reserve(tokens)
+                };
+                PoolInner::spawn_solves(&inner, &site, needed);
+            });
+        }
+    }
+
+    fn finish_solve(&self, tokens: &mut SiteTokens, result: Result<String, WappuError>) {
+        tokens.solving -= 1;
+        match result {
+            Ok(token) => {
+                self.solved.fetch_add(1, Ordering::Relaxed);
+                tokens.failures = 0;
+                tokens.retry_at = None;
+                // The first caller still waiting gets the token, otherwise it's pooled
+                let mut token = Some(token);
+                while let Some(waiter) = tokens.waiters.pop_front() {
+                    match waiter.send(Ok(token.take().unwrap())) {
+                        Ok(()) => break,
+                        Err(returned) => token = returned.ok(),
+                    }
+                }
+                if let Some(token) = token {
+                    tokens.tokens.push_back(PooledToken {
+                        token,
+                        solved_at: Instant::now(),
+                    });
+                }
+            }
+            Err(error) => {
+                self.failed.fetch_add(1, Ordering::Relaxed);
+                tokens.failures += 1;
+                let backoff = MIN_BACKOFF.saturating_mul(1 << (tokens.failures - 1).min(16));
+                tokens.retry_at = Some(Instant::now() + backoff.min(MAX_BACKOFF));
+                // Nothing else is coming for the callers waiting, they get the error
+                if tokens.solving == 0 {
+                    for waiter in tokens.waiters.drain(..) {
+                        let _ = waiter.send(Err(WappuError::CapmonsterError(error.to_string())));
+                    }
+                }
+            }
+        }
+    }
+
+    // Replaces expired tokens for as long as the pool is alive
+    async fn maintain(inner: Weak<PoolInner>, site: Site) {
+        let interval = match inner.upgrade() {
+            Some(inner) => (inner.ttl / 4).min(Duration::from_secs(1)),
+            None => return,
+        };
+
+        loop {
+            tokio::time::sleep(interval).await;
+            match inner.upgrade() {
+                Some(inner) => PoolInner::refill(&inner, &site),
+                None => return,
+            }
+        }
+    }
+}
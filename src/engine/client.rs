@@ -1,16 +1,28 @@
+use crate::engine::cookies::{Cookie, CookieJar};
+use crate::engine::html::HtmlParser;
+use crate::engine::metadata::PageMetadata;
 use reqwest::{
     self,
-    header::{HeaderMap, HeaderValue, SET_COOKIE},
-    Response, StatusCode,
+    cookie::CookieStore,
+    header::{HeaderMap, HeaderValue, SET_COOKIE, USER_AGENT},
+    Response, StatusCode, Url,
 };
 use serde::de::DeserializeOwned;
-use std::{collections::HashMap, error::Error};
+use std::{collections::HashMap, error::Error, sync::Arc};
 
+// New kinds of errors come with new features, so matches need a wildcard arm
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum WappuError {
     Network(reqwest::Error),
     UnexpectedStatusCode(reqwest::StatusCode, String),
     CapmonsterError(String),
+    ScriptError(String),
+    NavigationError(String),
+    FormError(String),
+    CdpError(String),
+    ExtractionError(String),
+    FeedError(String),
 }
 
 impl std::fmt::Display for WappuError {
@@ -21,6 +33,12 @@ impl std::fmt::Display for WappuError {
                 write!(f, "Unexpected status code: {}. Response text: {}", code, text)
             }
             WappuError::CapmonsterError(ref err) => write!(f, "Capmonster error: {}", err),
+            WappuError::ScriptError(ref err) => write!(f, "Script error: {}", err),
+            WappuError::NavigationError(ref err) => write!(f, "Navigation error: {}", err),
+            WappuError::FormError(ref err) => write!(f, "Form error: {}", err),
+            WappuError::CdpError(ref err) => write!(f, "DevTools protocol error: {}", err),
+            WappuError::ExtractionError(ref err) => write!(f, "Extraction error: {}", err),
+            WappuError::FeedError(ref err) => write!(f, "Feed error: {}", err),
         }
     }
 }
@@ -45,10 +63,9 @@ macro_rules! headers {
 #[macro_export]
 macro_rules! query_params {
     ($($key:expr => $value:expr),* $(,)?) => {{
-        let mut params = Vec::new();
-        $(
-            params.push((String::from($key), String::from($value)));
-        )*
+        let params: Vec<(String, String)> = vec![
+            $((String::from($key), String::from($value)),)*
+        ];
         params
     }};
 }
@@ -61,9 +78,79 @@ impl From<reqwest::Error> for WappuError {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProxyType {
+    Http,
+    Https,
+    Socks4,
+    Socks5,
+}
+
+impl ProxyType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProxyType::Http => "http",
+            ProxyType::Https => "https",
+            ProxyType::Socks4 => "socks4",
+            ProxyType::Socks5 => "socks5",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProxyConfig {
+    pub proxy_type: ProxyType,
+    pub address: String,
+    pub port: u16,
+    pub login: Option<String>,
+    pub password: Option<String>,
+}
+
+impl ProxyConfig {
+    pub fn new(proxy_type: ProxyType, address: &str, port: u16) -> Self {
+        ProxyConfig {
+            proxy_type,
+            address: address.to_string(),
+            port,
+            login: None,
+            password: None,
+        }
+    }
+
+    pub fn credentials(mut self, login: &str, password: &str) -> Self {
+        self.login = Some(login.to_string());
+        self.password = Some(password.to_string());
+        self
+    }
+
+    fn to_reqwest(&self) -> Result<reqwest::Proxy, reqwest::Error> {
+        let proxy = reqwest::Proxy::all(format!(
+            "{}://{}:{}",
+            self.proxy_type.as_str(),
+            self.address,
+            self.port
+        ))?;
+        Ok(match (&self.login, &self.password) {
+            (Some(login), Some(password)) => proxy.basic_auth(login, password),
+            _ => proxy,
+        })
+    }
+}
+
+#[derive(Clone)]
 pub struct WappuClient {
     client: reqwest::Client,
     query_params: Vec<(String, String)>,
+    proxy: Option<ProxyConfig>,
+    user_agent: Option<String>,
+    // Only kept when the cookie store is turned on with `cookie_store(true)`
+    cookie_jar: Option<Arc<CookieJar>>,
+}
+
+impl Default for WappuClient {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl WappuClient {
@@ -71,6 +158,9 @@ impl WappuClient {
         WappuClient {
             client: reqwest::Client::new(),
             query_params: Vec::new(),
+            proxy: None,
+            user_agent: None,
+            cookie_jar: None,
         }
     }
 
@@ -79,6 +169,95 @@ impl WappuClient {
         self
     }
 
+    // Routes every request through the given proxy. Fails if reqwest does not support the
+    // proxy type (socks proxies need reqwest's `socks` feature).
+    pub fn proxy(mut self, proxy: ProxyConfig) -> Result<Self, WappuError> {
+        self.client = Self::build_client(Some(&proxy), self.cookie_jar.as_ref())?;
+        self.proxy = Some(proxy);
+        Ok(self)
+    }
+
+    // User agent sent with every request unless the request headers already set one
+    pub fn user_agent(mut self, user_agent: &str) -> Self {
+        self.user_agent = Some(user_agent.to_string());
+        self
+    }
+
+    // Stores the cookies responses set and sends them back with later requests, like a browser.
+    // Off by default, so requests carry only the headers they're given. Clones of the client
+    // share one store; turning it off drops the stored cookies.
+    pub fn cookie_store(mut self, enabled: bool) -> Self {
+        if enabled == self.cookie_jar.is_some() {
+            return self;
+        }
+        self.cookie_jar = enabled.then(|| Arc::new(CookieJar::default()));
+        self.client = Self::build_client(self.proxy.as_ref(), self.cookie_jar.as_ref())
+            .expect("the proxy was accepted when it was set");
+        self
+    }
+
+    pub fn has_cookie_store(&self) -> bool {
+        self.cookie_jar.is_some()
+    }
+
+    // Returns the cookies this client would send to `url`, formatted as a Cookie header value.
+    // None without a cookie store.
+    pub fn cookies_for(&self, url: &str) -> Option<String> {
+        let url = Url::parse(url).ok()?;
+        self.cookie_jar
+            .as_ref()?
+            .cookies(&url)
+            .and_then(|value| value.to_str().ok().map(str::to_string))
+    }
+
+    // Stores a cookie, given as a Set-Cookie header value, as if `url` had set it. Does nothing
+    // without a cookie store.
+    pub fn add_cookie(&self, cookie: &str, url: &str) {
+        if let (Some(cookie_jar), Ok(url)) = (&self.cookie_jar, Url::parse(url)) {
+            cookie_jar.add_cookie_str(cookie, &url);
+        }
+    }
+
+    // Returns the cookies this client would send to `url` with all their attributes. Empty
+    // without a cookie store.
+    pub fn cookies(&self, url: &str) -> Vec<Cookie> {
+        match (&self.cookie_jar, Url::parse(url)) {
+            (Some(cookie_jar), Ok(url)) => cookie_jar.cookies_for(&url),
+            _ => Vec::new(),
+        }
+    }
+
+    // Stores `cookie` as if `url` had set it, keeping its attributes. Does nothing without a
+    // cookie store.
+    pub fn set_cookie(&self, cookie: &Cookie, url: &str) {
+        if let (Some(cookie_jar), Ok(url)) = (&self.cookie_jar, Url::parse(url)) {
+            cookie_jar.add_cookie(cookie, &url);
+        }
+    }
+
+    pub fn proxy_config(&self) -> Option<&ProxyConfig> {
+        self.proxy.as_ref()
+    }
+
+    pub fn current_user_agent(&self) -> Option<&str> {
+        self.user_agent.as_deref()
+    }
+
+    fn build_client(
+        proxy: Option<&ProxyConfig>,
+        cookie_jar: Option<&Arc<CookieJar>>,
+    ) -> Result<reqwest::Client, reqwest::Error> {
+        let builder = match cookie_jar {
+            Some(cookie_jar) => reqwest::Client::builder().cookie_provider(cookie_jar.clone()),
+            None => reqwest::Client::builder(),
+        };
+        let builder = match proxy {
+            Some(proxy) => builder.proxy(proxy.to_reqwest()?),
+            None => builder,
+        };
+        builder.build()
+    }
+
     pub async fn get(
         &self,
         url: &str,
@@ -114,6 +293,25 @@ impl WappuClient {
         WappuResponse::from_response(response).await
     }
 
+    // Like `post`, for bodies that aren't text such as multipart uploads
+    pub async fn post_bytes(
+        &self,
+        url: &str,
+        body: Vec<u8>,
+        headers: Option<HeaderMap>,
+    ) -> Result<WappuResponse, WappuError> {
+        let request = self.client.post(url).body(body);
+
+        let request = if !self.query_params.is_empty() {
+            request.query(&self.query_params)
+        } else {
+            request
+        };
+
+        let response = self.send_request(request, headers).await?;
+        WappuResponse::from_response(response).await
+    }
+
     pub async fn put(
         &self,
         url: &str,
@@ -184,15 +382,37 @@ impl WappuClient {
         WappuResponse::from_response(response).await
     }
 
+    // Like `get`, but hands back the body chunk by chunk as it arrives instead of buffering it
+    pub async fn get_stream(
+        &self,
+        url: &str,
+        headers: Option<HeaderMap>,
+    ) -> Result<WappuStream, WappuError> {
+        let request = self.client.get(url);
+
+        let request = if !self.query_params.is_empty() {
+            request.query(&self.query_params)
+        } else {
+            request
+        };
+
+        let response = self.send_request(request, headers).await?;
+        Ok(WappuStream { response })
+    }
+
     async fn send_request(
         &self,
         request: reqwest::RequestBuilder,
         headers: Option<HeaderMap>,
     ) -> Result<Response, WappuError> {
         let mut request = request;
+        let sets_user_agent = headers.as_ref().is_some_and(|h| h.contains_key(USER_AGENT));
         if let Some(h) = headers {
             request = request.headers(h);
         }
+        if let (false, Some(user_agent)) = (sets_user_agent, &self.user_agent) {
+            request = request.header(USER_AGENT, user_agent);
+        }
         let response = request.send().await?;
         if !response.status().is_success() {
             let status_code = response.status();
@@ -203,7 +423,33 @@ impl WappuClient {
     }
 }
 
+// A response whose body hasn't been read yet, see `WappuClient::get_stream`
+pub struct WappuStream {
+    response: Response,
+}
+
+impl WappuStream {
+    pub fn url(&self) -> &str {
+        self.response.url().as_str()
+    }
+
+    pub fn headers(&self) -> &HeaderMap {
+        self.response.headers()
+    }
+
+    pub fn status_code(&self) -> StatusCode {
+        self.response.status()
+    }
+
+    // The next chunk of the body, None once it has all been read
+    pub async fn chunk(&mut self) -> Result<Option<Vec<u8>>, WappuError> {
+        let chunk = self.response.chunk().await?;
+        Ok(chunk.map(|bytes| bytes.to_vec()))
+    }
+}
+
 pub struct WappuResponse {
+    url: String,
     text: String,
     headers: HeaderMap,
     status_code: StatusCode,
@@ -213,6 +459,7 @@ pub struct WappuResponse {
 impl WappuResponse {
     // Creates a new WappuResponse from a reqwest::Response, fetching text, headers, and cookies asynchronously
     async fn from_response(response: Response) -> Result<Self, WappuError> {
+        let url = response.url().to_string();
         let status_code = response.status();
         let headers = response.headers().clone(); // Clone headers before consuming response
 
@@ -220,12 +467,13 @@ impl WappuResponse {
         let cookies = headers
             .get_all(SET_COOKIE)
             .iter()
-            .filter_map(|header_value| parse_cookie(header_value))
+            .filter_map(parse_cookie)
             .collect();
 
         let body_text = response.text().await.map_err(WappuError::from)?;
 
         Ok(WappuResponse {
+            url,
             text: body_text,
             headers,
             status_code,
@@ -233,6 +481,11 @@ impl WappuResponse {
         })
     }
 
+    // Final URL of the response, after any redirects
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
     // Method to get the response text without consuming the response
     pub fn text(&self) -> &str {
         &self.text
@@ -256,6 +509,12 @@ impl WappuResponse {
     pub async fn json<T: DeserializeOwned>(&self) -> Result<T, WappuError> {
         serde_json::from_str(&self.text).map_err(WappuError::from)
     }
+
+    // Title, description, OpenGraph and Twitter card fields and link relations of an HTML
+    // response, with URLs resolved against the final URL
+    pub fn metadata(&self) -> PageMetadata {
+        PageMetadata::from_document(&HtmlParser::new().parse_html(&self.text), &self.url)
+    }
 }
 
 // Utility function to parse a cookie from a Set-Cookie header value
@@ -0,0 +1,151 @@
+use reqwest::header::HeaderValue;
+use reqwest::Url;
+use std::sync::RwLock;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use time::OffsetDateTime;
+
+// Whether a cookie goes along with requests started from other sites
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SameSite {
+    Strict,
+    Lax,
+    None,
+}
+
+// A stored cookie with the attributes that decide where it is sent
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cookie {
+    pub name: String,
+    pub value: String,
+    // The host a host-only cookie was set by, or the domain a domain cookie was set for,
+    // without a leading dot
+    pub domain: String,
+    // Host-only cookies are sent to their exact host only, not to its subdomains
+    pub host_only: bool,
+    pub path: String,
+    pub secure: bool,
+    pub http_only: bool,
+    // None for session cookies
+    pub expires: Option<SystemTime>,
+    // None when the cookie didn't set the attribute
+    pub same_site: Option<SameSite>,
+}
+
+impl Cookie {
+    fn to_raw(&self) -> cookie_store::RawCookie<'static> {
+        let mut builder = cookie_store::RawCookie::build(self.name.clone(), self.value.clone())
+            .path(self.path.clone())
+            .secure(self.secure)
+            .http_only(self.http_only);
+        // Without a Domain attribute the store makes the cookie host-only
+        if !self.host_only {
+            builder = builder.domain(self.domain.clone());
+        }
+        if let Some(same_site) = self.same_site {
+            builder = builder.same_site(match same_site {
+                SameSite::Strict => cookie::SameSite::Strict,
+                SameSite::Lax => cookie::SameSite::Lax,
+                SameSite::None => cookie::SameSite::None,
+            });
+        }
+        if let Some(expires) = self.expires {
+            let nanos = match expires.duration_since(UNIX_EPOCH) {
+                Ok(since) => since.as_nanos() as i128,
+                Err(_) => 0,
+            };
+            if let Ok(expires) = OffsetDateTime::from_unix_timestamp_nanos(nanos) {
+                builder = builder.expires(expires);
+            }
+        }
+        builder.finish()
+    }
+
+    fn from_stored(cookie: &cookie_store::Cookie) -> Option<Self> {
+        let (domain, host_only) = match &cookie.domain {
+            cookie_store::CookieDomain::HostOnly(host) => (host.clone(), true),
+            cookie_store::CookieDomain::Suffix(suffix) => (suffix.clone(), false),
+            _ => return None,
+        };
+        let expires = match &cookie.expires {
+            cookie_store::CookieExpiration::AtUtc(at) => {
+                let nanos = at.unix_timestamp_nanos().max(0) as u64;
+                Some(UNIX_EPOCH + Duration::from_nanos(nanos))
+            }
+            cookie_store::CookieExpiration::SessionEnd => None,
+        };
+        Some(Cookie {
+            name: cookie.name().to_string(),
+            value: cookie.value().to_string(),
+            domain,
+            host_only,
+            path: String::from(&cookie.path),
+            secure: cookie.secure().unwrap_or(false),
+            http_only: cookie.http_only().unwrap_or(false),
+            expires,
+            same_site: cookie.same_site().map(|same_site| match same_site {
+                cookie::SameSite::Strict => SameSite::Strict,
+                cookie::SameSite::Lax => SameSite::Lax,
+                cookie::SameSite::None => SameSite::None,
+            }),
+        })
+    }
+}
+
+// The cookie store behind `WappuClient::cookie_store`. Unlike reqwest's own jar it can hand
+// back whole cookies, so they can be passed on to a browser without losing their attributes.
+#[derive(Debug, Default)]
+pub struct CookieJar {
+    store: RwLock<cookie_store::CookieStore>,
+}
+
+impl CookieJar {
+    // Stores a cookie, given as a Set-Cookie header value, as if `url` had set it
+    pub fn add_cookie_str(&self, cookie: &str, url: &Url) {
+        let _ = self.store.write().unwrap().parse(cookie, url);
+    }
+
+    pub fn add_cookie(&self, cookie: &Cookie, url: &Url) {
+        let _ = self
+            .store
+            .write()
+            .unwrap()
+            .insert_raw(&cookie.to_raw(), url);
+    }
+
+    // The unexpired cookies that would be sent to `url`
+    pub fn cookies_for(&self, url: &Url) -> Vec<Cookie> {
+        self.store
+            .read()
+            .unwrap()
+            .matches(url)
+            .into_iter()
+            .filter_map(|cookie| Cookie::from_stored(cookie))
+            .collect()
+    }
+}
+
+impl reqwest::cookie::CookieStore for CookieJar {
+    fn set_cookies(&self, headers: &mut dyn Iterator<Item = &HeaderValue>, url: &Url) {
+        let mut store = self.store.write().unwrap();
+        for header in headers {
+            if let Ok(cookie) = header.to_str() {
+                let _ = store.parse(cookie, url);
+            }
+        }
+    }
+
+    fn cookies(&self, url: &Url) -> Option<HeaderValue> {
+        let header = self
+            .store
+            .read()
+            .unwrap()
+            .get_request_values(url)
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect::<Vec<_>>()
+            .join("; ");
+        if header.is_empty() {
+            return None;
+        }
+        HeaderValue::from_str(&header).ok()
+    }
+}
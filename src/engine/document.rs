@@ -0,0 +1,602 @@
+use crate::engine::html::{Doctype, HtmlElement, NodeKind};
+use crate::engine::selector::{Selection, Selector};
+use html5ever::{local_name, parse_document, tendril::TendrilSink, LocalName, Namespace};
+use markup5ever_rcdom::{Handle, NodeData, RcDom};
+use smallvec::SmallVec;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::Arc;
+
+// A compact, read-only alternative to the `HtmlElement` tree for large pages. Nodes live in one
+// vector in document order, so a node's subtree is the run of nodes after it and selecting is a
+// linear scan. Tag and attribute names are interned atoms shared with the parser, attributes are
+// kept inline, and all text, comment and attribute values are slices of one shared buffer.
+// `to_element` converts to an `HtmlElement` for the APIs that need one. A document holds at most
+// `u32::MAX` nodes and bytes of text, building a bigger one panics.
+//
+// The nodes are behind an `Arc`, so a document is `Send + Sync` and cloning it is cheap: parse
+// once and hand clones to tasks or threads. `NodeHandle` is an owned node that keeps its document
+// alive, for results that have to outlive the borrow they were selected from.
+#[derive(Debug, Clone)]
+pub struct Document {
+    arena: Arc<Arena>,
+}
+
+#[derive(Debug)]
+struct Arena {
+    nodes: Vec<Node>,
+    text: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NodeId(u32);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeType {
+    Document,
+    Fragment,
+    Doctype,
+    Element,
+    Text,
+    Comment,
+    ProcessingInstruction,
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    parent: Option<NodeId>,
+    // One past the last node of the subtree
+    end: u32,
+    data: Data,
+}
+
+#[derive(Debug, Clone)]
+enum Data {
+    Document,
+    Fragment,
+    Doctype {
+        name: Span,
+        public_id: Span,
+        system_id: Span,
+    },
+    Element {
+        name: LocalName,
+        // Empty except for elements converted from `XmlParser` trees, as with `HtmlElement`
+        namespace: Namespace,
+        attributes: SmallVec<[Attribute; 2]>,
+    },
+    Text(Span),
+    Comment(Span),
+    ProcessingInstruction {
+        target: Span,
+        data: Span,
+    },
+}
+
+#[derive(Debug, Clone)]
+struct Attribute {
+    name: LocalName,
+    value: Span,
+}
+
+// A slice of the document's text buffer
+#[derive(Debug, Clone, Copy)]
+struct Span {
+    start: u32,
+    len: u32,
+}
+
+impl Document {
+    pub fn parse_html(input: &str) -> Self {
+        let dom = parse_document(RcDom::default(), Default::default()).one(input);
+        let mut builder = Builder::default();
+        builder.add_dom(&dom.document, None);
+        builder.finish()
+    }
+
+    pub fn from_element(element: &HtmlElement) -> Self {
+        let mut builder = Builder::default();
+        builder.add_element(element, None);
+        builder.finish()
+    }
+
+    pub fn root(&self) -> NodeRef<'_> {
+        NodeRef {
+            document: self,
+            id: NodeId(0),
+        }
+    }
+
+    pub fn get(&self, id: NodeId) -> Option<NodeRef<'_>> {
+        if id.0 as usize >= self.len() {
+            return None;
+        }
+        Some(NodeRef { document: self, id })
+    }
+
+    // Number of nodes, elements and otherwise
+    pub fn len(&self) -> usize {
+        self.arena.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.arena.nodes.is_empty()
+    }
+
+    pub fn select(&self, selector: &Selector) -> Vec<NodeRef<'_>> {
+        self.root().select(selector)
+    }
+
+    pub fn to_element(&self) -> HtmlElement {
+        self.root().to_element()
+    }
+
+    fn node(&self, id: NodeId) -> &Node {
+        &self.arena.nodes[id.0 as usize]
+    }
+
+    fn str(&self, span: Span) -> &str {
+        &self.arena.text[span.start as usize..(span.start + span.len) as usize]
+    }
+}
+
+// A node of a `Document`, cheap to copy around
+#[derive(Clone, Copy)]
+pub struct NodeRef<'a> {
+    document: &'a Document,
+    id: NodeId,
+}
+
+impl<'a> NodeRef<'a> {
+    pub fn id(&self) -> NodeId {
+        self.id
+    }
+
+    // An owned handle to this node, which can be kept or sent elsewhere after the borrow ends
+    pub fn to_handle(&self) -> NodeHandle {
+        NodeHandle {
+            document: self.document.clone(),
+            id: self.id,
+        }
+    }
+
+    pub fn node_type(&self) -> NodeType {
+        match self.data() {
+            Data::Document => NodeType::Document,
+            Data::Fragment => NodeType::Fragment,
+            Data::Doctype { .. } => NodeType::Doctype,
+            Data::Element { .. } => NodeType::Element,
+            Data::Text(_) => NodeType::Text,
+            Data::Comment(_) => NodeType::Comment,
+            Data::ProcessingInstruction { .. } => NodeType::ProcessingInstruction,
+        }
+    }
+
+    pub fn is_element(&self) -> bool {
+        matches!(self.data(), Data::Element { .. })
+    }
+
+    pub fn tag_name(&self) -> Option<&'a str> {
+        match self.data() {
+            Data::Element { name, .. } => Some(name),
+            _ => None,
+        }
+    }
+
+    pub fn namespace(&self) -> Option<&'a str> {
+        match self.data() {
+            Data::Element { namespace, .. } if !namespace.is_empty() => Some(namespace),
+            _ => None,
+        }
+    }
+
+    pub fn attribute(&self, name: &str) -> Option<&'a str> {
+        self.attributes()
+            .find(|(attribute, _)| *attribute == name)
+            .map(|(_, value)| value)
+    }
+
+    pub fn attributes(&self) -> impl Iterator<Item = (&'a str, &'a str)> + 'a {
+        let document = self.document;
+        let attributes: &'a [Attribute] = match self.data() {
+            Data::Element { attributes, .. } => attributes,
+            _ => &[],
+        };
+        attributes
+            .iter()
+            .map(move |attribute| (&*attribute.name, document.str(attribute.value)))
+    }
+
+    // The text of a text node, the contents of a comment or the data of a processing instruction
+    pub fn value(&self) -> Option<&'a str> {
+        match *self.data() {
+            Data::Text(span) | Data::Comment(span) => Some(self.document.str(span)),
+            Data::ProcessingInstruction { data, .. } => Some(self.document.str(data)),
+            _ => None,
+        }
+    }
+
+    // The joined text of the node's text children, like `HtmlElement::text`
+    pub fn text(&self) -> String {
+        if let Some(value) = self.value() {
+            return value.to_string();
+        }
+        self.children()
+            .filter(|child| child.node_type() == NodeType::Text)
+            .filter_map(|child| child.value())
+            .collect()
+    }
+
+    // The text of every text node in the subtree, like `textContent`
+    pub fn text_content(&self) -> String {
+        self.descendants()
+            .filter(|node| node.node_type() == NodeType::Text)
+            .filter_map(|node| node.value())
+            .collect()
+    }
+
+    pub fn parent(&self) -> Option<NodeRef<'a>> {
+        self.document.node(self.id).parent.map(|id| self.at(id))
+    }
+
+    pub fn children(&self) -> Children<'a> {
+        Children {
+            document: self.document,
+            next: self.id.0 + 1,
+            end: self.document.node(self.id).end,
+        }
+    }
+
+    // The nodes below this one in document order, not including itself
+    pub fn descendants(&self) -> impl Iterator<Item = NodeRef<'a>> + 'a {
+        let document = self.document;
+        self.subtree().skip(1).map(move |index| NodeRef {
+            document,
+            id: NodeId(index),
+        })
+    }
+
+    // The node itself and every element below it matching the selector, like `Selector::select`
+    pub fn select(&self, selector: &Selector) -> Vec<NodeRef<'a>> {
+        let matcher = Matcher::new(selector);
+        let document = self.document;
+        self.subtree()
+            .filter(|&index| matcher.matches(document, document.node(NodeId(index))))
+            .map(|index| NodeRef {
+                document,
+                id: NodeId(index),
+            })
+            .collect()
+    }
+
+    pub fn to_element(&self) -> HtmlElement {
+        let document = self.document;
+        let owned = |span| document.str(span).to_string();
+        let leaf = |kind, text| {
+            let mut element = HtmlElement::new_text(text);
+            element.kind = kind;
+            element
+        };
+        match self.data() {
+            Data::Text(span) => HtmlElement::new_text(document.str(*span)),
+            Data::Comment(span) => HtmlElement::new_comment(document.str(*span)),
+            Data::ProcessingInstruction { target, data } => leaf(
+                NodeKind::ProcessingInstruction {
+                    target: owned(*target),
+                },
+                document.str(*data),
+            ),
+            Data::Doctype {
+                name,
+                public_id,
+                system_id,
+            } => leaf(
+                NodeKind::Doctype(Doctype {
+                    name: owned(*name),
+                    public_id: owned(*public_id),
+                    system_id: owned(*system_id),
+                }),
+                "",
+            ),
+            Data::Document | Data::Fragment | Data::Element { .. } => {
+                let mut element = match self.data() {
+                    Data::Document => leaf(NodeKind::Document, ""),
+                    Data::Fragment => leaf(NodeKind::Fragment, ""),
+                    _ => HtmlElement::new_element(self.tag_name().unwrap_or_default()),
+                };
+                element.namespace = self.namespace().map(str::to_string);
+                element.attributes = self
+                    .attributes()
+                    .map(|(name, value)| (name.to_string(), value.to_string()))
+                    .collect::<HashMap<_, _>>();
+                element.children = self.children().map(|child| child.to_element()).collect();
+                element.text = self.text();
+                element
+            }
+        }
+    }
+
+    fn data(&self) -> &'a Data {
+        &self.document.node(self.id).data
+    }
+
+    fn at(&self, id: NodeId) -> NodeRef<'a> {
+        NodeRef {
+            document: self.document,
+            id,
+        }
+    }
+
+    fn subtree(&self) -> Range<u32> {
+        self.id.0..self.document.node(self.id).end
+    }
+}
+
+impl std::fmt::Debug for NodeRef<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NodeRef")
+            .field("id", &self.id)
+            .field("node_type", &self.node_type())
+            .field("tag_name", &self.tag_name())
+            .finish()
+    }
+}
+
+// A node that owns a clone of its document, so it's `'static` and can be moved between tasks
+#[derive(Debug, Clone)]
+pub struct NodeHandle {
+    document: Document,
+    id: NodeId,
+}
+
+impl NodeHandle {
+    pub fn id(&self) -> NodeId {
+        self.id
+    }
+
+    pub fn document(&self) -> &Document {
+        &self.document
+    }
+
+    // The node, borrowed from the handle's document
+    pub fn node(&self) -> NodeRef<'_> {
+        NodeRef {
+            document: &self.document,
+            id: self.id,
+        }
+    }
+}
+
+impl Selection<'_> {
+    // Owned handles to the selected elements, for results that have to outlive `root`, the element
+    // they were selected from. `root` is converted into one `Document` the handles share; elements
+    // from outside of it are left out. To select repeatedly, convert once with
+    // `Document::from_element` and select from it instead.
+    pub fn to_handles(&self, root: &HtmlElement) -> Vec<NodeHandle> {
+        let document = Document::from_element(root);
+        // Documents are built in the tree's pre-order, so a node's id is its position in it
+        let mut ids = HashMap::new();
+        index(root, &mut ids);
+        self.elements
+            .iter()
+            .filter_map(|element| ids.get(&(*element as *const HtmlElement)))
+            .map(|&id| NodeHandle {
+                document: document.clone(),
+                id,
+            })
+            .collect()
+    }
+}
+
+fn index(element: &HtmlElement, ids: &mut HashMap<*const HtmlElement, NodeId>) {
+    ids.insert(element, NodeId(offset(ids.len(), "nodes")));
+    for child in &element.children {
+        index(child, ids);
+    }
+}
+
+pub struct Children<'a> {
+    document: &'a Document,
+    next: u32,
+    end: u32,
+}
+
+impl<'a> Iterator for Children<'a> {
+    type Item = NodeRef<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next >= self.end {
+            return None;
+        }
+        let id = NodeId(self.next);
+        // The next sibling starts where this child's subtree ends
+        self.next = self.document.node(id).end;
+        Some(NodeRef {
+            document: self.document,
+            id,
+        })
+    }
+}
+
+// A selector with its names interned once, so matching compares atoms instead of strings
+struct Matcher<'s> {
+    tag_name: Option<LocalName>,
+    class_name: Option<&'s str>,
+    id: Option<&'s str>,
+    namespaced_name: Option<(Namespace, LocalName)>,
+}
+
+impl<'s> Matcher<'s> {
+    fn new(selector: &'s Selector) -> Self {
+        Matcher {
+            tag_name: selector.tag_name.as_deref().map(LocalName::from),
+            class_name: selector.class_name.as_deref(),
+            id: selector.id.as_deref(),
+            namespaced_name: selector
+                .namespaced_name
+                .as_ref()
+                .map(|(namespace, local_name)| {
+                    (
+                        Namespace::from(namespace.as_str()),
+                        LocalName::from(local_name.as_str()),
+                    )
+                }),
+        }
+    }
+
+    fn matches(&self, document: &Document, node: &Node) -> bool {
+        let Data::Element {
+            name,
+            namespace,
+            attributes,
+        } = &node.data
+        else {
+            return false;
+        };
+        let attribute = |wanted: &LocalName| {
+            attributes
+                .iter()
+                .find(|attribute| attribute.name == *wanted)
+                .map(|attribute| document.str(attribute.value))
+        };
+
+        self.tag_name.as_ref() == Some(name)
+            || self.class_name.is_some_and(|class_name| {
+                attribute(&local_name!("class"))
+                    .is_some_and(|classes| classes.split_whitespace().any(|c| c == class_name))
+            })
+            || self
+                .id
+                .is_some_and(|id| attribute(&local_name!("id")) == Some(id))
+            || self
+                .namespaced_name
+                .as_ref()
+                .is_some_and(|(wanted_namespace, local_name)| {
+                    wanted_namespace == namespace && name.rsplit(':').next() == Some(&**local_name)
+                })
+    }
+}
+
+// Node ids and text offsets are stored as u32 to keep nodes small, a document past that size
+// can't be represented
+fn offset(value: usize, what: &str) -> u32 {
+    u32::try_from(value).unwrap_or_else(|_| panic!("Document has more than u32::MAX {}", what))
+}
+
+#[derive(Default)]
+struct Builder {
+    nodes: Vec<Node>,
+    text: String,
+}
+
+impl Builder {
+    fn span(&mut self, text: &str) -> Span {
+        let end = offset(self.text.len() + text.len(), "bytes of text");
+        let len = offset(text.len(), "bytes of text");
+        self.text.push_str(text);
+        Span {
+            start: end - len,
+            len,
+        }
+    }
+
+    // Adds a node and returns its id, its subtree's end is set once its children are in
+    fn push(&mut self, parent: Option<NodeId>, data: Data) -> NodeId {
+        let end = offset(self.nodes.len() + 1, "nodes");
+        let id = NodeId(end - 1);
+        self.nodes.push(Node { parent, end, data });
+        id
+    }
+
+    fn close(&mut self, id: NodeId) {
+        self.nodes[id.0 as usize].end = offset(self.nodes.len(), "nodes");
+    }
+
+    fn add_dom(&mut self, handle: &Handle, parent: Option<NodeId>) {
+        let data = match &handle.data {
+            NodeData::Document => Data::Document,
+            NodeData::Doctype {
+                name,
+                public_id,
+                system_id,
+            } => Data::Doctype {
+                name: self.span(name),
+                public_id: self.span(public_id),
+                system_id: self.span(system_id),
+            },
+            NodeData::Element { name, attrs, .. } => Data::Element {
+                name: name.local.clone(),
+                namespace: Namespace::from(""),
+                attributes: attrs
+                    .borrow()
+                    .iter()
+                    .map(|attribute| Attribute {
+                        name: attribute.name.local.clone(),
+                        value: self.span(&attribute.value),
+                    })
+                    .collect(),
+            },
+            NodeData::Text { contents } => Data::Text(self.span(&contents.borrow())),
+            NodeData::Comment { contents } => Data::Comment(self.span(contents)),
+            NodeData::ProcessingInstruction { target, contents } => Data::ProcessingInstruction {
+                target: self.span(target),
+                data: self.span(contents),
+            },
+        };
+        let id = self.push(parent, data);
+        for child in handle.children.borrow().iter() {
+            self.add_dom(child, Some(id));
+        }
+        self.close(id);
+    }
+
+    fn add_element(&mut self, element: &HtmlElement, parent: Option<NodeId>) {
+        let data = match &element.kind {
+            NodeKind::Document => Data::Document,
+            NodeKind::Fragment => Data::Fragment,
+            NodeKind::Element => {
+                let mut attributes: Vec<_> = element.attributes.iter().collect();
+                // HashMap order isn't meaningful, sorting keeps conversions repeatable
+                attributes.sort();
+                Data::Element {
+                    name: LocalName::from(element.tag_name.as_deref().unwrap_or_default()),
+                    namespace: Namespace::from(element.namespace.as_deref().unwrap_or_default()),
+                    attributes: attributes
+                        .into_iter()
+                        .map(|(name, value)| Attribute {
+                            name: LocalName::from(name.as_str()),
+                            value: self.span(value),
+                        })
+                        .collect(),
+                }
+            }
+            NodeKind::Text => Data::Text(self.span(&element.text)),
+            NodeKind::Comment => Data::Comment(self.span(&element.text)),
+            NodeKind::Doctype(doctype) => Data::Doctype {
+                name: self.span(&doctype.name),
+                public_id: self.span(&doctype.public_id),
+                system_id: self.span(&doctype.system_id),
+            },
+            NodeKind::ProcessingInstruction { target } => Data::ProcessingInstruction {
+                target: self.span(target),
+                data: self.span(&element.text),
+            },
+        };
+        let id = self.push(parent, data);
+        for child in &element.children {
+            self.add_element(child, Some(id));
+        }
+        self.close(id);
+    }
+
+    fn finish(mut self) -> Document {
+        self.nodes.shrink_to_fit();
+        self.text.shrink_to_fit();
+        Document {
+            arena: Arc::new(Arena {
+                nodes: self.nodes,
+                text: self.text,
+            }),
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use crate::engine::html::{HtmlElement, NodeKind};
+use crate::engine::selector::Selector;
+
+// Tree editing. The operations keep `text` in step with the element's text node children, so
+// code reading `text` and the serializer see the same content afterwards.
+impl HtmlElement {
+    pub fn set_attribute(&mut self, name: &str, value: &str) {
+        self.attributes.insert(name.to_string(), value.to_string());
+    }
+
+    pub fn remove_attribute(&mut self, name: &str) -> Option<String> {
+        self.attributes.remove(name)
+    }
+
+    // Replaces everything inside the element with a single text node, like setting
+    // `textContent` in a browser
+    pub fn set_text(&mut self, text: &str) {
+        self.children.clear();
+        if !text.is_empty() {
+            self.children.push(HtmlElement::new_text(text));
+        }
+        self.text = text.to_string();
+    }
+
+    pub fn append_child(&mut self, node: HtmlElement) {
+        self.children.push(node);
+        self.normalize_text();
+    }
+
+    // Inserts `node` before the child at `index`, at the end when `index` is past it
+    pub fn insert_child(&mut self, index: usize, node: HtmlElement) {
+        self.children.insert(index.min(self.children.len()), node);
+        self.normalize_text();
+    }
+
+    pub fn remove_child(&mut self, index: usize) -> Option<HtmlElement> {
+        if index >= self.children.len() {
+            return None;
+        }
+        let removed = self.children.remove(index);
+        self.normalize_text();
+        Some(removed)
+    }
+
+    // Removes every element below this one matching `selector` along with its subtree,
+    // returning how many were removed
+    pub fn remove(&mut self, selector: &Selector) -> usize {
+        let before = self.children.len();
+        self.children
+            .retain(|child| !(child.is_element() && selector.matches(child)));
+        let mut removed = before - self.children.len();
+        for child in &mut self.children {
+            removed += child.remove(selector);
+        }
+        if removed > 0 {
+            self.normalize_text();
+        }
+        removed
+    }
+
+    // Replaces every element below this one matching `selector` with its children, e.g. to drop
+    // `<font>` or `<span>` wrappers while keeping their content. Returns how many were unwrapped.
+    pub fn unwrap(&mut self, selector: &Selector) -> usize {
+        let mut unwrapped = 0;
+        for mut child in std::mem::take(&mut self.children) {
+            unwrapped += child.unwrap(selector);
+            if child.is_element() && selector.matches(&child) {
+                unwrapped += 1;
+                self.children.append(&mut child.children);
+            } else {
+                self.children.push(child);
+            }
+        }
+        if unwrapped > 0 {
+            self.normalize_text();
+        }
+        unwrapped
+    }
+
+    // Calls `edit` on this element and every element below it matching `selector`, in document
+    // order, returning how many were edited
+    pub fn edit(&mut self, selector: &Selector, mut edit: impl FnMut(&mut HtmlElement)) -> usize {
+        self.edit_recursive(selector, &mut edit)
+    }
+
+    fn edit_recursive(
+        &mut self,
+        selector: &Selector,
+        edit: &mut dyn FnMut(&mut HtmlElement),
+    ) -> usize {
+        let mut edited = 0;
+        if self.is_element() && selector.matches(self) {
+            edit(self);
+            edited += 1;
+        }
+        for child in &mut self.children {
+            edited += child.edit_recursive(selector, edit);
+        }
+        edited
+    }
+
+    // Merges adjacent text nodes, drops empty ones and recomputes `text` from them
+    pub(crate) fn normalize_text(&mut self) {
+        let mut children: Vec<HtmlElement> = Vec::with_capacity(self.children.len());
+        for child in std::mem::take(&mut self.children) {
+            if child.kind == NodeKind::Text {
+                if child.text.is_empty() {
+                    continue;
+                }
+                if let Some(previous) = children.last_mut().filter(|c| c.kind == NodeKind::Text) {
+                    previous.text.push_str(&child.text);
+                    continue;
+                }
+            }
+            children.push(child);
+        }
+        self.children = children;
+        if matches!(
+            self.kind,
+            NodeKind::Element | NodeKind::Document | NodeKind::Fragment
+        ) {
+            self.text = self
+                .children
+                .iter()
+                .filter(|child| child.kind == NodeKind::Text)
+                .map(|child| child.text.as_str())
+                .collect();
+        }
+    }
+}
@@ -0,0 +1,534 @@
+use crate::engine::client::WappuError;
+use crate::engine::html::HtmlElement;
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Number, Value};
+
+// Helpers for the data sites embed in their pages for client-side rendering, so it can be read
+// without running any JavaScript
+impl HtmlElement {
+    // Parses the JSON in the script with the given id, e.g. `__NUXT_DATA__`
+    pub fn script_json<T: DeserializeOwned>(&self, id: &str) -> Result<T, WappuError> {
+        let script = scripts(self)
+            .into_iter()
+            .find(|script| script.attributes.get("id").map(String::as_str) == Some(id))
+            .ok_or_else(|| extraction_error(format!("No script with id {}", id)))?;
+        from_value(parse_js_literal(&script.text)?)
+    }
+
+    // The page props Next.js renders into `<script id="__NEXT_DATA__">`
+    pub fn next_data<T: DeserializeOwned>(&self) -> Result<T, WappuError> {
+        self.script_json("__NEXT_DATA__")
+    }
+
+    // Every `application/json`, `application/ld+json` or other `+json` script that holds valid JSON
+    pub fn json_scripts(&self) -> Vec<Value> {
+        scripts(self)
+            .into_iter()
+            .filter(|script| {
+                script.attributes.get("type").is_some_and(|script_type| {
+                    let script_type = script_type.trim().to_ascii_lowercase();
+                    script_type == "application/json" || script_type.ends_with("+json")
+                })
+            })
+            .filter_map(|script| serde_json::from_str(script.text.trim()).ok())
+            .collect()
+    }
+
+    // Reads the literal assigned to `name` in an inline script, like
+    // `window.__INITIAL_STATE__ = {...}` or `var config = JSON.parse('...')`
+    pub fn script_variable<T: DeserializeOwned>(&self, name: &str) -> Result<T, WappuError> {
+        for script in scripts(self) {
+            if script.attributes.contains_key("src") {
+                continue;
+            }
+            for start in assignments(&script.text, name) {
+                // Whatever follows the literal, like `;` or more statements, is ignored
+                if let Ok(value) = LiteralParser::new(&script.text[start..]).parse_value() {
+                    return from_value(value);
+                }
+            }
+        }
+        Err(extraction_error(format!(
+            "No literal is assigned to {}",
+            name
+        )))
+    }
+}
+
+// Parses a JavaScript literal into JSON. On top of JSON it accepts unquoted and numeric keys,
+// single-quoted and template strings, trailing commas, comments, hex numbers, `undefined`,
+// `NaN` and `Infinity` (which become null), the `!0`/`!1` booleans minifiers emit and
+// `JSON.parse("...")` calls.
+pub fn parse_js_literal(source: &str) -> Result<Value, WappuError> {
+    let mut parser = LiteralParser::new(source);
+    let value = parser.parse_value()?;
+    parser.skip_whitespace();
+    parser.eat(';');
+    parser.skip_whitespace();
+    if parser.peek().is_some() {
+        return Err(parser.error("Unexpected trailing content"));
+    }
+    Ok(value)
+}
+
+// Parses the literals in `source` one after the other, for scripts holding several values back to
+// back or separated by commas or semicolons
+pub(crate) fn parse_js_literals(source: &str) -> Result<Vec<Value>, WappuError> {
+    let mut parser = LiteralParser::new(source);
+    let mut values = Vec::new();
+    loop {
+        parser.skip_whitespace();
+        while parser.eat(',') || parser.eat(';') {
+            parser.skip_whitespace();
+        }
+        if parser.peek().is_none() {
+            return Ok(values);
+        }
+        values.push(parser.parse_value()?);
+    }
+}
+
+pub(crate) fn from_value<T: DeserializeOwned>(value: Value) -> Result<T, WappuError> {
+    serde_json::from_value(value).map_err(|err| extraction_error(err.to_string()))
+}
+
+fn extraction_error(message: String) -> WappuError {
+    WappuError::ExtractionError(message)
+}
+
+pub(crate) fn scripts(element: &HtmlElement) -> Vec<&HtmlElement> {
+    let mut scripts = Vec::new();
+    collect_scripts(element, &mut scripts);
+    scripts
+}
+
+fn collect_scripts<'a>(element: &'a HtmlElement, scripts: &mut Vec<&'a HtmlElement>) {
+    for child in &element.children {
+        if child.tag_name.as_deref() == Some("script") {
+            scripts.push(child);
+        } else {
+            collect_scripts(child, scripts);
+        }
+    }
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '$'
+}
+
+// Byte offsets just past the `=` of every `name = ...` assignment in `source`. A name also
+// matches as the last part of a property path, so `__STATE__` finds `window.__STATE__ =`.
+fn assignments(source: &str, name: &str) -> Vec<usize> {
+    let mut offsets = Vec::new();
+    for (index, _) in source.match_indices(name) {
+        let before = source[..index].chars().next_back();
+        if before.is_some_and(is_identifier_char) {
+            continue;
+        }
+        let rest = &source[index + name.len()..];
+        if rest.chars().next().is_some_and(is_identifier_char) {
+            continue;
+        }
+        let trimmed = rest.trim_start();
+        // `=` but not `==`, `=>` or `===`
+        if trimmed.starts_with('=') && !trimmed.starts_with("==") && !trimmed.starts_with("=>") {
+            offsets.push(source.len() - trimmed.len() + 1);
+        }
+    }
+    offsets
+}
+
+// How deep objects and arrays may nest, the same limit serde_json has. Page scripts are untrusted
+// and the parser recurses, so this keeps a deeply nested literal from overflowing the stack.
+const MAX_DEPTH: usize = 128;
+
+struct LiteralParser {
+    chars: Vec<char>,
+    position: usize,
+    // Objects and arrays open around the current position
+    depth: usize,
+}
+
+impl LiteralParser {
+    fn new(source: &str) -> Self {
+        LiteralParser {
+            chars: source.chars().collect(),
+            position: 0,
+            depth: 0,
+        }
+    }
+
+    fn error(&self, message: &str) -> WappuError {
+        extraction_error(format!("{} at character {}", message, self.position))
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.position + offset).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.position += 1;
+        Some(c)
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        if self.peek() == Some(expected) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), WappuError> {
+        if self.eat(expected) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("Expected '{}'", expected)))
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        loop {
+            match (self.peek(), self.peek_at(1)) {
+                (Some(c), _) if c.is_whitespace() => self.position += 1,
+                (Some('/'), Some('/')) => {
+                    while self.peek().is_some_and(|c| c != '\n') {
+                        self.position += 1;
+                    }
+                }
+                (Some('/'), Some('*')) => {
+                    self.position += 2;
+                    while self.peek().is_some()
+                        && !(self.peek() == Some('*') && self.peek_at(1) == Some('/'))
+                    {
+                        self.position += 1;
+                    }
+                    self.position = (self.position + 2).min(self.chars.len());
+                }
+                _ => break,
+            }
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<Value, WappuError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(bracket @ ('{' | '[')) => {
+                if self.depth == MAX_DEPTH {
+                    return Err(self.error("Literal nested too deeply"));
+                }
+                self.depth += 1;
+                let value = if bracket == '{' {
+                    self.parse_object()
+                } else {
+                    self.parse_array()
+                };
+                self.depth -= 1;
+                value
+            }
+            Some(quote @ ('"' | '\'' | '`')) => self.parse_string(quote).map(Value::String),
+            Some('!') => {
+                // Minified booleans: !0 is true and !1 is false
+                self.position += 1;
+                self.skip_whitespace();
+                match self.next() {
+                    Some('0') => Ok(Value::Bool(true)),
+                    Some('1') => Ok(Value::Bool(false)),
+                    _ => Err(self.error("Unsupported expression")),
+                }
+            }
+            Some(c) if c.is_ascii_digit() || c == '-' || c == '+' || c == '.' => {
+                self.parse_number()
+            }
+            Some(c) if is_identifier_char(c) => self.parse_identifier_value(),
+            Some(_) => Err(self.error("Unexpected character")),
+            None => Err(self.error("Unexpected end of input")),
+        }
+    }
+
+    fn parse_object(&mut self) -> Result<Value, WappuError> {
+        self.expect('{')?;
+        let mut object = Map::new();
+        loop {
+            self.skip_whitespace();
+            if self.eat('}') {
+                return Ok(Value::Object(object));
+            }
+            let key = match self.peek() {
+                Some(quote @ ('"' | '\'' | '`')) => self.parse_string(quote)?,
+                Some(c) if c.is_ascii_digit() => match self.parse_number()? {
+                    Value::Number(number) => number.to_string(),
+                    _ => return Err(self.error("Invalid key")),
+                },
+                Some(c) if is_identifier_char(c) => self.parse_identifier(),
+                _ => return Err(self.error("Expected a key")),
+            };
+            self.skip_whitespace();
+            self.expect(':')?;
+            let value = self.parse_value()?;
+            object.insert(key, value);
+
+            self.skip_whitespace();
+            if !self.eat(',') {
+                self.skip_whitespace();
+                self.expect('}')?;
+                return Ok(Value::Object(object));
+            }
+        }
+    }
+
+    fn parse_array(&mut self) -> Result<Value, WappuError> {
+        self.expect('[')?;
+        let mut array = Vec::new();
+        loop {
+            self.skip_whitespace();
+            if self.eat(']') {
+                return Ok(Value::Array(array));
+            }
+            // Holes like [1,,2] read as undefined
+            if self.peek() == Some(',') {
+                self.position += 1;
+                array.push(Value::Null);
+                continue;
+            }
+            array.push(self.parse_value()?);
+
+            self.skip_whitespace();
+            if !self.eat(',') {
+                self.skip_whitespace();
+                self.expect(']')?;
+                return Ok(Value::Array(array));
+            }
+        }
+    }
+
+    fn parse_string(&mut self, quote: char) -> Result<String, WappuError> {
+        self.expect(quote)?;
+        let mut string = String::new();
+        loop {
+            match self.next() {
+                None => return Err(self.error("Unterminated string")),
+                Some(c) if c == quote => return Ok(string),
+                Some('$') if quote == '`' && self.peek() == Some('{') => {
+                    return Err(self.error("Template literals with substitutions are not supported"))
+                }
+                Some('\\') => self.parse_escape(&mut string)?,
+                Some(c) => string.push(c),
+            }
+        }
+    }
+
+    fn parse_escape(&mut self, string: &mut String) -> Result<(), WappuError> {
+        let escaped = self
+            .next()
+            .ok_or_else(|| self.error("Unterminated string"))?;
+        match escaped {
+            'n' => string.push('\n'),
+            't' => string.push('\t'),
+            'r' => string.push('\r'),
+            'b' => string.push('\u{8}'),
+            'f' => string.push('\u{c}'),
+            'v' => string.push('\u{b}'),
+            '0' if !self.peek().is_some_and(|c| c.is_ascii_digit()) => string.push('\0'),
+            'x' => {
+                let code = self.parse_hex(2)?;
+                string.push(char::from_u32(code).unwrap_or('\u{fffd}'));
+            }
+            'u' => {
+                let code = if self.eat('{') {
+                    let start = self.position;
+                    while self.peek().is_some_and(|c| c.is_ascii_hexdigit()) {
+                        self.position += 1;
+                    }
+                    let digits: String = self.chars[start..self.position].iter().collect();
+                    self.expect('}')?;
+                    u32::from_str_radix(&digits, 16).map_err(|_| self.error("Invalid escape"))?
+                } else {
+                    let code = self.parse_hex(4)?;
+                    // A high surrogate followed by an escaped low surrogate is one character
+                    if (0xd800..0xdc00).contains(&code)
+                        && self.peek() == Some('\\')
+                        && self.peek_at(1) == Some('u')
+                    {
+                        let resume = self.position;
+                        self.position += 2;
+                        match self.parse_hex(4) {
+                            Ok(low) if (0xdc00..0xe000).contains(&low) => {
+                                0x10000 + ((code - 0xd800) << 10) + (low - 0xdc00)
+                            }
+                            _ => {
+                                self.position = resume;
+                                code
+                            }
+                        }
+                    } else {
+                        code
+                    }
+                };
+                string.push(char::from_u32(code).unwrap_or('\u{fffd}'));
+            }
+            // An escaped line break continues the string on the next line
+            '\r' => {
+                self.eat('\n');
+            }
+            '\n' | '\u{2028}' | '\u{2029}' => {}
+            c => string.push(c),
+        }
+        Ok(())
+    }
+
+    fn parse_hex(&mut self, digits: usize) -> Result<u32, WappuError> {
+        let end = self.position + digits;
+        if end > self.chars.len() {
+            return Err(self.error("Invalid escape"));
+        }
+        let hex: String = self.chars[self.position..end].iter().collect();
+        let code = u32::from_str_radix(&hex, 16).map_err(|_| self.error("Invalid escape"))?;
+        self.position = end;
+        Ok(code)
+    }
+
+    fn parse_number(&mut self) -> Result<Value, WappuError> {
+        let negative = match self.peek() {
+            Some('-') => {
+                self.position += 1;
+                true
+            }
+            Some('+') => {
+                self.position += 1;
+                false
+            }
+            _ => false,
+        };
+        self.skip_whitespace();
+        if self.peek().is_some_and(|c| c.is_alphabetic()) {
+            // -Infinity and friends
+            return match self.parse_identifier_value()? {
+                Value::Number(number) if negative => Ok(negate(&number)),
+                value => Ok(value),
+            };
+        }
+
+        let radix = match (self.peek(), self.peek_at(1).map(|c| c.to_ascii_lowercase())) {
+            (Some('0'), Some('x')) => 16,
+            (Some('0'), Some('o')) => 8,
+            (Some('0'), Some('b')) => 2,
+            _ => 10,
+        };
+        let number = if radix != 10 {
+            self.position += 2;
+            let start = self.position;
+            while self.peek().is_some_and(|c| c.is_digit(radix) || c == '_') {
+                self.position += 1;
+            }
+            let digits: String = self.chars[start..self.position]
+                .iter()
+                .filter(|c| **c != '_')
+                .collect();
+            let value =
+                u64::from_str_radix(&digits, radix).map_err(|_| self.error("Invalid number"))?;
+            Number::from(value)
+        } else {
+            let start = self.position;
+            while let Some(c) = self.peek() {
+                let exponent_sign = (c == '-' || c == '+')
+                    && self.position > start
+                    && matches!(self.chars[self.position - 1], 'e' | 'E');
+                if c.is_ascii_digit()
+                    || c == '.'
+                    || c == 'e'
+                    || c == 'E'
+                    || c == '_'
+                    || exponent_sign
+                {
+                    self.position += 1;
+                } else {
+                    break;
+                }
+            }
+            let text: String = self.chars[start..self.position]
+                .iter()
+                .filter(|c| **c != '_')
+                .collect();
+            if text.is_empty() {
+                return Err(self.error("Invalid number"));
+            }
+            match text.parse::<i64>() {
+                Ok(integer) => Number::from(integer),
+                Err(_) => {
+                    let float: f64 = text.parse().map_err(|_| self.error("Invalid number"))?;
+                    match Number::from_f64(float) {
+                        Some(number) => number,
+                        None => return Ok(Value::Null),
+                    }
+                }
+            }
+        };
+        Ok(if negative {
+            negate(&number)
+        } else {
+            Value::Number(number)
+        })
+    }
+
+    fn parse_identifier(&mut self) -> String {
+        let start = self.position;
+        while self.peek().is_some_and(is_identifier_char) {
+            self.position += 1;
+        }
+        self.chars[start..self.position].iter().collect()
+    }
+
+    fn parse_identifier_value(&mut self) -> Result<Value, WappuError> {
+        let start = self.position;
+        let identifier = self.parse_identifier();
+        match identifier.as_str() {
+            "true" => Ok(Value::Bool(true)),
+            "false" => Ok(Value::Bool(false)),
+            "null" | "undefined" | "NaN" | "Infinity" => Ok(Value::Null),
+            // void 0 is how minifiers write undefined
+            "void" => {
+                self.skip_whitespace();
+                self.parse_value()?;
+                Ok(Value::Null)
+            }
+            "JSON" => {
+                self.skip_whitespace();
+                self.expect('.')?;
+                self.skip_whitespace();
+                if self.parse_identifier() != "parse" {
+                    return Err(self.error("Only JSON.parse calls are supported"));
+                }
+                self.skip_whitespace();
+                self.expect('(')?;
+                let json = match self.parse_value()? {
+                    Value::String(json) => json,
+                    _ => return Err(self.error("JSON.parse expects a string")),
+                };
+                self.skip_whitespace();
+                self.expect(')')?;
+                serde_json::from_str(&json).map_err(|err| self.error(&err.to_string()))
+            }
+            _ => {
+                self.position = start;
+                Err(self.error(&format!("Unsupported identifier {}", identifier)))
+            }
+        }
+    }
+}
+
+fn negate(number: &Number) -> Value {
+    if let Some(integer) = number.as_i64() {
+        Value::Number(Number::from(-integer))
+    } else {
+        Number::from_f64(-number.as_f64().unwrap_or_default())
+            .map(Value::Number)
+            .unwrap_or(Value::Null)
+    }
+}
@@ -0,0 +1,702 @@
+use crate::engine::client::{WappuClient, WappuError};
+use crate::engine::html::HtmlElement;
+use crate::engine::xml::XmlParser;
+use crate::utils::resolve_url;
+use reqwest::header::{
+    HeaderMap, HeaderValue, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED,
+};
+use reqwest::StatusCode;
+use std::time::SystemTime;
+use time::format_description::well_known::{Rfc2822, Rfc3339};
+use time::{Date, Month, OffsetDateTime, PrimitiveDateTime, Time, UtcOffset};
+
+const ATOM: &str = "http://www.w3.org/2005/Atom";
+const ATOM_03: &str = "http://purl.org/atom/ns#";
+const RSS_10: &str = "http://purl.org/rss/1.0/";
+const RSS_090: &str = "http://my.netscape.com/rdf/simple/0.9/";
+
+// Namespaces whose elements are matched by their bare name, next to unprefixed ones. Each format
+// only has its own, so an `atom:link` in an RSS channel is never taken for the channel's link.
+const RSS_NAMESPACES: &[&str] = &[RSS_10, RSS_090];
+const ATOM_NAMESPACES: &[&str] = &[ATOM, ATOM_03];
+
+// Extension elements are matched by namespace, or by their usual prefix when a feed forgot to
+// declare it
+struct Extension {
+    namespace: &'static str,
+    prefix: &'static str,
+}
+
+const DUBLIN_CORE: Extension = Extension {
+    namespace: "http://purl.org/dc/elements/1.1/",
+    prefix: "dc",
+};
+const CONTENT: Extension = Extension {
+    namespace: "http://purl.org/rss/1.0/modules/content/",
+    prefix: "content",
+};
+const MEDIA: Extension = Extension {
+    namespace: "http://search.yahoo.com/mrss/",
+    prefix: "media",
+};
+// RSS feeds borrow Atom's link element to give their own URL
+const ATOM_LINK: Extension = Extension {
+    namespace: ATOM,
+    prefix: "atom",
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FeedFormat {
+    // RSS with its version: "0.90", "0.91", "0.92", "1.0" or "2.0"
+    Rss(String),
+    Atom,
+}
+
+// What to send on the next request so an unchanged feed comes back as 304 Not Modified
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CacheValidators {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+impl CacheValidators {
+    fn from_headers(headers: &HeaderMap) -> Self {
+        let header = |name| {
+            headers
+                .get(name)
+                .and_then(|value: &HeaderValue| value.to_str().ok())
+                .map(str::to_string)
+        };
+        CacheValidators {
+            etag: header(ETAG),
+            last_modified: header(LAST_MODIFIED),
+        }
+    }
+
+    fn to_headers(&self) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        if let Some(etag) = self.etag.as_deref().and_then(|etag| etag.parse().ok()) {
+            headers.insert(IF_NONE_MATCH, etag);
+        }
+        if let Some(date) = self
+            .last_modified
+            .as_deref()
+            .and_then(|date| date.parse().ok())
+        {
+            headers.insert(IF_MODIFIED_SINCE, date);
+        }
+        headers
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Person {
+    pub name: Option<String>,
+    pub email: Option<String>,
+    pub uri: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Enclosure {
+    pub url: String,
+    pub media_type: Option<String>,
+    pub length: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub id: Option<String>,
+    pub title: Option<String>,
+    pub link: Option<String>,
+    // RSS description or Atom summary, often HTML
+    pub summary: Option<String>,
+    // Full content from `content:encoded` or Atom content, often HTML
+    pub content: Option<String>,
+    pub published: Option<SystemTime>,
+    pub updated: Option<SystemTime>,
+    pub authors: Vec<Person>,
+    pub categories: Vec<String>,
+    pub enclosures: Vec<Enclosure>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Feed {
+    pub format: FeedFormat,
+    // URL the feed was fetched from, links in it are resolved against this
+    pub url: String,
+    pub title: Option<String>,
+    pub link: Option<String>,
+    // The URL the feed gives for itself with a `rel="self"` link, which may differ from `url`
+    // after redirects
+    pub self_link: Option<String>,
+    pub description: Option<String>,
+    pub updated: Option<SystemTime>,
+    pub authors: Vec<Person>,
+    pub entries: Vec<Entry>,
+    pub validators: CacheValidators,
+}
+
+impl Feed {
+    // Parses an RSS 0.9x/1.0/2.0 or Atom document. Tolerates what real feeds get wrong:
+    // whitespace or a byte order mark before the XML declaration, undeclared namespace
+    // prefixes, odd capitalisation, relative links and most date formats in the wild.
+    pub fn parse(input: &str, url: &str) -> Result<Feed, WappuError> {
+        let input = input.trim_start_matches('\u{feff}').trim_start();
+        let document = XmlParser::new().parse_xml(input);
+        let root = elements(&document)
+            .next()
+            .ok_or_else(|| feed_error("Document has no root element"))?;
+        let parser = |namespaces| FeedParser { url, namespaces };
+
+        match root.local_name().map(str::to_ascii_lowercase).as_deref() {
+            Some("rss") => {
+                let version = root
+                    .attributes
+                    .get("version")
+                    .map(|version| version.trim().to_string())
+                    .unwrap_or_else(|| "2.0".to_string());
+                Ok(parser(RSS_NAMESPACES).rss(root, FeedFormat::Rss(version)))
+            }
+            Some("rdf") => {
+                let version = if root.namespace.as_deref() == Some(RSS_090)
+                    || elements(root).any(|child| child.namespace.as_deref() == Some(RSS_090))
+                {
+                    "0.90"
+                } else {
+                    "1.0"
+                };
+                Ok(parser(RSS_NAMESPACES).rss(root, FeedFormat::Rss(version.to_string())))
+            }
+            Some("feed") => Ok(parser(ATOM_NAMESPACES).atom(root)),
+            _ => Err(feed_error(&format!(
+                "Not an RSS or Atom feed: root element is {}",
+                root.tag_name.as_deref().unwrap_or_default()
+            ))),
+        }
+    }
+}
+
+// Fetches and parses feeds, see `fetch_if_modified` for cheap polling
+pub struct FeedReader {
+    client: WappuClient,
+}
+
+impl Default for FeedReader {
+    fn default() -> Self {
+        Self::new(WappuClient::new())
+    }
+}
+
+impl FeedReader {
+    pub fn new(client: WappuClient) -> Self {
+        FeedReader { client }
+    }
+
+    pub async fn fetch(&self, url: &str) -> Result<Feed, WappuError> {
+        self.fetch_if_modified(url, &CacheValidators::default())
+            .await?
+            .ok_or_else(|| feed_error("Server answered Not Modified to an unconditional request"))
+    }
+
+    // Conditional GET with the validators of the last fetch (`feed.validators`), None when the
+    // feed hasn't changed since and the server answered 304 Not Modified without a body
+    pub async fn fetch_if_modified(
+        &self,
+        url: &str,
+        validators: &CacheValidators,
+    ) -> Result<Option<Feed>, WappuError> {
+        let response = match self.client.get(url, Some(validators.to_headers())).await {
+            Ok(response) => response,
+            Err(WappuError::UnexpectedStatusCode(StatusCode::NOT_MODIFIED, _)) => return Ok(None),
+            Err(err) => return Err(err),
+        };
+        let mut feed = Feed::parse(response.text(), response.url())?;
+        feed.validators = CacheValidators::from_headers(response.headers());
+        Ok(Some(feed))
+    }
+}
+
+struct FeedParser<'a> {
+    url: &'a str,
+    // Where the format's own elements live, see `is_core`
+    namespaces: &'static [&'static str],
+}
+
+impl FeedParser<'_> {
+    fn rss(&self, root: &HtmlElement, format: FeedFormat) -> Feed {
+        let channel = self.child(root, "channel");
+        let get = |name| channel.and_then(|channel| self.child_text(channel, name));
+        let extension = |extension, name| {
+            channel
+                .and_then(|channel| extension_child(channel, extension, name))
+                .and_then(text)
+        };
+
+        // RSS 1.0 and 0.90 put the items next to the channel instead of inside it
+        let items = channel
+            .into_iter()
+            .chain(std::iter::once(root))
+            .flat_map(|parent| self.children(parent, "item"));
+
+        let mut authors = Vec::new();
+        authors.extend(get("managingEditor").map(|author| rss_person(&author)));
+        authors.extend(extension(&DUBLIN_CORE, "creator").map(named_person));
+
+        Feed {
+            format,
+            url: self.url.to_string(),
+            title: get("title"),
+            link: get("link")
+                .and_then(|link| self.resolve(&link))
+                .or_else(|| channel.and_then(|channel| self.rss_atom_link(channel, "alternate"))),
+            self_link: channel.and_then(|channel| self.rss_atom_link(channel, "self")),
+            description: get("description"),
+            updated: get("lastBuildDate")
+                .or_else(|| get("pubDate"))
+                .or_else(|| extension(&DUBLIN_CORE, "date"))
+                .and_then(|date| parse_date(&date)),
+            authors,
+            entries: items.map(|item| self.rss_item(item)).collect(),
+            validators: CacheValidators::default(),
+        }
+    }
+
+    fn rss_item(&self, item: &HtmlElement) -> Entry {
+        let get = |name| self.child_text(item, name);
+        let extension = |extension, name| extension_child(item, extension, name).and_then(text);
+
+        let guid = self.child(item, "guid");
+        let id = guid.and_then(text);
+        // A guid is a permalink unless it says otherwise
+        let permalink = guid
+            .filter(|guid| {
+                guid.attributes.get("isPermaLink").map(|value| value.trim()) != Some("false")
+            })
+            .and_then(text);
+        let link = get("link")
+            .or_else(|| {
+                self.child(item, "link")
+                    .and_then(|link| link.attributes.get("href").cloned())
+            })
+            .and_then(|link| self.resolve(&link))
+            .or_else(|| self.rss_atom_link(item, "alternate"))
+            .or_else(|| permalink.and_then(|link| self.resolve(&link)));
+
+        let mut authors = Vec::new();
+        authors.extend(get("author").map(|author| rss_person(&author)));
+        for creator in extension_children(item, &DUBLIN_CORE, "creator") {
+            authors.extend(text(creator).map(named_person));
+        }
+
+        let mut categories: Vec<String> =
+            self.children(item, "category").filter_map(text).collect();
+        categories.extend(extension_children(item, &DUBLIN_CORE, "subject").filter_map(text));
+
+        let mut enclosures = Vec::new();
+        for enclosure in self.children(item, "enclosure") {
+            enclosures.extend(self.enclosure(enclosure, "url", "type", "length"));
+        }
+        for media in extension_children(item, &MEDIA, "content") {
+            enclosures.extend(self.enclosure(media, "url", "type", "fileSize"));
+        }
+
+        Entry {
+            id,
+            title: get("title"),
+            link,
+            summary: get("description"),
+            content: extension(&CONTENT, "encoded"),
+            published: get("pubDate")
+                .or_else(|| extension(&DUBLIN_CORE, "date"))
+                .and_then(|date| parse_date(&date)),
+            updated: None,
+            authors,
+            categories,
+            enclosures,
+        }
+    }
+
+    fn atom(&self, root: &HtmlElement) -> Feed {
+        let get = |name| self.child_text(root, name);
+        Feed {
+            format: FeedFormat::Atom,
+            url: self.url.to_string(),
+            title: get("title"),
+            link: self.atom_link(root),
+            self_link: self.link_with_rel(self.children(root, "link"), "self"),
+            description: get("subtitle").or_else(|| get("tagline")),
+            updated: get("updated")
+                .or_else(|| get("modified"))
+                .and_then(|date| parse_date(&date)),
+            authors: self.atom_people(root),
+            entries: self
+                .children(root, "entry")
+                .map(|entry| self.atom_entry(entry, root))
+                .collect(),
+            validators: CacheValidators::default(),
+        }
+    }
+
+    fn atom_entry(&self, entry: &HtmlElement, feed: &HtmlElement) -> Entry {
+        let get = |name| self.child_text(entry, name);
+
+        // Entries without authors inherit the feed's
+        let mut authors = self.atom_people(entry);
+        if authors.is_empty() {
+            authors = self.atom_people(feed);
+        }
+
+        let enclosures = self
+            .children(entry, "link")
+            .filter(|link| link.attributes.get("rel").map(String::as_str) == Some("enclosure"))
+            .filter_map(|link| self.enclosure(link, "href", "type", "length"))
+            .collect();
+
+        Entry {
+            id: get("id"),
+            title: get("title"),
+            link: self.atom_link(entry),
+            summary: get("summary"),
+            content: self
+                .child(entry, "content")
+                .and_then(|content| self.atom_content(content)),
+            published: get("published")
+                .or_else(|| get("issued"))
+                .and_then(|date| parse_date(&date)),
+            updated: get("updated")
+                .or_else(|| get("modified"))
+                .and_then(|date| parse_date(&date)),
+            authors,
+            categories: self
+                .children(entry, "category")
+                .filter_map(|category| {
+                    category
+                        .attributes
+                        .get("term")
+                        .map(|term| term.trim().to_string())
+                        .or_else(|| text(category))
+                })
+                .collect(),
+            enclosures,
+        }
+    }
+
+    // The alternate link, which is what a link without `rel` means
+    fn atom_link(&self, parent: &HtmlElement) -> Option<String> {
+        self.link_with_rel(self.children(parent, "link"), "alternate")
+    }
+
+    // The `atom:link` of an RSS channel or item with the given rel
+    fn rss_atom_link(&self, parent: &HtmlElement, rel: &str) -> Option<String> {
+        self.link_with_rel(extension_children(parent, &ATOM_LINK, "link"), rel)
+    }
+
+    fn link_with_rel<'a>(
+        &self,
+        mut links: impl Iterator<Item = &'a HtmlElement>,
+        rel: &str,
+    ) -> Option<String> {
+        links
+            .find(
+                |link| match link.attributes.get("rel").map(|rel| rel.trim()) {
+                    Some(link_rel) => link_rel == rel,
+                    None => rel == "alternate",
+                },
+            )
+            .and_then(|link| link.attributes.get("href"))
+            .and_then(|href| self.resolve(href))
+    }
+
+    fn enclosure(
+        &self,
+        element: &HtmlElement,
+        url: &str,
+        media_type: &str,
+        length: &str,
+    ) -> Option<Enclosure> {
+        Some(Enclosure {
+            url: self.resolve(element.attributes.get(url)?)?,
+            media_type: element
+                .attributes
+                .get(media_type)
+                .map(|value| value.trim().to_string()),
+            length: element
+                .attributes
+                .get(length)
+                .and_then(|length| length.trim().parse().ok()),
+        })
+    }
+
+    fn resolve(&self, url: &str) -> Option<String> {
+        let url = url.trim();
+        if url.is_empty() {
+            return None;
+        }
+        resolve_url(self.url, url).or_else(|| Some(url.to_string()))
+    }
+
+    fn atom_people(&self, parent: &HtmlElement) -> Vec<Person> {
+        self.children(parent, "author")
+            .map(|author| Person {
+                name: self.child_text(author, "name"),
+                email: self.child_text(author, "email"),
+                uri: self
+                    .child_text(author, "uri")
+                    .or_else(|| self.child_text(author, "url")),
+            })
+            .filter(|person| person.name.is_some() || person.email.is_some())
+            .collect()
+    }
+
+    fn atom_content(&self, content: &HtmlElement) -> Option<String> {
+        match content.attributes.get("type").map(|kind| kind.trim()) {
+            // Inline XHTML is markup rather than escaped text, keep what it reads as
+            Some("xhtml") => {
+                let text = deep_text(content);
+                Some(text.split_whitespace().collect::<Vec<_>>().join(" "))
+                    .filter(|text| !text.is_empty())
+            }
+            _ => text(content),
+        }
+    }
+
+    fn is_core(&self, element: &HtmlElement, name: &str) -> bool {
+        let unprefixed = element
+            .tag_name
+            .as_deref()
+            .is_some_and(|tag_name| !tag_name.contains(':'));
+        let in_core_namespace = element
+            .namespace
+            .as_deref()
+            .is_some_and(|namespace| self.namespaces.contains(&namespace));
+        (unprefixed || in_core_namespace)
+            && element
+                .local_name()
+                .is_some_and(|local_name| local_name.eq_ignore_ascii_case(name))
+    }
+
+    fn children<'a>(
+        &'a self,
+        parent: &'a HtmlElement,
+        name: &'a str,
+    ) -> impl Iterator<Item = &'a HtmlElement> {
+        elements(parent).filter(move |child| self.is_core(child, name))
+    }
+
+    fn child<'a>(&self, parent: &'a HtmlElement, name: &str) -> Option<&'a HtmlElement> {
+        elements(parent).find(|child| self.is_core(child, name))
+    }
+
+    fn child_text(&self, parent: &HtmlElement, name: &str) -> Option<String> {
+        self.child(parent, name).and_then(text)
+    }
+}
+
+// RSS authors are an email address, usually with the name in parentheses or the other way around
+fn rss_person(author: &str) -> Person {
+    let author = author.trim();
+    let (name, email) = if let Some((email, name)) = author.split_once('(') {
+        (Some(name.trim_end_matches(')').trim()), Some(email.trim()))
+    } else if let Some((name, email)) = author.split_once('<') {
+        (Some(name.trim()), Some(email.trim_end_matches('>').trim()))
+    } else if author.contains('@') && !author.contains(' ') {
+        (None, Some(author))
+    } else {
+        (Some(author), None)
+    };
+    let non_empty =
+        |value: Option<&str>| value.filter(|value| !value.is_empty()).map(str::to_string);
+    Person {
+        name: non_empty(name),
+        email: non_empty(email),
+        uri: None,
+    }
+}
+
+fn named_person(name: String) -> Person {
+    Person {
+        name: Some(name),
+        email: None,
+        uri: None,
+    }
+}
+
+fn elements(parent: &HtmlElement) -> impl Iterator<Item = &HtmlElement> {
+    parent.children.iter().filter(|child| child.is_element())
+}
+
+fn extension_children<'a>(
+    parent: &'a HtmlElement,
+    extension: &'a Extension,
+    name: &'a str,
+) -> impl Iterator<Item = &'a HtmlElement> {
+    elements(parent).filter(move |child| {
+        let prefixed = format!("{}:{}", extension.prefix, name);
+        child.local_name() == Some(name)
+            && (child.namespace.as_deref() == Some(extension.namespace)
+                || child.tag_name.as_deref() == Some(prefixed.as_str()))
+    })
+}
+
+fn extension_child<'a>(
+    parent: &'a HtmlElement,
+    extension: &'a Extension,
+    name: &'a str,
+) -> Option<&'a HtmlElement> {
+    extension_children(parent, extension, name).next()
+}
+
+fn text(element: &HtmlElement) -> Option<String> {
+    Some(element.text.trim().to_string()).filter(|text| !text.is_empty())
+}
+
+fn deep_text(element: &HtmlElement) -> String {
+    let mut text = element.text.clone();
+    for child in elements(element) {
+        text.push(' ');
+        text.push_str(&deep_text(child));
+    }
+    text
+}
+
+fn feed_error(message: &str) -> WappuError {
+    WappuError::FeedError(message.to_string())
+}
+
+// Dates in RFC 822 (RSS) or RFC 3339 (Atom, Dublin Core) format. Dates `time` can't parse as
+// either are read leniently, allowing the usual deviations: two digit years, missing seconds or
+// zone, full month names, zone abbreviations. Dates outside the years `time` supports are None.
+fn parse_date(value: &str) -> Option<SystemTime> {
+    let value = value.trim();
+    OffsetDateTime::parse(value, &Rfc3339)
+        .or_else(|_| OffsetDateTime::parse(value, &Rfc2822))
+        .ok()
+        .or_else(|| parse_rfc3339(value))
+        .or_else(|| parse_rfc822(value))
+        // Fractions of a second are dropped, like the lenient parsers do
+        .map(|date| SystemTime::from(date.replace_nanosecond(0).unwrap_or(date)))
+}
+
+fn parse_rfc3339(value: &str) -> Option<OffsetDateTime> {
+    let (date, time) = match value.find(['T', 't', ' ']) {
+        Some(index) => (&value[..index], value[index + 1..].trim()),
+        None => (value, ""),
+    };
+    let mut parts = date.splitn(3, '-');
+    let year = parts.next()?.parse().ok()?;
+    let month = parts.next()?.parse().ok()?;
+    let day = parts.next()?.parse().ok()?;
+    if time.is_empty() {
+        return timestamp(year, month, day, (0, 0, 0), 0);
+    }
+
+    let zone_start = time.find(['Z', 'z', '+', '-']).unwrap_or(time.len());
+    let offset = parse_offset(time[zone_start..].trim())?;
+    timestamp(year, month, day, parse_time(&time[..zone_start])?, offset)
+}
+
+fn parse_rfc822(value: &str) -> Option<OffsetDateTime> {
+    let value = value.replace(',', " ");
+    let mut tokens = value.split_whitespace().peekable();
+    // The day of the week is optional and often wrong, skip it
+    if tokens
+        .peek()?
+        .starts_with(|c: char| c.is_ascii_alphabetic())
+    {
+        tokens.next();
+    }
+    let day = tokens.next()?.parse().ok()?;
+    let month = parse_month(tokens.next()?)?;
+    let year: i64 = match tokens.next()?.parse().ok()? {
+        year @ 0..=49 => 2000 + year,
+        year @ 50..=99 => 1900 + year,
+        year => year,
+    };
+    let time = match tokens.next() {
+        Some(time) => parse_time(time)?,
+        None => (0, 0, 0),
+    };
+    let offset = match tokens.next() {
+        Some(zone) => parse_offset(zone).unwrap_or(0),
+        None => 0,
+    };
+    timestamp(year, month, day, time, offset)
+}
+
+fn parse_month(month: &str) -> Option<u32> {
+    const MONTHS: [&str; 12] = [
+        "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+    ];
+    let prefix = month.get(..3)?.to_ascii_lowercase();
+    MONTHS
+        .iter()
+        .position(|name| *name == prefix)
+        .map(|index| index as u32 + 1)
+}
+
+// "hh:mm", "hh:mm:ss" or "hh:mm:ss.fff", fractions of a second are dropped
+fn parse_time(time: &str) -> Option<(u32, u32, u32)> {
+    let mut parts = time.trim().split(':');
+    let hour = parts.next()?.parse().ok()?;
+    let minute = parts.next()?.parse().ok()?;
+    let second = match parts.next() {
+        Some(second) => second.split('.').next()?.parse().ok()?,
+        None => 0,
+    };
+    Some((hour, minute, second))
+}
+
+// Offset from UTC in seconds
+fn parse_offset(zone: &str) -> Option<i64> {
+    let hours = match zone.to_ascii_uppercase().as_str() {
+        "" | "Z" | "UT" | "UTC" | "GMT" => 0,
+        "EDT" => -4,
+        "EST" | "CDT" => -5,
+        "CST" | "MDT" => -6,
+        "MST" | "PDT" => -7,
+        "PST" => -8,
+        _ => {
+            let sign = match zone.chars().next()? {
+                '+' => 1,
+                '-' => -1,
+                _ => return None,
+            };
+            let digits: String = zone[1..].chars().filter(|c| *c != ':').collect();
+            if digits.len() != 4 && digits.len() != 2 {
+                return None;
+            }
+            let hours: i64 = digits.get(..2)?.parse().ok()?;
+            let minutes: i64 = digits
+                .get(2..)
+                .filter(|m| !m.is_empty())
+                .map_or(Some(0), |m| m.parse().ok())?;
+            return Some(sign * (hours * 3600 + minutes * 60));
+        }
+    };
+    Some(hours * 3600)
+}
+
+fn timestamp(
+    year: i64,
+    month: u32,
+    day: u32,
+    time: (u32, u32, u32),
+    offset: i64,
+) -> Option<OffsetDateTime> {
+    let (hour, minute, second) = time;
+    let date = Date::from_calendar_date(
+        i32::try_from(year).ok()?,
+        Month::try_from(u8::try_from(month).ok()?).ok()?,
+        u8::try_from(day).ok()?,
+    )
+    .ok()?;
+    // A leap second is read as the last second of its minute
+    let time = Time::from_hms(
+        u8::try_from(hour).ok()?,
+        u8::try_from(minute).ok()?,
+        u8::try_from(second.min(59)).ok()?,
+    )
+    .ok()?;
+    let offset = UtcOffset::from_whole_seconds(i32::try_from(offset).ok()?).ok()?;
+    Some(PrimitiveDateTime::new(date, time).assume_offset(offset))
+}
@@ -0,0 +1,760 @@
+use crate::engine::client::{WappuClient, WappuError, WappuResponse};
+use crate::engine::html::HtmlElement;
+use crate::engine::selector::Selector;
+use crate::utils::{document_base_url, resolve_url};
+use reqwest::header::{HeaderMap, HeaderValue, CONTENT_TYPE};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+use url::{form_urlencoded, Url};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FormMethod {
+    Get,
+    Post,
+}
+
+impl FormMethod {
+    // Invalid or missing values leave the choice to the caller, like the HTML spec's defaults
+    fn parse(value: Option<&String>) -> Option<Self> {
+        match value?.trim().to_ascii_lowercase().as_str() {
+            "get" => Some(FormMethod::Get),
+            "post" => Some(FormMethod::Post),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FormEnctype {
+    UrlEncoded,
+    Multipart,
+    TextPlain,
+}
+
+impl FormEnctype {
+    fn parse(value: Option<&String>) -> Option<Self> {
+        match value?.trim().to_ascii_lowercase().as_str() {
+            "application/x-www-form-urlencoded" => Some(FormEnctype::UrlEncoded),
+            "multipart/form-data" => Some(FormEnctype::Multipart),
+            "text/plain" => Some(FormEnctype::TextPlain),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FormEnctype::UrlEncoded => "application/x-www-form-urlencoded",
+            FormEnctype::Multipart => "multipart/form-data",
+            FormEnctype::TextPlain => "text/plain",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SelectOption {
+    pub value: String,
+    pub label: String,
+    pub selected: bool,
+    pub disabled: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FormFile {
+    pub filename: String,
+    pub content_type: String,
+    pub contents: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ControlKind {
+    // Text-like inputs, including hidden ones, with their input type
+    Input(String),
+    Checkbox {
+        checked: bool,
+    },
+    Radio {
+        checked: bool,
+    },
+    Textarea,
+    Select {
+        multiple: bool,
+        options: Vec<SelectOption>,
+    },
+    File(Option<FormFile>),
+    // Submit buttons and image inputs, only the one the form is submitted with is sent.
+    // The overrides come from its formaction, formmethod and formenctype attributes.
+    Submit {
+        image: bool,
+        action: Option<String>,
+        method: Option<FormMethod>,
+        enctype: Option<FormEnctype>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FormControl {
+    pub name: String,
+    pub value: String,
+    pub kind: ControlKind,
+}
+
+// A form found in a document, with its action resolved and the controls a browser would submit.
+// Disabled controls, buttons that can't submit and controls without a name are left out, except
+// for submit buttons: an unnamed one adds no entry but still applies its overrides when it's the
+// one submitting.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Form {
+    pub id: Option<String>,
+    pub name: Option<String>,
+    pub action: String,
+    pub method: FormMethod,
+    pub enctype: FormEnctype,
+    pub controls: Vec<FormControl>,
+}
+
+// The HTTP request submitting a form results in
+#[derive(Debug, Clone, PartialEq)]
+pub struct FormRequest {
+    pub method: FormMethod,
+    pub url: String,
+    pub content_type: Option<String>,
+    pub body: Option<Vec<u8>>,
+}
+
+enum EntryValue<'a> {
+    Text(String),
+    File(Option<&'a FormFile>),
+}
+
+struct Entry<'a> {
+    name: String,
+    value: EntryValue<'a>,
+}
+
+impl Form {
+    // Every form in the document, in document order
+    pub fn all(document: &HtmlElement, page_url: &str) -> Vec<Form> {
+        let mut elements = Vec::new();
+        collect_forms(document, &mut elements);
+        elements
+            .iter()
+            .map(|form| Form::from_element(document, form, page_url))
+            .collect()
+    }
+
+    // The first form element matching `selector`
+    pub fn select(document: &HtmlElement, selector: &Selector, page_url: &str) -> Option<Form> {
+        selector
+            .select(document)
+            .elements
+            .into_iter()
+            .find(|element| element.tag_name.as_deref() == Some("form"))
+            .map(|form| Form::from_element(document, form, page_url))
+    }
+
+    // Builds the form from its element, `document` is needed for the `<base href>` and for
+    // controls associated through a `form="id"` attribute
+    pub fn from_element(document: &HtmlElement, form: &HtmlElement, page_url: &str) -> Form {
+        let base_url = document_base_url(document, page_url);
+        // An empty action, on the form or on a button, submits back to the page itself
+        let resolve_action = |action: Option<&String>| match action.map(|action| action.trim()) {
+            Some(action) if !action.is_empty() => {
+                resolve_url(&base_url, action).unwrap_or_else(|| page_url.to_string())
+            }
+            _ => page_url.to_string(),
+        };
+        let action = resolve_action(form.attributes.get("action"));
+
+        let mut controls = Vec::new();
+        collect_controls(
+            document,
+            form,
+            form.attributes.get("id"),
+            None,
+            false,
+            &mut controls,
+        );
+        for control in &mut controls {
+            if let ControlKind::Submit {
+                action: Some(action),
+                ..
+            } = &mut control.kind
+            {
+                *action = resolve_action(Some(action));
+            }
+        }
+
+        Form {
+            id: form.attributes.get("id").cloned(),
+            name: form.attributes.get("name").cloned(),
+            action,
+            method: FormMethod::parse(form.attributes.get("method")).unwrap_or(FormMethod::Get),
+            enctype: FormEnctype::parse(form.attributes.get("enctype"))
+                .unwrap_or(FormEnctype::UrlEncoded),
+            controls,
+        }
+    }
+
+    pub fn control(&self, name: &str) -> Option<&FormControl> {
+        self.controls.iter().find(|control| control.name == name)
+    }
+
+    // Sets the value of the control called `name`. Checkboxes and radio buttons get the one
+    // with that value checked, selects get the option with that value selected.
+    pub fn set(&mut self, name: &str, value: &str) -> Result<&mut Self, WappuError> {
+        let control = self
+            .controls
+            .iter_mut()
+            .find(|control| control.name == name)
+            .ok_or_else(|| form_error(format!("No control named {}", name)))?;
+
+        match &mut control.kind {
+            ControlKind::Input(_) | ControlKind::Textarea => {
+                control.value = value.to_string();
+                Ok(self)
+            }
+            ControlKind::Checkbox { .. } | ControlKind::Radio { .. } => {
+                self.set_checked(name, value, true)
+            }
+            ControlKind::Select { multiple, options } => {
+                if !options.iter().any(|option| option.value == value) {
+                    return Err(form_error(format!("{} has no option {}", name, value)));
+                }
+                let multiple = *multiple;
+                for option in options.iter_mut() {
+                    if option.value == value {
+                        option.selected = true;
+                    } else if !multiple {
+                        option.selected = false;
+                    }
+                }
+                Ok(self)
+            }
+            ControlKind::File(_) => Err(form_error(format!(
+                "{} is a file input, use set_file instead",
+                name
+            ))),
+            ControlKind::Submit { .. } => Err(form_error(format!("{} is a submit button", name))),
+        }
+    }
+
+    // Checks or unchecks the checkbox or radio button called `name` with the given value.
+    // Checking a radio button unchecks the others in its group.
+    pub fn set_checked(
+        &mut self,
+        name: &str,
+        value: &str,
+        checked: bool,
+    ) -> Result<&mut Self, WappuError> {
+        let index = self
+            .controls
+            .iter()
+            .position(|control| {
+                control.name == name
+                    && control.value == value
+                    && matches!(
+                        control.kind,
+                        ControlKind::Checkbox { .. } | ControlKind::Radio { .. }
+                    )
+            })
+            .ok_or_else(|| form_error(format!("No checkbox or radio {} = {}", name, value)))?;
+
+        if checked && matches!(self.controls[index].kind, ControlKind::Radio { .. }) {
+            for control in self.controls.iter_mut().filter(|c| c.name == name) {
+                if let ControlKind::Radio { checked } = &mut control.kind {
+                    *checked = false;
+                }
+            }
+        }
+        match &mut self.controls[index].kind {
+            ControlKind::Checkbox { checked: state } | ControlKind::Radio { checked: state } => {
+                *state = checked
+            }
+            _ => unreachable!(),
+        }
+        Ok(self)
+    }
+
+    // Attaches a file to the file input called `name`
+    pub fn set_file(
+        &mut self,
+        name: &str,
+        filename: &str,
+        content_type: &str,
+        contents: &[u8],
+    ) -> Result<&mut Self, WappuError> {
+        let control = self
+            .controls
+            .iter_mut()
+            .find(|control| control.name == name && matches!(control.kind, ControlKind::File(_)))
+            .ok_or_else(|| form_error(format!("No file input named {}", name)))?;
+        control.kind = ControlKind::File(Some(FormFile {
+            filename: filename.to_string(),
+            content_type: content_type.to_string(),
+            contents: contents.to_vec(),
+        }));
+        Ok(self)
+    }
+
+    // Adds a hidden field the page itself doesn't have, e.g. one normally added by a script
+    pub fn add(&mut self, name: &str, value: &str) -> &mut Self {
+        self.controls.push(FormControl {
+            name: name.to_string(),
+            value: value.to_string(),
+            kind: ControlKind::Input("hidden".to_string()),
+        });
+        self
+    }
+
+    // Name/value pairs the form submits with the default button, files contribute their name
+    pub fn entries(&self) -> Vec<(String, String)> {
+        self.entry_list(self.default_button())
+            .into_iter()
+            .map(|entry| {
+                let value = match entry.value {
+                    EntryValue::Text(text) => text,
+                    EntryValue::File(file) => file.map(|f| f.filename.clone()).unwrap_or_default(),
+                };
+                (entry.name, value)
+            })
+            .collect()
+    }
+
+    // The request pressing Enter in the form makes, which submits it with its first button
+    pub fn request(&self) -> FormRequest {
+        self.build_request(self.default_button())
+    }
+
+    // The request clicking the submit button called `submitter` makes
+    pub fn request_with(&self, submitter: &str) -> Result<FormRequest, WappuError> {
+        let index = self
+            .controls
+            .iter()
+            .position(|control| {
+                control.name == submitter && matches!(control.kind, ControlKind::Submit { .. })
+            })
+            .ok_or_else(|| form_error(format!("No submit button named {}", submitter)))?;
+        Ok(self.build_request(Some(index)))
+    }
+
+    pub async fn submit(&self, client: &WappuClient) -> Result<WappuResponse, WappuError> {
+        self.request().send(client, None).await
+    }
+
+    pub async fn submit_with(
+        &self,
+        client: &WappuClient,
+        submitter: &str,
+    ) -> Result<WappuResponse, WappuError> {
+        self.request_with(submitter)?.send(client, None).await
+    }
+
+    fn default_button(&self) -> Option<usize> {
+        self.controls
+            .iter()
+            .position(|control| matches!(control.kind, ControlKind::Submit { .. }))
+    }
+
+    fn entry_list<'a>(&'a self, submitter: Option<usize>) -> Vec<Entry<'a>> {
+        let mut entries = Vec::new();
+        for (index, control) in self.controls.iter().enumerate() {
+            let mut push = |name: &str, value: EntryValue<'a>| {
+                let value = match value {
+                    EntryValue::Text(text) => EntryValue::Text(normalize_newlines(&text)),
+                    file => file,
+                };
+                entries.push(Entry {
+                    name: normalize_newlines(name),
+                    value,
+                });
+            };
+            let text = || EntryValue::Text(control.value.clone());
+            match &control.kind {
+                ControlKind::Input(input_type) => {
+                    // A hidden `_charset_` field reports the encoding the form is sent in
+                    if input_type == "hidden"
+                        && control.name.eq_ignore_ascii_case("_charset_")
+                        && control.value.is_empty()
+                    {
+                        push(&control.name, EntryValue::Text("UTF-8".to_string()));
+                    } else {
+                        push(&control.name, text());
+                    }
+                }
+                ControlKind::Textarea => push(&control.name, text()),
+                ControlKind::Checkbox { checked } | ControlKind::Radio { checked } => {
+                    if *checked {
+                        push(&control.name, text());
+                    }
+                }
+                ControlKind::Select { options, .. } => {
+                    for option in options.iter().filter(|o| o.selected && !o.disabled) {
+                        push(&control.name, EntryValue::Text(option.value.clone()));
+                    }
+                }
+                ControlKind::File(file) => push(&control.name, EntryValue::File(file.as_ref())),
+                ControlKind::Submit { image, .. } => {
+                    if submitter != Some(index) {
+                        continue;
+                    }
+                    if *image {
+                        // Image buttons send the click coordinates instead of a value
+                        let prefix = if control.name.is_empty() {
+                            String::new()
+                        } else {
+                            format!("{}.", control.name)
+                        };
+                        push(&format!("{}x", prefix), EntryValue::Text("0".to_string()));
+                        push(&format!("{}y", prefix), EntryValue::Text("0".to_string()));
+                    } else if !control.name.is_empty() {
+                        push(&control.name, text());
+                    }
+                }
+            }
+        }
+        entries
+    }
+
+    fn build_request(&self, submitter: Option<usize>) -> FormRequest {
+        let (mut action, mut method, mut enctype) =
+            (self.action.clone(), self.method, self.enctype);
+        if let Some(ControlKind::Submit {
+            action: button_action,
+            method: button_method,
+            enctype: button_enctype,
+            ..
+        }) = submitter.map(|index| &self.controls[index].kind)
+        {
+            action = button_action.clone().unwrap_or(action);
+            method = button_method.unwrap_or(method);
+            enctype = button_enctype.unwrap_or(enctype);
+        }
+
+        let entries = self.entry_list(submitter);
+        match method {
+            FormMethod::Get => {
+                // GET forms replace the query of the action with their entries
+                let url = match Url::parse(&action) {
+                    Ok(mut url) => {
+                        url.set_query(Some(&urlencode(&entries)));
+                        url.to_string()
+                    }
+                    Err(_) => action,
+                };
+                FormRequest {
+                    method,
+                    url,
+                    content_type: None,
+                    body: None,
+                }
+            }
+            FormMethod::Post => {
+                let (content_type, body) = match enctype {
+                    FormEnctype::UrlEncoded => (
+                        enctype.as_str().to_string(),
+                        urlencode(&entries).into_bytes(),
+                    ),
+                    FormEnctype::Multipart => {
+                        let boundary = multipart_boundary();
+                        (
+                            format!("{}; boundary={}", enctype.as_str(), boundary),
+                            multipart(&entries, &boundary),
+                        )
+                    }
+                    FormEnctype::TextPlain => (
+                        enctype.as_str().to_string(),
+                        text_plain(&entries).into_bytes(),
+                    ),
+                };
+                FormRequest {
+                    method,
+                    url: action,
+                    content_type: Some(content_type),
+                    body: Some(body),
+                }
+            }
+        }
+    }
+}
+
+impl FormRequest {
+    // Sends the request with `client`, along with any extra headers such as a Referer
+    pub async fn send(
+        &self,
+        client: &WappuClient,
+        headers: Option<HeaderMap>,
+    ) -> Result<WappuResponse, WappuError> {
+        let mut headers = headers.unwrap_or_default();
+        match (&self.body, &self.content_type) {
+            (Some(body), Some(content_type)) => {
+                let content_type = HeaderValue::from_str(content_type)
+                    .map_err(|err| form_error(err.to_string()))?;
+                headers.insert(CONTENT_TYPE, content_type);
+                client
+                    .post_bytes(&self.url, body.clone(), Some(headers))
+                    .await
+            }
+            _ => client.get(&self.url, Some(headers)).await,
+        }
+    }
+}
+
+fn form_error(message: String) -> WappuError {
+    WappuError::FormError(message)
+}
+
+fn collect_forms<'a>(element: &'a HtmlElement, forms: &mut Vec<&'a HtmlElement>) {
+    for child in &element.children {
+        if child.tag_name.as_deref() == Some("form") {
+            forms.push(child);
+        }
+        collect_forms(child, forms);
+    }
+}
+
+// Walks the document in order collecting the controls owned by `form`: those inside it without
+// a `form` attribute, and those anywhere whose `form` attribute names its id
+fn collect_controls(
+    element: &HtmlElement,
+    form: &HtmlElement,
+    form_id: Option<&String>,
+    owner: Option<&HtmlElement>,
+    disabled: bool,
+    controls: &mut Vec<FormControl>,
+) {
+    let mut first_legend = true;
+    for child in &element.children {
+        let tag = child.tag_name.as_deref();
+        let owner = if tag == Some("form") {
+            Some(child)
+        } else {
+            owner
+        };
+        // A disabled fieldset disables everything in it except its first legend
+        let child_disabled = if element.tag_name.as_deref() == Some("fieldset")
+            && element.attributes.contains_key("disabled")
+        {
+            let is_first_legend = tag == Some("legend") && first_legend;
+            if tag == Some("legend") {
+                first_legend = false;
+            }
+            !is_first_legend || disabled
+        } else {
+            disabled
+        };
+
+        let owned = match child.attributes.get("form") {
+            Some(id) => form_id == Some(id),
+            None => owner.is_some_and(|owner| std::ptr::eq(owner, form)),
+        };
+        if matches!(tag, Some("input" | "button" | "select" | "textarea")) {
+            if owned && !child_disabled && !child.attributes.contains_key("disabled") {
+                controls.extend(control_from_element(child));
+            }
+            continue;
+        }
+        // Options inside a datalist only offer suggestions
+        if tag != Some("datalist") {
+            collect_controls(child, form, form_id, owner, child_disabled, controls);
+        }
+    }
+}
+
+fn control_from_element(element: &HtmlElement) -> Option<FormControl> {
+    let attribute = |name: &str| element.attributes.get(name);
+    let name = attribute("name").cloned().unwrap_or_default();
+    let value = attribute("value").cloned().unwrap_or_default();
+
+    let kind = match element.tag_name.as_deref()? {
+        "textarea" => {
+            return non_empty(FormControl {
+                value: element.text.clone(),
+                name,
+                kind: ControlKind::Textarea,
+            })
+        }
+        "select" => {
+            let mut options = Vec::new();
+            collect_options(element, false, &mut options);
+            let multiple = element.attributes.contains_key("multiple");
+            // A single select always has an option selected, the first usable one by default
+            if !multiple && !options.iter().any(|option| option.selected) {
+                if let Some(option) = options.iter_mut().find(|option| !option.disabled) {
+                    option.selected = true;
+                }
+            }
+            // Only the last selected option of a single select stays selected
+            if !multiple {
+                if let Some(last) = options.iter().rposition(|option| option.selected) {
+                    for (index, option) in options.iter_mut().enumerate() {
+                        option.selected = index == last;
+                    }
+                }
+            }
+            ControlKind::Select { multiple, options }
+        }
+        "button" => match attribute("type").map(|t| t.to_ascii_lowercase()).as_deref() {
+            Some("button" | "reset") => return None,
+            _ => submit_kind(element, false),
+        },
+        _ => {
+            let input_type = attribute("type")
+                .map(|t| t.trim().to_ascii_lowercase())
+                .filter(|t| !t.is_empty())
+                .unwrap_or_else(|| "text".to_string());
+            match input_type.as_str() {
+                "button" | "reset" => return None,
+                "submit" => submit_kind(element, false),
+                "image" => submit_kind(element, true),
+                "checkbox" | "radio" => {
+                    let checked = element.attributes.contains_key("checked");
+                    let value = attribute("value")
+                        .cloned()
+                        .unwrap_or_else(|| "on".to_string());
+                    let kind = if input_type == "checkbox" {
+                        ControlKind::Checkbox { checked }
+                    } else {
+                        ControlKind::Radio { checked }
+                    };
+                    return non_empty(FormControl { name, value, kind });
+                }
+                "file" => ControlKind::File(None),
+                _ => ControlKind::Input(input_type),
+            }
+        }
+    };
+    // Submit buttons without a name are kept, they still pick the request the form makes
+    if matches!(kind, ControlKind::Submit { .. }) {
+        return Some(FormControl { name, value, kind });
+    }
+    non_empty(FormControl { name, value, kind })
+}
+
+fn non_empty(control: FormControl) -> Option<FormControl> {
+    Some(control).filter(|control| !control.name.is_empty())
+}
+
+fn submit_kind(element: &HtmlElement, image: bool) -> ControlKind {
+    ControlKind::Submit {
+        image,
+        action: element.attributes.get("formaction").cloned(),
+        method: FormMethod::parse(element.attributes.get("formmethod")),
+        enctype: FormEnctype::parse(element.attributes.get("formenctype")),
+    }
+}
+
+fn collect_options(element: &HtmlElement, disabled: bool, options: &mut Vec<SelectOption>) {
+    for child in &element.children {
+        let child_disabled = disabled || child.attributes.contains_key("disabled");
+        match child.tag_name.as_deref() {
+            Some("option") => {
+                let label = child.text.split_whitespace().collect::<Vec<_>>().join(" ");
+                options.push(SelectOption {
+                    value: child
+                        .attributes
+                        .get("value")
+                        .cloned()
+                        .unwrap_or_else(|| label.clone()),
+                    label,
+                    selected: child.attributes.contains_key("selected"),
+                    disabled: child_disabled,
+                });
+            }
+            Some("optgroup") => collect_options(child, child_disabled, options),
+            _ => collect_options(child, disabled, options),
+        }
+    }
+}
+
+// Browsers send line breaks in names and values as CRLF whatever the encoding
+fn normalize_newlines(text: &str) -> String {
+    text.replace("\r\n", "\n")
+        .replace('\r', "\n")
+        .replace('\n', "\r\n")
+}
+
+fn urlencode(entries: &[Entry]) -> String {
+    let mut serializer = form_urlencoded::Serializer::new(String::new());
+    for entry in entries {
+        match &entry.value {
+            EntryValue::Text(text) => serializer.append_pair(&entry.name, text),
+            EntryValue::File(file) => serializer.append_pair(
+                &entry.name,
+                file.map(|file| file.filename.as_str()).unwrap_or(""),
+            ),
+        };
+    }
+    serializer.finish()
+}
+
+fn text_plain(entries: &[Entry]) -> String {
+    entries
+        .iter()
+        .map(|entry| {
+            let value = match &entry.value {
+                EntryValue::Text(text) => text.as_str(),
+                EntryValue::File(file) => file.map(|file| file.filename.as_str()).unwrap_or(""),
+            };
+            format!("{}={}\r\n", entry.name, value)
+        })
+        .collect()
+}
+
+// File contents are sent as they are, so the body is bytes rather than text
+fn multipart(entries: &[Entry], boundary: &str) -> Vec<u8> {
+    // Quotes and line breaks in names and filenames are percent-encoded, like browsers do
+    let escape = |text: &str| {
+        text.replace('"', "%22")
+            .replace('\r', "%0D")
+            .replace('\n', "%0A")
+    };
+    let mut body = Vec::new();
+    for entry in entries {
+        body.extend_from_slice(
+            format!(
+                "--{}\r\nContent-Disposition: form-data; name=\"{}\"",
+                boundary,
+                escape(&entry.name)
+            )
+            .as_bytes(),
+        );
+        match &entry.value {
+            EntryValue::Text(text) => {
+                body.extend_from_slice(format!("\r\n\r\n{}\r\n", text).as_bytes())
+            }
+            EntryValue::File(file) => {
+                // An empty file input still sends a part, with no filename and no content
+                let (filename, content_type, contents) = match file {
+                    Some(file) => (
+                        file.filename.as_str(),
+                        file.content_type.as_str(),
+                        file.contents.as_slice(),
+                    ),
+                    None => ("", "application/octet-stream", &[][..]),
+                };
+                body.extend_from_slice(
+                    format!(
+                        "; filename=\"{}\"\r\nContent-Type: {}\r\n\r\n",
+                        escape(filename),
+                        content_type
+                    )
+                    .as_bytes(),
+                );
+                body.extend_from_slice(contents);
+                body.extend_from_slice(b"\r\n");
+            }
+        }
+    }
+    body.extend_from_slice(format!("--{}--\r\n", boundary).as_bytes());
+    body
+}
+
+fn multipart_boundary() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_nanos() as u64)
+        .unwrap_or_default();
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+    format!("----WappuFormBoundary{:016x}{:04x}", nanos, count & 0xffff)
+}
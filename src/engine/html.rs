@@ -1,12 +1,20 @@
 extern crate html5ever;
 extern crate markup5ever_rcdom as rcdom;
 
-use html5ever::{parse_document, tendril::TendrilSink};
+use html5ever::{
+    namespace_url, ns, parse_document, parse_fragment, tendril::TendrilSink, LocalName, QualName,
+};
 use markup5ever_rcdom::{Handle, NodeData, RcDom};
 use std::collections::HashMap;
 
 pub struct HtmlParser;
 
+impl Default for HtmlParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl HtmlParser {
     pub fn new() -> Self {
         HtmlParser {}
@@ -17,50 +25,213 @@ impl HtmlParser {
 
         HtmlElement::from_dom(&dom.document)
     }
+
+    // Parses a snippet as if it were the content of a `context_tag` element, the way
+    // `innerHTML` does, so `<tr>` rows parse with "tbody" as context instead of being dropped.
+    // The returned root has no tag name and holds the snippet's nodes.
+    pub fn parse_fragment(&self, input: &str, context_tag: &str) -> HtmlElement {
+        let context = QualName::new(
+            None,
+            ns!(html),
+            LocalName::from(context_tag.to_ascii_lowercase()),
+        );
+        let dom =
+            parse_fragment(RcDom::default(), Default::default(), context, Vec::new()).one(input);
+
+        // The fragment algorithm puts the parsed nodes under an `html` element
+        let mut root = dom
+            .document
+            .children
+            .borrow()
+            .first()
+            .map(HtmlElement::from_dom)
+            .unwrap_or_else(|| HtmlElement::from_dom(&dom.document));
+        root.tag_name = None;
+        root.attributes.clear();
+        root.kind = NodeKind::Fragment;
+        root
+    }
 }
 
-#[derive(Debug)]
+// What a node in the parsed tree is. All kinds of nodes are kept in `children` in document
+// order; the text of an element's text node children is also joined in its `text`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum NodeKind {
+    Document,
+    // The root `parse_fragment` returns
+    Fragment,
+    Element,
+    Text,
+    // The comment's contents are in `text`
+    Comment,
+    Doctype(Doctype),
+    // The instruction's data is in `text`
+    ProcessingInstruction { target: String },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Doctype {
+    pub name: String,
+    pub public_id: String,
+    pub system_id: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct HtmlElement {
     pub tag_name: Option<String>,
     pub text: String,
     pub children: Vec<HtmlElement>,
     pub attributes: HashMap<String, String>,
+    pub kind: NodeKind,
+    // Namespace URI of elements parsed by `XmlParser`, None for HTML
+    pub namespace: Option<String>,
 }
 
 impl HtmlElement {
+    pub fn new_element(tag_name: &str) -> Self {
+        HtmlElement {
+            tag_name: Some(tag_name.to_string()),
+            ..HtmlElement::node(NodeKind::Element, String::new())
+        }
+    }
+
+    pub fn new_text(text: &str) -> Self {
+        HtmlElement::node(NodeKind::Text, text.to_string())
+    }
+
+    pub fn new_comment(comment: &str) -> Self {
+        HtmlElement::node(NodeKind::Comment, comment.to_string())
+    }
+
+    pub fn is_element(&self) -> bool {
+        self.kind == NodeKind::Element
+    }
+
+    // The tag name without its namespace prefix, `entry` for `atom:entry`
+    pub fn local_name(&self) -> Option<&str> {
+        self.tag_name
+            .as_deref()
+            .map(|tag_name| tag_name.rsplit(':').next().unwrap_or(tag_name))
+    }
+
+    // Comment nodes in this subtree in document order
+    pub fn comments(&self) -> Vec<&HtmlElement> {
+        self.nodes_of_kind(&|kind| *kind == NodeKind::Comment)
+    }
+
+    // Processing instructions in this subtree in document order
+    pub fn processing_instructions(&self) -> Vec<&HtmlElement> {
+        self.nodes_of_kind(&|kind| matches!(kind, NodeKind::ProcessingInstruction { .. }))
+    }
+
+    // The document's `<!DOCTYPE>`, if it had one
+    pub fn doctype(&self) -> Option<&Doctype> {
+        self.children.iter().find_map(|child| match child.kind {
+            NodeKind::Doctype(ref doctype) => Some(doctype),
+            _ => None,
+        })
+    }
+
+    // Parses the contents of a comment node as HTML, for markup sites hide in comments. The
+    // wrapper of conditional comments like `<!--[if IE]>...<![endif]-->` is removed first.
+    // None if this isn't a comment.
+    pub fn parse_comment(&self) -> Option<HtmlElement> {
+        if self.kind != NodeKind::Comment {
+            return None;
+        }
+        let mut contents = self.text.as_str();
+        if contents.starts_with("[if ") {
+            if let Some(end) = contents.find("]>") {
+                contents = &contents[end + 2..];
+            }
+            contents = contents.strip_suffix("<![endif]").unwrap_or(contents);
+        }
+        Some(HtmlParser::new().parse_fragment(contents, "body"))
+    }
+
+    fn nodes_of_kind<'a>(&'a self, predicate: &dyn Fn(&NodeKind) -> bool) -> Vec<&'a HtmlElement> {
+        let mut nodes = Vec::new();
+        self.collect_nodes(predicate, &mut nodes);
+        nodes
+    }
+
+    fn collect_nodes<'a>(
+        &'a self,
+        predicate: &dyn Fn(&NodeKind) -> bool,
+        nodes: &mut Vec<&'a HtmlElement>,
+    ) {
+        for child in &self.children {
+            if predicate(&child.kind) {
+                nodes.push(child);
+            }
+            child.collect_nodes(predicate, nodes);
+        }
+    }
+
+    fn node(kind: NodeKind, text: String) -> Self {
+        HtmlElement {
+            tag_name: None,
+            text,
+            children: vec![],
+            attributes: HashMap::new(),
+            kind,
+            namespace: None,
+        }
+    }
+
     fn from_dom(handle: &Handle) -> Self {
+        HtmlElement::convert(handle, false)
+    }
+
+    // Converts a tree built by xml5ever, keeping names as written with their prefixes
+    pub(crate) fn from_xml_dom(handle: &Handle) -> Self {
+        HtmlElement::convert(handle, true)
+    }
+
+    fn convert(handle: &Handle, xml: bool) -> Self {
         match handle.data {
             NodeData::Document => {
-                let children = handle
+                let children: Vec<HtmlElement> = handle
                     .children
                     .borrow()
                     .iter()
-                    .map(HtmlElement::from_dom)
+                    .map(|child| HtmlElement::convert(child, xml))
+                    .collect();
+                let text = children
+                    .iter()
+                    .filter(|child| child.kind == NodeKind::Text)
+                    .map(|child| child.text.as_str())
                     .collect();
 
                 HtmlElement {
                     tag_name: None,
-                    text: String::new(),
+                    text,
                     children,
                     attributes: HashMap::new(),
+                    kind: NodeKind::Document,
+                    namespace: None,
                 }
             }
             NodeData::Element { ref name, ref attrs, .. } => {
-                let tag_name = Some(name.local.to_string());
+                let name_of = |name: &QualName| match name.prefix {
+                    Some(ref prefix) if xml => format!("{}:{}", prefix, name.local),
+                    _ => name.local.to_string(),
+                };
+                let tag_name = Some(name_of(name));
                 let attributes = attrs.borrow().iter().map(|attr| {
-                    (attr.name.local.to_string(), attr.value.to_string())
+                    (name_of(&attr.name), attr.value.to_string())
                 }).collect();
+                let namespace = Some(name.ns.to_string()).filter(|ns| xml && !ns.is_empty());
 
                 let mut children = Vec::new();
                 let mut text = String::new();
                 for child in handle.children.borrow().iter() {
-                    let child_element = HtmlElement::from_dom(child);
+                    let child_element = HtmlElement::convert(child, xml);
                     // Append child text to the parent element's text if the child is a text node.
-                    if child_element.tag_name.is_none() {
+                    if child_element.kind == NodeKind::Text {
                         text += &child_element.text;
-                    } else {
-                        children.push(child_element);
                     }
+                    children.push(child_element);
                 }
 
                 HtmlElement {
@@ -68,20 +239,37 @@ impl HtmlElement {
                     text, // Now contains the aggregated text of its child text nodes.
                     children,
                     attributes,
+                    kind: NodeKind::Element,
+                    namespace,
                 }
             }
-            NodeData::Text { ref contents } => HtmlElement {
-                tag_name: None,
-                text: contents.borrow().to_string(),
-                children: vec![],
-                attributes: HashMap::new(),
-            },
-            _ => HtmlElement {
-                tag_name: None,
-                text: String::new(),
-                children: vec![],
-                attributes: HashMap::new(),
-            },
+            NodeData::Text { ref contents } => {
+                HtmlElement::node(NodeKind::Text, contents.borrow().to_string())
+            }
+            NodeData::Comment { ref contents } => {
+                HtmlElement::node(NodeKind::Comment, contents.to_string())
+            }
+            NodeData::Doctype {
+                ref name,
+                ref public_id,
+                ref system_id,
+            } => HtmlElement::node(
+                NodeKind::Doctype(Doctype {
+                    name: name.to_string(),
+                    public_id: public_id.to_string(),
+                    system_id: system_id.to_string(),
+                }),
+                String::new(),
+            ),
+            NodeData::ProcessingInstruction {
+                ref target,
+                ref contents,
+            } => HtmlElement::node(
+                NodeKind::ProcessingInstruction {
+                    target: target.to_string(),
+                },
+                contents.to_string(),
+            ),
         }
     }
 }
@@ -0,0 +1,363 @@
+use crate::engine::client::WappuError;
+use crate::engine::embedded::{from_value, parse_js_literals, scripts};
+use crate::engine::html::HtmlElement;
+use serde::de::{DeserializeOwned, Deserializer};
+use serde::Deserialize;
+use serde_json::Value;
+
+// Types that are an Article as far as `articles` is concerned
+const ARTICLE_TYPES: &[&str] = &[
+    "Article",
+    "NewsArticle",
+    "BlogPosting",
+    "Report",
+    "ScholarlyArticle",
+    "TechArticle",
+    "AnalysisNewsArticle",
+    "OpinionNewsArticle",
+    "ReportageNewsArticle",
+    "ReviewNewsArticle",
+    "LiveBlogPosting",
+];
+
+// The schema.org JSON-LD a page embeds in `<script type="application/ld+json">`. Top-level
+// arrays and `@graph` arrays are flattened, so `nodes` holds every top-level entity as an object.
+#[derive(Debug, Clone, Default)]
+pub struct JsonLd {
+    pub nodes: Vec<Value>,
+}
+
+impl JsonLd {
+    // Reads the JSON-LD scripts below `element`. They're parsed tolerantly, accepting comments,
+    // trailing commas, several objects in one script and `<!-- -->` or CDATA wrappers, and the
+    // ones that can't be read are skipped.
+    pub fn from_element(element: &HtmlElement) -> Self {
+        let mut nodes = Vec::new();
+        for script in scripts(element) {
+            let is_json_ld = script.attributes.get("type").is_some_and(|script_type| {
+                script_type
+                    .trim()
+                    .eq_ignore_ascii_case("application/ld+json")
+            });
+            if !is_json_ld {
+                continue;
+            }
+            if let Ok(values) = parse_js_literals(unwrap_script(&script.text)) {
+                for value in values {
+                    flatten(value, &mut nodes);
+                }
+            }
+        }
+        JsonLd { nodes }
+    }
+
+    // The entities whose `@type` is, or includes, `type_name`. Types match by their last path
+    // segment, so "Product" also finds "http://schema.org/Product" and "schema:Product".
+    pub fn of_type(&self, type_name: &str) -> Vec<&Value> {
+        self.nodes
+            .iter()
+            .filter(|node| has_type(node, type_name))
+            .collect()
+    }
+
+    // The first entity of the type deserialized into `T`
+    pub fn first<T: DeserializeOwned>(&self, type_name: &str) -> Result<T, WappuError> {
+        let node = self.of_type(type_name).into_iter().next().ok_or_else(|| {
+            WappuError::ExtractionError(format!("No JSON-LD entity of type {}", type_name))
+        })?;
+        from_value(node.clone())
+    }
+
+    // Every entity of the type deserialized into `T`
+    pub fn all<T: DeserializeOwned>(&self, type_name: &str) -> Result<Vec<T>, WappuError> {
+        self.of_type(type_name)
+            .into_iter()
+            .map(|node| from_value(node.clone()))
+            .collect()
+    }
+
+    pub fn products(&self) -> Result<Vec<Product>, WappuError> {
+        self.all("Product")
+    }
+
+    // Articles along with their subtypes like NewsArticle and BlogPosting
+    pub fn articles(&self) -> Result<Vec<Article>, WappuError> {
+        self.nodes
+            .iter()
+            .filter(|node| {
+                ARTICLE_TYPES
+                    .iter()
+                    .any(|type_name| has_type(node, type_name))
+            })
+            .map(|node| from_value(node.clone()))
+            .collect()
+    }
+
+    pub fn breadcrumbs(&self) -> Result<Vec<BreadcrumbList>, WappuError> {
+        self.all("BreadcrumbList")
+    }
+}
+
+impl HtmlElement {
+    // The schema.org JSON-LD embedded in the page, see `JsonLd`
+    pub fn json_ld(&self) -> JsonLd {
+        JsonLd::from_element(self)
+    }
+
+    // The page's JSON-LD, microdata and RDFa items together, for code that doesn't care which
+    // syntax a site uses
+    pub fn structured_data(&self) -> JsonLd {
+        let mut nodes = self.json_ld().nodes;
+        nodes.extend(self.microdata().nodes);
+        nodes.extend(self.rdfa().nodes);
+        JsonLd { nodes }
+    }
+}
+
+// Common schema.org shapes. Schema.org is loose about types, so fields that may be a plain value
+// or an object take the object's name or URL, fields that may be one value or a list are lists,
+// and numbers written as strings (or the other way around) are strings.
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Product {
+    #[serde(default, deserialize_with = "text")]
+    pub name: Option<String>,
+    #[serde(default, deserialize_with = "text")]
+    pub description: Option<String>,
+    #[serde(default, deserialize_with = "text")]
+    pub sku: Option<String>,
+    #[serde(default, deserialize_with = "text")]
+    pub gtin: Option<String>,
+    #[serde(default, deserialize_with = "text")]
+    pub brand: Option<String>,
+    #[serde(default, deserialize_with = "texts")]
+    pub image: Vec<String>,
+    #[serde(default, deserialize_with = "text")]
+    pub url: Option<String>,
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub offers: Vec<Offer>,
+    #[serde(default, deserialize_with = "optional")]
+    pub aggregate_rating: Option<AggregateRating>,
+}
+
+// An Offer, or an AggregateOffer summarizing several with `low_price` and `high_price`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Offer {
+    #[serde(default, deserialize_with = "text")]
+    pub price: Option<String>,
+    #[serde(default, deserialize_with = "text")]
+    pub low_price: Option<String>,
+    #[serde(default, deserialize_with = "text")]
+    pub high_price: Option<String>,
+    #[serde(default, deserialize_with = "text")]
+    pub price_currency: Option<String>,
+    // The schema.org item type name, like "InStock"
+    #[serde(default, deserialize_with = "enumeration")]
+    pub availability: Option<String>,
+    #[serde(default, deserialize_with = "enumeration")]
+    pub item_condition: Option<String>,
+    #[serde(default, deserialize_with = "text")]
+    pub url: Option<String>,
+    #[serde(default, deserialize_with = "text")]
+    pub seller: Option<String>,
+    #[serde(default, deserialize_with = "text")]
+    pub price_valid_until: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AggregateRating {
+    #[serde(default, deserialize_with = "text")]
+    pub rating_value: Option<String>,
+    #[serde(default, deserialize_with = "text")]
+    pub review_count: Option<String>,
+    #[serde(default, deserialize_with = "text")]
+    pub rating_count: Option<String>,
+    #[serde(default, deserialize_with = "text")]
+    pub best_rating: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Article {
+    #[serde(default, deserialize_with = "text")]
+    pub headline: Option<String>,
+    #[serde(default, deserialize_with = "text")]
+    pub name: Option<String>,
+    #[serde(default, deserialize_with = "text")]
+    pub description: Option<String>,
+    // Author names
+    #[serde(default, deserialize_with = "texts")]
+    pub author: Vec<String>,
+    #[serde(default, deserialize_with = "text")]
+    pub publisher: Option<String>,
+    #[serde(default, deserialize_with = "text")]
+    pub date_published: Option<String>,
+    #[serde(default, deserialize_with = "text")]
+    pub date_modified: Option<String>,
+    #[serde(default, deserialize_with = "texts")]
+    pub image: Vec<String>,
+    #[serde(default, deserialize_with = "text")]
+    pub url: Option<String>,
+    #[serde(default, deserialize_with = "text")]
+    pub article_body: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BreadcrumbList {
+    // In `position` order
+    #[serde(default, deserialize_with = "list_items")]
+    pub item_list_element: Vec<ListItem>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ListItem {
+    pub position: Option<u32>,
+    pub name: Option<String>,
+    // The URL of the item
+    pub item: Option<String>,
+}
+
+// Strips what's sometimes wrapped around script content for ancient browsers
+fn unwrap_script(text: &str) -> &str {
+    let mut text = text.trim();
+    for (prefix, suffix) in [
+        ("<!--", "-->"),
+        ("//<![CDATA[", "//]]>"),
+        ("<![CDATA[", "]]>"),
+    ] {
+        if let Some(inner) = text.strip_prefix(prefix) {
+            text = inner.strip_suffix(suffix).unwrap_or(inner).trim();
+        }
+    }
+    text
+}
+
+fn flatten(value: Value, nodes: &mut Vec<Value>) {
+    match value {
+        Value::Array(values) => {
+            for value in values {
+                flatten(value, nodes);
+            }
+        }
+        Value::Object(mut object) => match object.remove("@graph") {
+            Some(graph) => {
+                flatten(graph, nodes);
+                // Anything besides the context next to the graph is an entity of its own
+                object.remove("@context");
+                if !object.is_empty() {
+                    nodes.push(Value::Object(object));
+                }
+            }
+            None => nodes.push(Value::Object(object)),
+        },
+        _ => {}
+    }
+}
+
+fn has_type(node: &Value, type_name: &str) -> bool {
+    let matches = |value: &Value| value.as_str().is_some_and(|t| short_name(t) == type_name);
+    match &node["@type"] {
+        Value::Array(types) => types.iter().any(matches),
+        value => matches(value),
+    }
+}
+
+// "http://schema.org/InStock" and "schema:InStock" are both "InStock"
+fn short_name(name: &str) -> &str {
+    name.trim()
+        .rsplit(['/', ':', '#'])
+        .next()
+        .unwrap_or_default()
+}
+
+// A value as text: strings as they are, numbers and booleans written out, and for objects their
+// name, URL or id
+fn value_text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(string) => {
+            let string = string.trim();
+            (!string.is_empty()).then(|| string.to_string())
+        }
+        Value::Number(number) => Some(number.to_string()),
+        Value::Bool(boolean) => Some(boolean.to_string()),
+        Value::Array(values) => values.iter().find_map(value_text),
+        Value::Object(object) => ["name", "url", "contentUrl", "@id", "@value"]
+            .iter()
+            .find_map(|key| object.get(*key).and_then(value_text)),
+        Value::Null => None,
+    }
+}
+
+fn text<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    Ok(value_text(&Value::deserialize(deserializer)?))
+}
+
+fn texts<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    Ok(match Value::deserialize(deserializer)? {
+        Value::Array(values) => values.iter().filter_map(value_text).collect(),
+        value => value_text(&value).into_iter().collect(),
+    })
+}
+
+fn enumeration<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    Ok(text(deserializer)?.map(|name| short_name(&name).to_string()))
+}
+
+// Objects that don't fit the shape are skipped rather than failing the whole entity
+fn one_or_many<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: DeserializeOwned,
+{
+    Ok(match Value::deserialize(deserializer)? {
+        Value::Array(values) => values
+            .into_iter()
+            .filter_map(|value| serde_json::from_value(value).ok())
+            .collect(),
+        value => serde_json::from_value(value).ok().into_iter().collect(),
+    })
+}
+
+fn optional<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: DeserializeOwned,
+{
+    Ok(one_or_many(deserializer)?.into_iter().next())
+}
+
+fn list_items<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<ListItem>, D::Error> {
+    let values = match Value::deserialize(deserializer)? {
+        Value::Array(values) => values,
+        value => vec![value],
+    };
+    let mut items: Vec<ListItem> = values
+        .iter()
+        .map(|value| {
+            let item = &value["item"];
+            ListItem {
+                position: match &value["position"] {
+                    Value::String(position) => position.trim().parse().ok(),
+                    position => position
+                        .as_u64()
+                        .and_then(|position| position.try_into().ok()),
+                },
+                // The name is either on the list item or on the thing it points to
+                name: value_text(&value["name"]).or_else(|| value_text(&item["name"])),
+                item: match item {
+                    Value::Object(object) => ["@id", "url"]
+                        .iter()
+                        .find_map(|key| object.get(*key).and_then(value_text)),
+                    item => value_text(item),
+                }
+                .or_else(|| value_text(&value["url"])),
+            }
+        })
+        .collect();
+    items.sort_by_key(|item| item.position.unwrap_or(u32::MAX));
+    Ok(items)
+}
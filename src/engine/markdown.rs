@@ -0,0 +1,686 @@
+use crate::engine::html::{HtmlElement, NodeKind};
+use crate::engine::selector::Selection;
+use crate::utils::resolve_url;
+
+// Elements that start a new block; anything else is rendered inline
+pub(crate) const BLOCK_ELEMENTS: &[&str] = &[
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "body",
+    "center",
+    "dd",
+    "details",
+    "dialog",
+    "div",
+    "dl",
+    "dt",
+    "fieldset",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hgroup",
+    "hr",
+    "html",
+    "li",
+    "main",
+    "menu",
+    "nav",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "summary",
+    "table",
+    "ul",
+];
+
+// Elements with nothing worth reading in Markdown
+const SKIPPED_ELEMENTS: &[&str] = &[
+    "head", "script", "style", "noscript", "template", "iframe", "object", "embed", "svg", "math",
+    "canvas", "select", "textarea", "button",
+];
+
+// CommonMark list numbers have at most nine digits
+const MAX_LIST_NUMBER_DIGITS: usize = 9;
+const MAX_LIST_NUMBER: usize = 999_999_999;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkStyle {
+    // [text](https://example.com)
+    Inline,
+    // [text][1] with the URLs listed at the end as `[1]: https://example.com`
+    Referenced,
+}
+
+// Converts HTML to CommonMark with the GitHub flavoured extensions for tables, strikethrough and
+// task lists
+pub struct MarkdownConverter {
+    base_url: Option<String>,
+    link_style: LinkStyle,
+    strip_images: bool,
+}
+
+impl Default for MarkdownConverter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MarkdownConverter {
+    pub fn new() -> Self {
+        MarkdownConverter {
+            base_url: None,
+            link_style: LinkStyle::Inline,
+            strip_images: false,
+        }
+    }
+
+    // URL relative link and image URLs are resolved against, usually the page's URL
+    pub fn base_url(mut self, base_url: &str) -> Self {
+        self.base_url = Some(base_url.to_string());
+        self
+    }
+
+    pub fn link_style(mut self, link_style: LinkStyle) -> Self {
+        self.link_style = link_style;
+        self
+    }
+
+    // Leave images out instead of writing them as `![alt](src)`
+    pub fn strip_images(mut self, strip_images: bool) -> Self {
+        self.strip_images = strip_images;
+        self
+    }
+
+    pub fn convert(&self, element: &HtmlElement) -> String {
+        self.convert_all(&[element])
+    }
+
+    // Converts the selected elements as consecutive blocks of one document
+    pub fn convert_selection(&self, selection: &Selection) -> String {
+        self.convert_all(&selection.elements)
+    }
+
+    fn convert_all(&self, elements: &[&HtmlElement]) -> String {
+        let mut renderer = Renderer {
+            converter: self,
+            references: Vec::new(),
+        };
+        let mut blocks = Vec::new();
+        for element in elements {
+            match element.kind {
+                NodeKind::Element => blocks.extend(renderer.block(element)),
+                _ => blocks.extend(renderer.blocks(&element.children)),
+            }
+        }
+        let mut markdown = join_blocks(&blocks);
+        if !renderer.references.is_empty() {
+            markdown.push_str("\n\n");
+            for (index, url) in renderer.references.iter().enumerate() {
+                markdown.push_str(&format!("[{}]: {}\n", index + 1, url));
+            }
+            markdown.pop();
+        }
+        markdown
+    }
+}
+
+struct Block {
+    markdown: String,
+    is_list: bool,
+}
+
+impl Block {
+    fn new(markdown: String) -> Self {
+        Block {
+            markdown,
+            is_list: false,
+        }
+    }
+}
+
+struct Renderer<'a> {
+    converter: &'a MarkdownConverter,
+    // Link URLs in order, for `LinkStyle::Referenced`
+    references: Vec<String>,
+}
+
+impl Renderer<'_> {
+    // Renders nodes as a sequence of blocks, runs of inline content becoming paragraphs
+    fn blocks(&mut self, nodes: &[HtmlElement]) -> Vec<Block> {
+        let mut blocks = Vec::new();
+        let mut paragraph = String::new();
+        for node in nodes {
+            if is_block(node) {
+                flush_paragraph(&mut paragraph, &mut blocks);
+                blocks.extend(self.block(node));
+            } else {
+                self.inline(node, &mut paragraph);
+            }
+        }
+        flush_paragraph(&mut paragraph, &mut blocks);
+        blocks
+    }
+
+    fn block(&mut self, element: &HtmlElement) -> Option<Block> {
+        let tag_name = element.tag_name.as_deref().unwrap_or_default();
+        if SKIPPED_ELEMENTS.contains(&tag_name) {
+            return None;
+        }
+        let markdown = match tag_name {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                let level = tag_name[1..].parse().unwrap_or(1);
+                let text = single_line(&self.inline_text(element));
+                format!("{} {}", "#".repeat(level), text)
+            }
+            "hr" => "---".to_string(),
+            "pre" => code_block(element),
+            "blockquote" => {
+                let quoted = join_blocks(&self.blocks(&element.children));
+                quoted
+                    .lines()
+                    .map(|line| match line {
+                        "" => ">".to_string(),
+                        line => format!("> {}", line),
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            }
+            "ul" | "ol" | "menu" => {
+                return Some(Block {
+                    markdown: self.list(element)?,
+                    is_list: true,
+                })
+            }
+            "table" => self.table(element)?,
+            "li" => {
+                // A list item outside of a list
+                return self.list_item(element, "- ").map(Block::new);
+            }
+            _ => join_blocks(&self.blocks(&element.children)),
+        };
+        Some(Block::new(markdown)).filter(|block| !block.markdown.trim().is_empty())
+    }
+
+    fn list(&mut self, list: &HtmlElement) -> Option<String> {
+        let ordered = list.tag_name.as_deref() == Some("ol");
+        let mut number: usize = list
+            .attributes
+            .get("start")
+            .and_then(|start| start.trim().parse().ok())
+            .unwrap_or(1)
+            .min(MAX_LIST_NUMBER);
+        let mut items = Vec::new();
+        for child in list.children.iter().filter(|child| child.is_element()) {
+            let marker = match ordered {
+                true => format!("{}. ", number),
+                false => "- ".to_string(),
+            };
+            let item = match child.tag_name.as_deref() {
+                Some("li") => self.list_item(child, &marker),
+                // Lists nested straight in a list rather than in an item
+                Some("ul" | "ol") => self.list(child).map(|list| indent(&list, "  ")),
+                _ => self.block(child).map(|block| block.markdown),
+            };
+            if let Some(item) = item {
+                items.push(item);
+                number = (number + 1).min(MAX_LIST_NUMBER);
+            }
+        }
+        Some(items.join("\n")).filter(|items| !items.is_empty())
+    }
+
+    fn list_item(&mut self, item: &HtmlElement, marker: &str) -> Option<String> {
+        let mut content = String::new();
+        // GitHub task list items start with a checkbox
+        let checkbox = find_first_element(item)
+            .filter(|first| first.tag_name.as_deref() == Some("input"))
+            .filter(|input| input.attributes.get("type").map(String::as_str) == Some("checkbox"));
+        if let Some(checkbox) = checkbox {
+            content.push_str(match checkbox.attributes.contains_key("checked") {
+                true => "[x] ",
+                false => "[ ] ",
+            });
+        }
+
+        let blocks = self.blocks(&item.children);
+        // Keep a nested list right under its item so the list stays tight
+        for (index, block) in blocks.iter().enumerate() {
+            if index > 0 {
+                content.push_str(if block.is_list { "\n" } else { "\n\n" });
+            }
+            content.push_str(&block.markdown);
+        }
+        if content.trim().is_empty() {
+            return None;
+        }
+
+        let padding = " ".repeat(marker.len());
+        let mut lines = content.lines();
+        let mut markdown = format!("{}{}", marker, lines.next().unwrap_or_default());
+        for line in lines {
+            markdown.push('\n');
+            if !line.is_empty() {
+                markdown.push_str(&padding);
+                markdown.push_str(line);
+            }
+        }
+        Some(markdown)
+    }
+
+    fn table(&mut self, table: &HtmlElement) -> Option<String> {
+        let mut rows: Vec<&HtmlElement> = Vec::new();
+        collect_rows(table, &mut rows);
+        let mut cells: Vec<Vec<String>> = Vec::new();
+        let mut alignments: Vec<Option<&str>> = Vec::new();
+        for row in &rows {
+            let mut row_cells = Vec::new();
+            for (index, cell) in row
+                .children
+                .iter()
+                .filter(|cell| matches!(cell.tag_name.as_deref(), Some("td" | "th")))
+                .enumerate()
+            {
+                let text = single_line(&self.inline_text(cell)).replace('|', "\\|");
+                row_cells.push(text);
+                if alignments.len() <= index {
+                    alignments.push(cell.attributes.get("align").map(String::as_str));
+                }
+            }
+            cells.push(row_cells);
+        }
+        let columns = cells
+            .iter()
+            .map(Vec::len)
+            .max()
+            .filter(|columns| *columns > 0)?;
+
+        let format_row = |row: &[String]| {
+            let mut line = String::from("|");
+            for index in 0..columns {
+                line.push(' ');
+                line.push_str(row.get(index).map(String::as_str).unwrap_or_default());
+                line.push_str(" |");
+            }
+            line
+        };
+        // GFM tables need a header row, the first row is used as one
+        let mut lines = vec![format_row(&cells[0])];
+        let separator: Vec<&str> = (0..columns)
+            .map(|index| match alignments.get(index).copied().flatten() {
+                Some("left") => ":---",
+                Some("center") => ":---:",
+                Some("right") => "---:",
+                _ => "---",
+            })
+            .collect();
+        lines.push(format!("| {} |", separator.join(" | ")));
+        lines.extend(cells[1..].iter().map(|row| format_row(row)));
+        Some(lines.join("\n"))
+    }
+
+    fn inline_text(&mut self, element: &HtmlElement) -> String {
+        let mut text = String::new();
+        for child in &element.children {
+            self.inline(child, &mut text);
+        }
+        tidy_paragraph(&text)
+    }
+
+    fn inline(&mut self, node: &HtmlElement, output: &mut String) {
+        match node.kind {
+            NodeKind::Text => push_text(output, &escape(&collapse_whitespace(&node.text))),
+            NodeKind::Element => {}
+            _ => return,
+        }
+        let tag_name = node.tag_name.as_deref().unwrap_or_default();
+        if SKIPPED_ELEMENTS.contains(&tag_name) {
+            return;
+        }
+        match tag_name {
+            "br" => output.push_str("\\\n"),
+            "strong" | "b" => self.wrap(node, "**", output),
+            "em" | "i" => self.wrap(node, "*", output),
+            "del" | "s" | "strike" => self.wrap(node, "~~", output),
+            "code" | "kbd" | "samp" | "tt" => {
+                let code = collapse_whitespace(&raw_text(node));
+                if !code.trim().is_empty() {
+                    let fence = "`".repeat(longest_run(&code, '`') + 1);
+                    let padding = if code.starts_with('`') || code.ends_with('`') {
+                        " "
+                    } else {
+                        ""
+                    };
+                    output.push_str(&format!("{0}{1}{2}{1}{0}", fence, padding, code));
+                }
+            }
+            "a" => self.link(node, output),
+            "img" => {
+                if let Some(image) = self.image(node) {
+                    output.push_str(&image);
+                }
+            }
+            "input" => {}
+            _ => {
+                // Blocks can't stay blocks inside inline content such as a table cell, but their
+                // text at least stays apart, including that of a nested table's cells
+                let separate = is_block(node) || matches!(tag_name, "tr" | "td" | "th" | "caption");
+                if separate {
+                    push_text(output, " ");
+                }
+                for child in &node.children {
+                    self.inline(child, output);
+                }
+                if separate {
+                    push_text(output, " ");
+                }
+            }
+        }
+    }
+
+    // Wraps inline content in emphasis markers, keeping surrounding spaces outside of them
+    fn wrap(&mut self, element: &HtmlElement, marker: &str, output: &mut String) {
+        let mut inner = String::new();
+        for child in &element.children {
+            self.inline(child, &mut inner);
+        }
+        let trimmed = inner.trim();
+        if trimmed.is_empty() {
+            push_text(output, &inner);
+            return;
+        }
+        if inner.starts_with(char::is_whitespace) {
+            push_text(output, " ");
+        }
+        output.push_str(&format!("{}{}{}", marker, trimmed, marker));
+        if inner.ends_with(char::is_whitespace) {
+            output.push(' ');
+        }
+    }
+
+    fn link(&mut self, link: &HtmlElement, output: &mut String) {
+        let mut text = String::new();
+        for child in &link.children {
+            self.inline(child, &mut text);
+        }
+        let text = text.trim().to_string();
+        let url = link
+            .attributes
+            .get("href")
+            .map(|href| href.trim())
+            .filter(|href| !href.is_empty() && !is_script_url(href))
+            .map(|href| self.resolve(href));
+        let url = match url {
+            Some(url) if !text.is_empty() => url,
+            _ => {
+                push_text(output, &text);
+                return;
+            }
+        };
+
+        match self.converter.link_style {
+            LinkStyle::Inline => {
+                let title = link
+                    .attributes
+                    .get("title")
+                    .map(|title| format!(" \"{}\"", title.replace('"', "\\\"")))
+                    .unwrap_or_default();
+                output.push_str(&format!("[{}]({}{})", text, destination(&url), title));
+            }
+            LinkStyle::Referenced => {
+                let number = match self.references.iter().position(|known| *known == url) {
+                    Some(index) => index + 1,
+                    None => {
+                        self.references.push(url);
+                        self.references.len()
+                    }
+                };
+                output.push_str(&format!("[{}][{}]", text, number));
+            }
+        }
+    }
+
+    fn image(&self, image: &HtmlElement) -> Option<String> {
+        if self.converter.strip_images {
+            return None;
+        }
+        let src = image
+            .attributes
+            .get("src")
+            .map(|src| src.trim())
+            .filter(|src| !src.is_empty() && !is_script_url(src))?;
+        let alt = image
+            .attributes
+            .get("alt")
+            .map(|alt| escape(&collapse_whitespace(alt)))
+            .unwrap_or_default();
+        let title = image
+            .attributes
+            .get("title")
+            .map(|title| format!(" \"{}\"", title.replace('"', "\\\"")))
+            .unwrap_or_default();
+        Some(format!(
+            "![{}]({}{})",
+            alt.trim(),
+            destination(&self.resolve(src)),
+            title
+        ))
+    }
+
+    fn resolve(&self, url: &str) -> String {
+        self.converter
+            .base_url
+            .as_deref()
+            .and_then(|base| resolve_url(base, url))
+            .unwrap_or_else(|| url.to_string())
+    }
+}
+
+fn is_block(node: &HtmlElement) -> bool {
+    node.is_element()
+        && node
+            .tag_name
+            .as_deref()
+            .is_some_and(|tag_name| BLOCK_ELEMENTS.contains(&tag_name))
+}
+
+// URLs that run code or show a page of their own instead of pointing somewhere. Browsers drop
+// tabs and newlines anywhere in a URL and control characters around it, so `java\tscript:` runs
+// script too. Data URLs are only kept for images.
+fn is_script_url(url: &str) -> bool {
+    let url: String = url
+        .chars()
+        .filter(|c| !matches!(c, '\t' | '\n' | '\r'))
+        .collect();
+    let url = url
+        .trim_start_matches(|c: char| c <= ' ')
+        .to_ascii_lowercase();
+    url.starts_with("javascript:")
+        || url.starts_with("vbscript:")
+        || (url.starts_with("data:") && !url.starts_with("data:image/"))
+}
+
+fn flush_paragraph(paragraph: &mut String, blocks: &mut Vec<Block>) {
+    let text = tidy_paragraph(paragraph);
+    if !text.is_empty() {
+        blocks.push(Block::new(text));
+    }
+    paragraph.clear();
+}
+
+// Trims the spaces HTML whitespace leaves around lines and the paragraph, and escapes what would
+// start a heading, list, quote or thematic break at the start of a line
+fn tidy_paragraph(text: &str) -> String {
+    let lines: Vec<String> = text
+        .split('\n')
+        .map(|line| escape_line_start(line.trim()))
+        .collect();
+    lines
+        .join("\n")
+        .trim_matches(|c| c == ' ' || c == '\n')
+        .to_string()
+}
+
+fn escape_line_start(line: &str) -> String {
+    let mut chars = line.chars();
+    let marker = match chars.next() {
+        // Emphasis starts with `*` too, only `*` followed by a space is a bullet
+        Some('*') => chars.next().is_none_or(char::is_whitespace).then_some(0),
+        Some('#' | '-' | '+' | '=' | '>') => Some(0),
+        Some(c) if c.is_ascii_digit() => {
+            // `1.` or `1)` followed by a space starts an ordered list, escape the punctuation
+            let digits = line.len() - line.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+            let rest = &line[digits..];
+            let is_marker = digits <= MAX_LIST_NUMBER_DIGITS
+                && rest.starts_with(['.', ')'])
+                && rest[1..].chars().next().is_none_or(char::is_whitespace);
+            is_marker.then_some(digits)
+        }
+        _ => None,
+    };
+    match marker {
+        Some(index) => format!("{}\\{}", &line[..index], &line[index..]),
+        None => line.to_string(),
+    }
+}
+
+// Joins the lines of inline Markdown with spaces for the places that can't break lines, dropping
+// the backslashes of hard line breaks
+fn single_line(text: &str) -> String {
+    text.split_inclusive('\n')
+        .map(|line| line.strip_suffix("\\\n").unwrap_or(line).trim())
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn join_blocks(blocks: &[Block]) -> String {
+    blocks
+        .iter()
+        .map(|block| block.markdown.as_str())
+        .filter(|markdown| !markdown.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+fn code_block(pre: &HtmlElement) -> String {
+    let code = raw_text(pre);
+    let code = code.strip_suffix('\n').unwrap_or(&code);
+    // `class="language-rust"` on the `<code>` inside, as most highlighters write it
+    let language = find_first_element(pre)
+        .filter(|code| code.tag_name.as_deref() == Some("code"))
+        .and_then(|code| code.attributes.get("class"))
+        .and_then(|classes| {
+            classes.split_whitespace().find_map(|class| {
+                class
+                    .strip_prefix("language-")
+                    .or_else(|| class.strip_prefix("lang-"))
+            })
+        })
+        .unwrap_or_default();
+    let fence = "`".repeat(longest_run(code, '`').max(2) + 1);
+    format!("{}{}\n{}\n{}", fence, language, code, fence)
+}
+
+fn collect_rows<'a>(element: &'a HtmlElement, rows: &mut Vec<&'a HtmlElement>) {
+    for child in &element.children {
+        match child.tag_name.as_deref() {
+            Some("tr") => rows.push(child),
+            Some("thead" | "tbody" | "tfoot") => collect_rows(child, rows),
+            _ => {}
+        }
+    }
+}
+
+fn find_first_element(element: &HtmlElement) -> Option<&HtmlElement> {
+    element.children.iter().find(|child| child.is_element())
+}
+
+// Text of all descendants as written, for code
+fn raw_text(element: &HtmlElement) -> String {
+    let mut text = String::new();
+    for child in &element.children {
+        match child.kind {
+            NodeKind::Text => text.push_str(&child.text),
+            NodeKind::Element if child.tag_name.as_deref() == Some("br") => text.push('\n'),
+            NodeKind::Element => text.push_str(&raw_text(child)),
+            _ => {}
+        }
+    }
+    text
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    let mut collapsed = String::with_capacity(text.len());
+    let mut in_whitespace = false;
+    for c in text.chars() {
+        if c.is_whitespace() && c != '\u{a0}' {
+            if !in_whitespace {
+                collapsed.push(' ');
+            }
+            in_whitespace = true;
+        } else {
+            collapsed.push(c);
+            in_whitespace = false;
+        }
+    }
+    collapsed
+}
+
+// Appends text without doubling up the space between two runs
+fn push_text(output: &mut String, text: &str) {
+    if text.starts_with(' ') && (output.is_empty() || output.ends_with([' ', '\n'])) {
+        output.push_str(&text[1..]);
+    } else {
+        output.push_str(text);
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '*' | '_' | '`' | '[' | ']' | '<' | '>' | '~') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn destination(url: &str) -> String {
+    if url.contains([' ', '(', ')']) {
+        format!("<{}>", url)
+    } else {
+        url.to_string()
+    }
+}
+
+fn indent(text: &str, padding: &str) -> String {
+    text.lines()
+        .map(|line| match line {
+            "" => String::new(),
+            line => format!("{}{}", padding, line),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn longest_run(text: &str, target: char) -> usize {
+    let mut longest = 0;
+    let mut current = 0;
+    for c in text.chars() {
+        current = if c == target { current + 1 } else { 0 };
+        longest = longest.max(current);
+    }
+    longest
+}
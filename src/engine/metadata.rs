@@ -0,0 +1,346 @@
+use crate::engine::html::HtmlElement;
+use crate::utils::{document_base_url, resolve_url};
+use std::collections::HashMap;
+
+// Link types of feeds announced with `<link rel="alternate">`
+const FEED_TYPES: &[&str] = &[
+    "application/rss+xml",
+    "application/atom+xml",
+    "application/feed+json",
+    "application/rdf+xml",
+];
+
+// What a page says about itself in its `<head>`: meta tags, OpenGraph and Twitter card fields
+// and link relations. URLs are resolved against the page, honouring `<base href>`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PageMetadata {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub keywords: Vec<String>,
+    pub author: Option<String>,
+    pub robots: Option<String>,
+    // `<html lang>`, or the Content-Language given in a meta tag
+    pub language: Option<String>,
+    pub canonical_url: Option<String>,
+    // The first `rel="icon"`, then an Apple touch icon, then `/favicon.ico` which browsers try
+    // when a page names none
+    pub favicon: Option<String>,
+    pub icons: Vec<PageLink>,
+    // Translations of the page from `<link rel="alternate" hreflang>`
+    pub alternates: Vec<PageLink>,
+    // RSS, Atom and JSON feeds from `<link rel="alternate" type>`
+    pub feeds: Vec<PageLink>,
+    pub next: Option<String>,
+    pub prev: Option<String>,
+    pub open_graph: OpenGraph,
+    pub twitter: TwitterCard,
+    // Every `<meta>` content by its lowercased name, property or http-equiv, the first one winning
+    pub meta: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PageLink {
+    pub url: String,
+    pub media_type: Option<String>,
+    pub hreflang: Option<String>,
+    pub title: Option<String>,
+    pub sizes: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OpenGraph {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub url: Option<String>,
+    pub site_name: Option<String>,
+    // `og:type`, like "article" or "website"
+    pub object_type: Option<String>,
+    pub locale: Option<String>,
+    pub images: Vec<OpenGraphMedia>,
+    pub videos: Vec<OpenGraphMedia>,
+    pub audio: Vec<OpenGraphMedia>,
+}
+
+// An `og:image`, `og:video` or `og:audio` along with the structured properties following it
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OpenGraphMedia {
+    pub url: String,
+    pub secure_url: Option<String>,
+    pub media_type: Option<String>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub alt: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TwitterCard {
+    // "summary", "summary_large_image", "app" or "player"
+    pub card: Option<String>,
+    pub site: Option<String>,
+    pub creator: Option<String>,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub image: Option<String>,
+    pub image_alt: Option<String>,
+}
+
+impl PageMetadata {
+    // Extracts the metadata of a parsed page fetched from `url`
+    pub fn from_document(document: &HtmlElement, url: &str) -> Self {
+        let mut extractor = Extractor {
+            base_url: document_base_url(document, url),
+            metadata: PageMetadata::default(),
+        };
+        extractor.visit(document);
+
+        let Extractor {
+            base_url,
+            mut metadata,
+        } = extractor;
+        let meta = |name: &str| metadata.meta.get(name).cloned();
+        let description = meta("description");
+        let author = meta("author");
+        let robots = meta("robots");
+        let keywords = meta("keywords");
+        let content_language = meta("content-language");
+
+        metadata.description = description;
+        metadata.author = author;
+        metadata.robots = robots;
+        metadata.keywords = keywords
+            .map(|keywords| {
+                keywords
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|keyword| !keyword.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default();
+        metadata.language = metadata.language.or(content_language);
+        metadata.favicon = metadata
+            .favicon
+            .or_else(|| metadata.icons.first().map(|icon| icon.url.clone()))
+            .or_else(|| resolve_url(&base_url, "/favicon.ico"));
+        metadata
+    }
+}
+
+struct Extractor {
+    base_url: String,
+    metadata: PageMetadata,
+}
+
+impl Extractor {
+    fn visit(&mut self, element: &HtmlElement) {
+        match element.tag_name.as_deref() {
+            Some("html") => {
+                self.metadata.language = attribute(element, "lang");
+            }
+            Some("title") if self.metadata.title.is_none() => {
+                let title = element
+                    .text
+                    .split_whitespace()
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                self.metadata.title = Some(title).filter(|title| !title.is_empty());
+            }
+            Some("meta") => self.meta(element),
+            Some("link") => self.link(element),
+            // Pagination is sometimes only marked on the links themselves
+            Some("a") => {
+                let href = self.href(element);
+                for rel in rels(element) {
+                    let slot = match rel.as_str() {
+                        "next" => &mut self.metadata.next,
+                        "prev" | "previous" => &mut self.metadata.prev,
+                        _ => continue,
+                    };
+                    if slot.is_none() {
+                        slot.clone_from(&href);
+                    }
+                }
+            }
+            // Nothing in an SVG or template describes the page
+            Some("svg") | Some("template") => return,
+            _ => {}
+        }
+        for child in &element.children {
+            if child.is_element() {
+                self.visit(child);
+            }
+        }
+    }
+
+    fn meta(&mut self, element: &HtmlElement) {
+        let Some(content) = attribute(element, "content") else {
+            return;
+        };
+        for key in ["name", "property", "http-equiv"] {
+            let Some(names) = element.attributes.get(key) else {
+                continue;
+            };
+            for name in names.split_whitespace() {
+                let name = name.to_ascii_lowercase();
+                self.open_graph(&name, &content);
+                self.twitter(&name, &content);
+                self.metadata
+                    .meta
+                    .entry(name)
+                    .or_insert_with(|| content.clone());
+            }
+        }
+    }
+
+    fn open_graph(&mut self, name: &str, content: &str) {
+        let Some(property) = name.strip_prefix("og:") else {
+            return;
+        };
+        let url = self.url(content);
+        let open_graph = &mut self.metadata.open_graph;
+        let field = match property {
+            "title" => &mut open_graph.title,
+            "description" => &mut open_graph.description,
+            "url" => {
+                open_graph.url.get_or_insert(url);
+                return;
+            }
+            "site_name" => &mut open_graph.site_name,
+            "type" => &mut open_graph.object_type,
+            "locale" => &mut open_graph.locale,
+            _ => {
+                let (kind, detail) = property.split_once(':').unwrap_or((property, ""));
+                let media = match kind {
+                    "image" => &mut open_graph.images,
+                    "video" => &mut open_graph.videos,
+                    "audio" => &mut open_graph.audio,
+                    _ => return,
+                };
+                media_property(media, detail, content, url);
+                return;
+            }
+        };
+        field.get_or_insert_with(|| content.to_string());
+    }
+
+    fn twitter(&mut self, name: &str, content: &str) {
+        let Some(property) = name.strip_prefix("twitter:") else {
+            return;
+        };
+        let value = match property {
+            "image" | "image:src" => self.url(content),
+            _ => content.to_string(),
+        };
+        let twitter = &mut self.metadata.twitter;
+        let field = match property {
+            "card" => &mut twitter.card,
+            "site" => &mut twitter.site,
+            "creator" => &mut twitter.creator,
+            "title" => &mut twitter.title,
+            "description" => &mut twitter.description,
+            "image" | "image:src" => &mut twitter.image,
+            "image:alt" => &mut twitter.image_alt,
+            _ => return,
+        };
+        field.get_or_insert(value);
+    }
+
+    fn link(&mut self, element: &HtmlElement) {
+        let Some(url) = self.href(element) else {
+            return;
+        };
+        let link = PageLink {
+            url: url.clone(),
+            media_type: attribute(element, "type").map(|media_type| media_type.to_lowercase()),
+            hreflang: attribute(element, "hreflang"),
+            title: attribute(element, "title"),
+            sizes: attribute(element, "sizes"),
+        };
+        let rels = rels(element);
+        let metadata = &mut self.metadata;
+        for rel in &rels {
+            match rel.as_str() {
+                "canonical" if metadata.canonical_url.is_none() => {
+                    metadata.canonical_url = Some(url.clone())
+                }
+                "next" if metadata.next.is_none() => metadata.next = Some(url.clone()),
+                "prev" | "previous" if metadata.prev.is_none() => metadata.prev = Some(url.clone()),
+                "alternate" if link.hreflang.is_some() => metadata.alternates.push(link.clone()),
+                "alternate"
+                    if link
+                        .media_type
+                        .as_deref()
+                        .is_some_and(|media_type| FEED_TYPES.contains(&media_type)) =>
+                {
+                    metadata.feeds.push(link.clone())
+                }
+                _ => {}
+            }
+        }
+        // "shortcut icon" is one icon, listed once
+        if rels.iter().any(|rel| rel == "icon") {
+            metadata.favicon.get_or_insert_with(|| url.clone());
+            metadata.icons.push(link);
+        } else if rels
+            .iter()
+            .any(|rel| rel == "apple-touch-icon" || rel == "apple-touch-icon-precomposed")
+        {
+            metadata.icons.push(link);
+        }
+    }
+
+    fn href(&self, element: &HtmlElement) -> Option<String> {
+        attribute(element, "href").and_then(|href| resolve_url(&self.base_url, &href))
+    }
+
+    fn url(&self, url: &str) -> String {
+        resolve_url(&self.base_url, url).unwrap_or_else(|| url.trim().to_string())
+    }
+}
+
+fn media_property(media: &mut Vec<OpenGraphMedia>, detail: &str, content: &str, url: String) {
+    // `og:image:url` repeats the `og:image` before it, or stands in for it
+    let starts_media =
+        detail.is_empty() || (detail == "url" && media.last().is_none_or(|last| last.url != url));
+    if starts_media {
+        media.push(OpenGraphMedia {
+            url,
+            ..OpenGraphMedia::default()
+        });
+        return;
+    }
+    // Structured properties belong to the media declared before them
+    let Some(last) = media.last_mut() else {
+        return;
+    };
+    match detail {
+        "secure_url" => last.secure_url = Some(url),
+        "type" => last.media_type = Some(content.to_string()),
+        "width" => last.width = content.trim().parse().ok(),
+        "height" => last.height = content.trim().parse().ok(),
+        "alt" => last.alt = Some(content.to_string()),
+        _ => {}
+    }
+}
+
+// A trimmed, non-empty attribute value
+fn attribute(element: &HtmlElement, name: &str) -> Option<String> {
+    element
+        .attributes
+        .get(name)
+        .map(|value| value.trim())
+        .filter(|value| !value.is_empty())
+        .map(str::to_string)
+}
+
+fn rels(element: &HtmlElement) -> Vec<String> {
+    element
+        .attributes
+        .get("rel")
+        .map(|rel| {
+            rel.split_whitespace()
+                .map(str::to_ascii_lowercase)
+                .collect()
+        })
+        .unwrap_or_default()
+}
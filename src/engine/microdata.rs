@@ -0,0 +1,240 @@
+use crate::engine::html::HtmlElement;
+use crate::engine::jsonld::JsonLd;
+use crate::engine::readability::text_content;
+use crate::utils::{document_base_url, resolve_url};
+use serde_json::{Map, Value};
+use std::collections::{HashMap, HashSet};
+
+// Extracts `itemscope`/`itemprop` microdata following the WHATWG algorithm: properties are
+// gathered from the item's subtree and the elements its `itemref` points to, nested items become
+// nested objects, and values are read by element type (`meta@content`, `a@href`, `img@src`,
+// `time@datetime`, ...). Items come out in the JSON-LD shape, the vocabulary of the `itemtype` as
+// `@context` and the rest as `@type`, so they work with `JsonLd`'s lookups. A nested item from
+// another vocabulary gets its own `@context`.
+pub struct MicrodataParser {
+    base_url: Option<String>,
+}
+
+impl Default for MicrodataParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MicrodataParser {
+    pub fn new() -> Self {
+        MicrodataParser { base_url: None }
+    }
+
+    // The page's URL, which URL properties are resolved against. Without it they're kept as
+    // written.
+    pub fn base_url(mut self, base_url: &str) -> Self {
+        self.base_url = Some(base_url.to_string());
+        self
+    }
+
+    // The top-level items of the document, those with `itemscope` but no `itemprop`
+    pub fn parse_microdata(&self, document: &HtmlElement) -> JsonLd {
+        let mut context = Context {
+            base_url: self
+                .base_url
+                .as_ref()
+                .map(|base_url| document_base_url(document, base_url)),
+            ids: HashMap::new(),
+            order: HashMap::new(),
+        };
+        index(document, &mut context);
+
+        let mut top_level = Vec::new();
+        collect_top_level(document, &mut top_level);
+        let nodes = top_level
+            .into_iter()
+            .map(|element| context.item(element, None, &mut Vec::new()))
+            .collect();
+        JsonLd { nodes }
+    }
+}
+
+impl HtmlElement {
+    // The microdata items in the page, see `MicrodataParser`
+    pub fn microdata(&self) -> JsonLd {
+        MicrodataParser::new().parse_microdata(self)
+    }
+}
+
+struct Context<'a> {
+    base_url: Option<String>,
+    // The first element with each id, for `itemref`
+    ids: HashMap<&'a str, &'a HtmlElement>,
+    // Document order of every element, properties are listed in it
+    order: HashMap<*const HtmlElement, usize>,
+}
+
+impl<'a> Context<'a> {
+    fn item(
+        &self,
+        element: &'a HtmlElement,
+        vocabulary: Option<&str>,
+        // Items being built further up, an `itemref` back to one of them is skipped
+        ancestors: &mut Vec<*const HtmlElement>,
+    ) -> Value {
+        let mut item = Map::new();
+        let types: Vec<&str> = element
+            .attributes
+            .get("itemtype")
+            .map(|types| types.split_whitespace().collect())
+            .unwrap_or_default();
+        let item_vocabulary = types.first().map(|first| vocabulary_of(first));
+        if let Some(item_vocabulary) = item_vocabulary.filter(|item| Some(*item) != vocabulary) {
+            item.insert("@context".to_string(), item_vocabulary.into());
+        }
+        let vocabulary = item_vocabulary.or(vocabulary);
+        if !types.is_empty() {
+            let types: Vec<Value> = types
+                .iter()
+                .map(|item_type| compact(item_type, vocabulary).into())
+                .collect();
+            item.insert("@type".to_string(), one_or_many(types));
+        }
+        if let Some(id) = element.attributes.get("itemid") {
+            item.insert("@id".to_string(), self.url(id).into());
+        }
+
+        ancestors.push(element);
+        for property in self.properties(element) {
+            let value = if property.attributes.contains_key("itemscope") {
+                if ancestors.contains(&(property as *const HtmlElement)) {
+                    continue;
+                }
+                self.item(property, vocabulary, ancestors)
+            } else {
+                self.value(property)
+            };
+            for name in property.attributes["itemprop"].split_whitespace() {
+                insert_value(&mut item, compact(name, vocabulary), value.clone());
+            }
+        }
+        ancestors.pop();
+        Value::Object(item)
+    }
+
+    // The elements holding the item's properties, in document order
+    fn properties(&self, root: &'a HtmlElement) -> Vec<&'a HtmlElement> {
+        let mut pending: Vec<&HtmlElement> = root
+            .children
+            .iter()
+            .filter(|child| child.is_element())
+            .collect();
+        if let Some(references) = root.attributes.get("itemref") {
+            pending.extend(
+                references
+                    .split_whitespace()
+                    .filter_map(|id| self.ids.get(id).copied()),
+            );
+        }
+
+        let mut visited: HashSet<*const HtmlElement> = HashSet::from([root as *const _]);
+        let mut results = Vec::new();
+        while let Some(current) = pending.pop() {
+            if !visited.insert(current) {
+                continue;
+            }
+            if current
+                .attributes
+                .get("itemprop")
+                .is_some_and(|names| !names.trim().is_empty())
+            {
+                results.push(current);
+            }
+            if !current.attributes.contains_key("itemscope") {
+                pending.extend(current.children.iter().filter(|child| child.is_element()));
+            }
+        }
+        results.sort_by_key(|element| self.order.get(&(*element as *const _)).copied());
+        results
+    }
+
+    fn value(&self, element: &HtmlElement) -> Value {
+        let attribute = |name: &str| element.attributes.get(name).cloned().unwrap_or_default();
+        let tag_name = element.tag_name.as_deref().unwrap_or_default();
+        match tag_name.to_ascii_lowercase().as_str() {
+            "meta" => attribute("content").into(),
+            "audio" | "embed" | "iframe" | "img" | "source" | "track" | "video" => {
+                self.url(&attribute("src")).into()
+            }
+            "a" | "area" | "link" => self.url(&attribute("href")).into(),
+            "object" => self.url(&attribute("data")).into(),
+            "data" | "meter" => attribute("value").into(),
+            "time" if element.attributes.contains_key("datetime") => attribute("datetime").into(),
+            _ => text_content(element).into(),
+        }
+    }
+
+    fn url(&self, url: &str) -> String {
+        match (&self.base_url, url.trim()) {
+            (Some(base_url), url) if !url.is_empty() => {
+                resolve_url(base_url, url).unwrap_or_else(|| url.to_string())
+            }
+            (_, url) => url.to_string(),
+        }
+    }
+}
+
+fn index<'a>(element: &'a HtmlElement, context: &mut Context<'a>) {
+    let position = context.order.len();
+    context.order.insert(element, position);
+    if let Some(id) = element.attributes.get("id") {
+        context.ids.entry(id.as_str()).or_insert(element);
+    }
+    for child in &element.children {
+        if child.is_element() {
+            index(child, context);
+        }
+    }
+}
+
+fn collect_top_level<'a>(element: &'a HtmlElement, items: &mut Vec<&'a HtmlElement>) {
+    if element.attributes.contains_key("itemscope") && !element.attributes.contains_key("itemprop")
+    {
+        items.push(element);
+    }
+    for child in &element.children {
+        collect_top_level(child, items);
+    }
+}
+
+// "https://schema.org/" for "https://schema.org/Product"
+pub(crate) fn vocabulary_of(name: &str) -> &str {
+    match name.rfind(['/', '#']) {
+        Some(end) => &name[..=end],
+        None => "",
+    }
+}
+
+// Names in the vocabulary lose its prefix, like JSON-LD terms under an `@context`
+pub(crate) fn compact<'a>(name: &'a str, vocabulary: Option<&str>) -> &'a str {
+    vocabulary
+        .filter(|vocabulary| !vocabulary.is_empty())
+        .and_then(|vocabulary| name.strip_prefix(vocabulary))
+        .filter(|name| !name.is_empty())
+        .unwrap_or(name)
+}
+
+// A property with several values becomes an array, as in JSON-LD
+pub(crate) fn insert_value(item: &mut Map<String, Value>, name: &str, value: Value) {
+    match item.get_mut(name) {
+        Some(Value::Array(values)) => values.push(value),
+        Some(existing) => *existing = Value::Array(vec![existing.take(), value]),
+        None => {
+            item.insert(name.to_string(), value);
+        }
+    }
+}
+
+pub(crate) fn one_or_many(mut values: Vec<Value>) -> Value {
+    if values.len() == 1 {
+        values.remove(0)
+    } else {
+        Value::Array(values)
+    }
+}
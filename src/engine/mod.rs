@@ -1,5 +1,22 @@
 pub mod html;
 pub mod client;
+pub mod cookies;
 pub mod selector;
+pub mod form;
+pub mod embedded;
+pub mod streaming;
+pub mod xml;
+pub mod feed;
+pub mod edit;
+pub mod serialize;
+pub mod sanitize;
+pub mod markdown;
+pub mod text;
+pub mod readability;
+pub mod jsonld;
+pub mod microdata;
+pub mod rdfa;
+pub mod metadata;
+pub mod document;
 #[cfg(feature = "captcha")]
 pub mod captcha;
\ No newline at end of file
@@ -0,0 +1,183 @@
+use crate::engine::html::HtmlElement;
+use crate::engine::jsonld::JsonLd;
+use crate::engine::microdata::{compact, insert_value, one_or_many};
+use crate::engine::readability::text_content;
+use crate::utils::{document_base_url, resolve_url};
+use serde_json::{Map, Value};
+
+// Extracts RDFa Lite: `vocab` sets the vocabulary, `typeof` starts an item, `property` adds a
+// property to the innermost item around it, and `resource` names the item. A `resource` without
+// `typeof` or `property` starts an untyped item, while next to `property` it is the property's
+// value. Items come out in the JSON-LD shape like microdata does, with an `@context` wherever the
+// vocabulary changes and absolute IRIs for terms from another vocabulary than their item's.
+// Properties outside of any item, like Open Graph's `<meta property="og:title">`, describe the
+// page itself and are gathered into a node of their own, first in the list. Prefixed names such
+// as `og:title` are kept as written: `prefix` declarations aren't applied, so a name reads the
+// same whatever IRI a page maps its prefix to.
+pub struct RdfaParser {
+    base_url: Option<String>,
+}
+
+impl Default for RdfaParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RdfaParser {
+    pub fn new() -> Self {
+        RdfaParser { base_url: None }
+    }
+
+    // The page's URL, which URL values are resolved against and which identifies the page's own
+    // node. Without it URLs are kept as written.
+    pub fn base_url(mut self, base_url: &str) -> Self {
+        self.base_url = Some(base_url.to_string());
+        self
+    }
+
+    pub fn parse_rdfa(&self, document: &HtmlElement) -> JsonLd {
+        let base_url = self
+            .base_url
+            .as_ref()
+            .map(|base_url| document_base_url(document, base_url));
+        let mut walker = Walker {
+            base_url: base_url.as_deref(),
+            nodes: Vec::new(),
+        };
+        let mut page = Map::new();
+        walker.walk(document, None, None, &mut page);
+
+        let mut nodes = walker.nodes;
+        if !page.is_empty() {
+            if let Some(base_url) = base_url {
+                page.insert("@id".to_string(), base_url.into());
+            }
+            nodes.insert(0, Value::Object(page));
+        }
+        JsonLd { nodes }
+    }
+}
+
+impl HtmlElement {
+    // The RDFa Lite items in the page, see `RdfaParser`
+    pub fn rdfa(&self) -> JsonLd {
+        RdfaParser::new().parse_rdfa(self)
+    }
+}
+
+struct Walker<'a> {
+    base_url: Option<&'a str>,
+    nodes: Vec<Value>,
+}
+
+impl Walker<'_> {
+    // `vocabulary` is the one in scope, `context` the one `item`'s terms are relative to
+    fn walk(
+        &mut self,
+        element: &HtmlElement,
+        vocabulary: Option<&str>,
+        context: Option<&str>,
+        item: &mut Map<String, Value>,
+    ) {
+        for child in element.children.iter().filter(|child| child.is_element()) {
+            let vocabulary = match child.attributes.get("vocab") {
+                // An empty vocab clears it
+                Some(vocab) => Some(vocab.trim()).filter(|vocab| !vocab.is_empty()),
+                None => vocabulary,
+            };
+            let properties: Vec<&str> = child
+                .attributes
+                .get("property")
+                .map(|names| names.split_whitespace().collect())
+                .unwrap_or_default();
+
+            let types = child.attributes.get("typeof");
+            let resource = child.attributes.get("resource");
+            if types.is_none() && (resource.is_none() || !properties.is_empty()) {
+                if !properties.is_empty() {
+                    let value = self.value(child);
+                    for name in &properties {
+                        insert_value(item, &term(name, vocabulary, context), value.clone());
+                    }
+                }
+                self.walk(child, vocabulary, context, item);
+                continue;
+            }
+
+            let mut nested = Map::new();
+            // Top-level items always say their vocabulary, nested ones when it changes
+            let nested_context = if properties.is_empty() || vocabulary != context {
+                if let Some(vocabulary) = vocabulary {
+                    nested.insert("@context".to_string(), vocabulary.into());
+                } else if context.is_some() && !properties.is_empty() {
+                    nested.insert("@context".to_string(), Value::Null);
+                }
+                vocabulary
+            } else {
+                context
+            };
+            let types: Vec<Value> = types
+                .into_iter()
+                .flat_map(|types| types.split_whitespace())
+                .map(|item_type| term(item_type, vocabulary, nested_context).into())
+                .collect();
+            if !types.is_empty() {
+                nested.insert("@type".to_string(), one_or_many(types));
+            }
+            if let Some(resource) = resource {
+                nested.insert("@id".to_string(), self.url(resource).into());
+            }
+            self.walk(child, vocabulary, nested_context, &mut nested);
+
+            if properties.is_empty() {
+                self.nodes.push(Value::Object(nested));
+            } else {
+                for name in &properties {
+                    insert_value(
+                        item,
+                        &term(name, vocabulary, context),
+                        Value::Object(nested.clone()),
+                    );
+                }
+            }
+        }
+    }
+
+    fn value(&self, element: &HtmlElement) -> Value {
+        let attributes = &element.attributes;
+        if let Some(content) = attributes.get("content") {
+            return content.clone().into();
+        }
+        if let Some(url) = ["resource", "href", "src"]
+            .iter()
+            .find_map(|name| attributes.get(*name))
+        {
+            return self.url(url).into();
+        }
+        if element.tag_name.as_deref() == Some("time") {
+            if let Some(datetime) = attributes.get("datetime") {
+                return datetime.clone().into();
+            }
+        }
+        text_content(element).into()
+    }
+
+    fn url(&self, url: &str) -> String {
+        let url = url.trim();
+        self.base_url
+            .filter(|_| !url.is_empty())
+            .and_then(|base_url| resolve_url(base_url, url))
+            .unwrap_or_else(|| url.to_string())
+    }
+}
+
+// A term of the vocabulary in scope, relative to the `@context` its item has. Absolute and
+// prefixed names are kept as written.
+fn term(name: &str, vocabulary: Option<&str>, context: Option<&str>) -> String {
+    let absolute = match vocabulary {
+        Some(vocabulary) if !name.contains(':') => format!("{}{}", vocabulary, name),
+        _ => name.to_string(),
+    };
+    compact(&absolute, context).to_string()
+}
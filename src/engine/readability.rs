@@ -0,0 +1,663 @@
+use crate::engine::html::{HtmlElement, NodeKind};
+use crate::engine::text::is_hidden;
+use crate::utils::{document_base_url, find_element, resolve_url};
+use std::collections::HashMap;
+
+// Elements that are never part of an article's body
+const REMOVED_ELEMENTS: &[&str] = &[
+    "script", "style", "noscript", "template", "iframe", "object", "embed", "svg", "canvas",
+    "button", "input", "select", "textarea", "nav", "footer", "aside", "link", "meta",
+];
+
+// Class and id fragments of page furniture, dropped before scoring unless they also match
+// MAYBE_CANDIDATES
+const UNLIKELY_CANDIDATES: &[&str] = &[
+    "ad-break",
+    "agegate",
+    "banner",
+    "breadcrumb",
+    "combx",
+    "comment",
+    "community",
+    "cookie",
+    "cover-wrap",
+    "disqus",
+    "extra",
+    "footer",
+    "gdpr",
+    "header",
+    "legends",
+    "menu",
+    "nav",
+    "pager",
+    "pagination",
+    "popup",
+    "related",
+    "remark",
+    "replies",
+    "rss",
+    "share",
+    "shoutbox",
+    "sidebar",
+    "skyscraper",
+    "social",
+    "sponsor",
+    "supplemental",
+];
+
+const MAYBE_CANDIDATES: &[&str] = &[
+    "and", "article", "body", "column", "content", "main", "shadow",
+];
+
+const UNLIKELY_ROLES: &[&str] = &[
+    "menu",
+    "menubar",
+    "complementary",
+    "navigation",
+    "alert",
+    "alertdialog",
+    "dialog",
+];
+
+const POSITIVE_HINTS: &[&str] = &[
+    "article", "body", "content", "entry", "hentry", "h-entry", "main", "page", "post", "text",
+    "blog", "story",
+];
+
+const NEGATIVE_HINTS: &[&str] = &[
+    "-ad-",
+    "hidden",
+    "banner",
+    "combx",
+    "comment",
+    "com-",
+    "contact",
+    "foot",
+    "masthead",
+    "media",
+    "outbrain",
+    "promo",
+    "related",
+    "scroll",
+    "share",
+    "shoutbox",
+    "sidebar",
+    "skyscraper",
+    "sponsor",
+    "shopping",
+    "tags",
+    "tool",
+    "widget",
+];
+
+// Blocks whose presence means a `<div>` isn't just a wrapped paragraph
+const BLOCK_CHILDREN: &[&str] = &[
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "dl",
+    "div",
+    "figure",
+    "footer",
+    "form",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "table",
+    "ul",
+];
+
+const TITLE_SEPARATORS: &[&str] = &[" | ", " - ", " – ", " — ", " :: ", " / ", " » "];
+
+// The main content of an article page and what the page says about it
+#[derive(Debug, Clone)]
+pub struct Article {
+    pub title: Option<String>,
+    pub byline: Option<String>,
+    // As written in the page, usually an ISO 8601 timestamp
+    pub published: Option<String>,
+    pub lead_image: Option<String>,
+    pub excerpt: Option<String>,
+    // A `<div>` holding the article body with the boilerplate around it removed
+    pub content: HtmlElement,
+}
+
+impl Article {
+    // The article body as laid out plain text
+    pub fn text(&self) -> String {
+        self.content.rendered_text()
+    }
+}
+
+// Finds the main content of article pages. Blocks of text are scored by length and comma count,
+// the scores flow up to their ancestors, and the best ancestor, adjusted for link density and
+// class/id hints, is taken as the article along with siblings that score nearly as well.
+// Navigation, footers, sidebars, comment sections and link lists are left out.
+pub struct Readability {
+    base_url: Option<String>,
+    min_text_length: usize,
+}
+
+impl Default for Readability {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Readability {
+    pub fn new() -> Self {
+        Readability {
+            base_url: None,
+            min_text_length: 250,
+        }
+    }
+
+    // The page's URL, which the lead image URL is resolved against
+    pub fn base_url(mut self, base_url: &str) -> Self {
+        self.base_url = Some(base_url.to_string());
+        self
+    }
+
+    // Pages whose best candidate has less text than this aren't considered articles
+    pub fn min_text_length(mut self, min_text_length: usize) -> Self {
+        self.min_text_length = min_text_length;
+        self
+    }
+
+    pub fn extract(&self, document: &HtmlElement) -> Option<Article> {
+        let body = find_element(document, &|element| {
+            element.tag_name.as_deref() == Some("body")
+        })
+        .unwrap_or(document);
+
+        // Page furniture is stripped by its class names first, which can take the article with
+        // it on pages with unlucky naming, so the untouched page is tried next
+        let content = [true, false]
+            .iter()
+            .map(|&strip_unlikely| self.grab_content(body, strip_unlikely))
+            .find(|content| text_length(content) >= self.min_text_length)?;
+
+        let metadata = metadata(document);
+        let mut article = Article {
+            title: title(document, &metadata),
+            byline: byline(body, &metadata),
+            published: [
+                "article:published_time",
+                "datepublished",
+                "dc.date",
+                "dcterms.created",
+                "date",
+            ]
+            .iter()
+            .find_map(|name| metadata.get(*name).cloned())
+            .or_else(|| {
+                find_element(body, &|element| {
+                    element.tag_name.as_deref() == Some("time")
+                        && element.attributes.contains_key("datetime")
+                })
+                .and_then(|time| time.attributes.get("datetime").cloned())
+            }),
+            lead_image: ["og:image", "og:image:url", "twitter:image"]
+                .iter()
+                .find_map(|name| metadata.get(*name).cloned())
+                .or_else(|| {
+                    find_element(&content, &|element| {
+                        element.tag_name.as_deref() == Some("img")
+                            && element.attributes.contains_key("src")
+                    })
+                    .and_then(|img| img.attributes.get("src").cloned())
+                }),
+            excerpt: ["og:description", "description", "twitter:description"]
+                .iter()
+                .find_map(|name| metadata.get(*name).cloned())
+                .or_else(|| {
+                    find_element(&content, &|element| {
+                        // Skipping datelines and captions
+                        element.tag_name.as_deref() == Some("p") && text_length(element) >= 25
+                    })
+                    .map(text_content)
+                }),
+            content,
+        };
+
+        if let Some(title) = &article.title {
+            remove_title_headings(&mut article.content, title);
+        }
+        if let (Some(base_url), Some(image)) = (&self.base_url, &article.lead_image) {
+            let base_url = document_base_url(document, base_url);
+            article.lead_image = resolve_url(&base_url, image).or(article.lead_image);
+        }
+        Some(article)
+    }
+
+    fn grab_content(&self, body: &HtmlElement, strip_unlikely: bool) -> HtmlElement {
+        let mut root = body.clone();
+        prepare(&mut root, strip_unlikely);
+
+        let mut candidates = HashMap::new();
+        score_paragraphs(&root, &root, &mut Vec::new(), &mut candidates);
+
+        let top = candidates
+            .iter()
+            .map(|(path, score)| (path, score * (1.0 - link_density(node_at(&root, path)))))
+            .max_by(|(a_path, a), (b_path, b)| {
+                // Ties go to the outermost candidate, then the first in document order
+                a.total_cmp(b)
+                    .then_with(|| b_path.len().cmp(&a_path.len()))
+                    .then_with(|| b_path.cmp(a_path))
+            })
+            .map(|(path, score)| (path.clone(), score));
+
+        let mut content = HtmlElement::new_element("div");
+        match top {
+            Some((path, score)) if !path.is_empty() => {
+                let parent = node_at(&root, &path[..path.len() - 1]);
+                let threshold = (score * 0.2).max(10.0);
+                for (index, sibling) in parent.children.iter().enumerate() {
+                    let mut sibling_path = path[..path.len() - 1].to_vec();
+                    sibling_path.push(index);
+                    let sibling_score = candidates
+                        .get(&sibling_path)
+                        .map(|score| score * (1.0 - link_density(sibling)));
+                    if sibling_path == path
+                        || sibling_score.is_some_and(|score| score >= threshold)
+                        || is_loose_paragraph(sibling)
+                    {
+                        content.children.push(sibling.clone());
+                    }
+                }
+            }
+            _ => content.children = root.children,
+        }
+
+        for child in &mut content.children {
+            clean(child);
+        }
+        content.normalize_text();
+        content
+    }
+}
+
+// Drops comments, hidden elements and those never part of an article, and with `strip_unlikely`
+// the ones whose class, id or role mark them as page furniture
+fn prepare(element: &mut HtmlElement, strip_unlikely: bool) {
+    element.children.retain(|child| match child.kind {
+        NodeKind::Text => true,
+        NodeKind::Element => {
+            let tag_name = child.tag_name.as_deref().unwrap_or_default();
+            !(REMOVED_ELEMENTS.contains(&tag_name)
+                || is_hidden(child)
+                || (strip_unlikely && is_unlikely_candidate(child)))
+        }
+        _ => false,
+    });
+    for child in &mut element.children {
+        prepare(child, strip_unlikely);
+    }
+    element.normalize_text();
+}
+
+fn is_unlikely_candidate(element: &HtmlElement) -> bool {
+    let tag_name = element.tag_name.as_deref().unwrap_or_default();
+    if matches!(tag_name, "html" | "body" | "article" | "main" | "a") {
+        return false;
+    }
+    if element
+        .attributes
+        .get("role")
+        .is_some_and(|role| UNLIKELY_ROLES.contains(&role.trim().to_ascii_lowercase().as_str()))
+    {
+        return true;
+    }
+    let hints = class_and_id(element);
+    contains_any(&hints, UNLIKELY_CANDIDATES) && !contains_any(&hints, MAYBE_CANDIDATES)
+}
+
+// Scores blocks of text and adds the scores to their ancestors, which are keyed by their path of
+// child indexes from the root
+fn score_paragraphs(
+    root: &HtmlElement,
+    element: &HtmlElement,
+    path: &mut Vec<usize>,
+    candidates: &mut HashMap<Vec<usize>, f64>,
+) {
+    for (index, child) in element.children.iter().enumerate() {
+        if child.is_element() {
+            path.push(index);
+            score_paragraphs(root, child, path, candidates);
+            path.pop();
+        }
+    }
+
+    if path.is_empty() || !is_paragraph(element) {
+        return;
+    }
+    let text = text_content(element);
+    let length = text.chars().count();
+    if length < 25 {
+        return;
+    }
+    let score =
+        1.0 + text.matches([',', '，']).count() as f64 + (length as f64 / 100.0).floor().min(3.0);
+
+    // The parent gets the full score, the grandparent half and further ancestors less still
+    for level in 0..5 {
+        let Some(end) = path.len().checked_sub(level + 1) else {
+            break;
+        };
+        let divider = match level {
+            0 => 1.0,
+            1 => 2.0,
+            _ => level as f64 * 3.0,
+        };
+        let ancestor = &path[..end];
+        *candidates
+            .entry(ancestor.to_vec())
+            .or_insert_with(|| initial_score(node_at(root, ancestor))) += score / divider;
+    }
+}
+
+fn initial_score(element: &HtmlElement) -> f64 {
+    let base = match element.tag_name.as_deref().unwrap_or_default() {
+        "div" | "article" | "section" | "main" => 5.0,
+        "pre" | "td" | "blockquote" => 3.0,
+        "address" | "ol" | "ul" | "dl" | "dd" | "dt" | "li" | "form" => -3.0,
+        "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "th" => -5.0,
+        _ => 0.0,
+    };
+    base + class_weight(element)
+}
+
+fn class_weight(element: &HtmlElement) -> f64 {
+    ["class", "id"]
+        .iter()
+        .filter_map(|name| element.attributes.get(*name))
+        .map(|value| {
+            let value = value.to_ascii_lowercase();
+            let mut weight = 0.0;
+            if contains_any(&value, NEGATIVE_HINTS) {
+                weight -= 25.0;
+            }
+            if contains_any(&value, POSITIVE_HINTS) {
+                weight += 25.0;
+            }
+            weight
+        })
+        .sum()
+}
+
+// Paragraphs, and divs holding nothing but text and inline elements, which are paragraphs in all
+// but name
+fn is_paragraph(element: &HtmlElement) -> bool {
+    match element.tag_name.as_deref().unwrap_or_default() {
+        "p" | "pre" | "td" => true,
+        "div" | "section" => !element.children.iter().any(|child| {
+            child
+                .tag_name
+                .as_deref()
+                .is_some_and(|tag_name| BLOCK_CHILDREN.contains(&tag_name))
+        }),
+        _ => false,
+    }
+}
+
+// Paragraphs next to the top candidate that read like prose rather than links
+fn is_loose_paragraph(element: &HtmlElement) -> bool {
+    if element.tag_name.as_deref() != Some("p") {
+        return false;
+    }
+    let text = text_content(element);
+    let length = text.chars().count();
+    let link_density = link_density(element);
+    (length > 80 && link_density < 0.25)
+        || (length > 0 && link_density == 0.0 && (text.contains(". ") || text.ends_with('.')))
+}
+
+// Removes what's left of the boilerplate inside the article: blocks hinted to be furniture,
+// link lists, image galleries and fragments too short to be content
+fn clean(element: &mut HtmlElement) {
+    element
+        .children
+        .retain(|child| !child.is_element() || !is_boilerplate(child));
+    for child in &mut element.children {
+        clean(child);
+    }
+}
+
+fn is_boilerplate(element: &HtmlElement) -> bool {
+    let tag_name = element.tag_name.as_deref().unwrap_or_default();
+    let weight = class_weight(element);
+    if matches!(tag_name, "h1" | "h2" | "h3" | "h4" | "h5" | "h6") {
+        return weight < 0.0;
+    }
+    if !matches!(tag_name, "div" | "section" | "ul" | "ol" | "table" | "form") {
+        return false;
+    }
+    if weight < 0.0 {
+        return true;
+    }
+
+    let text = text_content(element);
+    if text.matches([',', '，']).count() >= 10 {
+        return false;
+    }
+    let length = text.chars().count();
+    let paragraphs = count_elements(element, "p");
+    let images = count_elements(element, "img");
+    let list_items = count_elements(element, "li");
+    let link_density = link_density(element);
+    let is_list = matches!(tag_name, "ul" | "ol");
+
+    (images > 1 && (paragraphs as f64) < images as f64 / 2.0)
+        || (!is_list && list_items > paragraphs + 100)
+        || (length < 25 && (images == 0 || images > 2) && !has_media(element))
+        || (weight < 25.0 && link_density > 0.2 + if is_list { 0.3 } else { 0.0 })
+        || (weight >= 25.0 && link_density > 0.5)
+}
+
+// Headings repeating the article's title, which is returned separately
+fn remove_title_headings(content: &mut HtmlElement, title: &str) {
+    let title = title.trim().to_lowercase();
+    content.children.retain(|child| {
+        !(matches!(child.tag_name.as_deref(), Some("h1" | "h2"))
+            && text_content(child).to_lowercase() == title)
+    });
+    for child in &mut content.children {
+        if child.is_element() {
+            remove_title_headings(child, &title);
+        }
+    }
+    content.normalize_text();
+}
+
+// Values of `<meta>` tags by their lowercased property, name or itemprop, the first one winning
+fn metadata(document: &HtmlElement) -> HashMap<String, String> {
+    let mut metadata = HashMap::new();
+    collect_metadata(document, &mut metadata);
+    metadata
+}
+
+fn collect_metadata(element: &HtmlElement, metadata: &mut HashMap<String, String>) {
+    if element.tag_name.as_deref() == Some("meta") {
+        if let Some(content) = element.attributes.get("content") {
+            let content = content.trim();
+            for name in ["property", "name", "itemprop"] {
+                if let Some(key) = element.attributes.get(name) {
+                    // `property` may list several RDFa properties
+                    for key in key.split_whitespace() {
+                        if !content.is_empty() {
+                            metadata
+                                .entry(key.to_ascii_lowercase())
+                                .or_insert_with(|| content.to_string());
+                        }
+                    }
+                }
+            }
+        }
+    }
+    for child in &element.children {
+        collect_metadata(child, metadata);
+    }
+}
+
+fn title(document: &HtmlElement, metadata: &HashMap<String, String>) -> Option<String> {
+    if let Some(title) = ["og:title", "twitter:title", "dc.title"]
+        .iter()
+        .find_map(|name| metadata.get(*name))
+    {
+        return Some(title.clone());
+    }
+    let heading = || {
+        find_element(document, &|element| {
+            element.tag_name.as_deref() == Some("h1")
+        })
+        .map(text_content)
+        .filter(|heading| !heading.is_empty())
+    };
+    let Some(title) = find_element(document, &|element| {
+        element.tag_name.as_deref() == Some("title")
+    })
+    .map(text_content)
+    .filter(|title| !title.is_empty()) else {
+        return heading();
+    };
+
+    // "Article | Site" loses the site name, unless that leaves too little to be a headline
+    let separator = TITLE_SEPARATORS
+        .iter()
+        .filter_map(|separator| title.rfind(separator))
+        .max();
+    match separator {
+        Some(end) if title[..end].split_whitespace().count() >= 3 => {
+            Some(title[..end].trim().to_string())
+        }
+        _ => Some(title),
+    }
+}
+
+fn byline(body: &HtmlElement, metadata: &HashMap<String, String>) -> Option<String> {
+    let from_metadata = ["author", "article:author", "dc.creator", "twitter:creator"]
+        .iter()
+        .filter_map(|name| metadata.get(*name))
+        // article:author is often a profile URL
+        .find(|author| !author.starts_with("http"))
+        .cloned();
+    let byline = from_metadata.or_else(|| {
+        find_element(body, &|element| {
+            if !element.is_element() {
+                return false;
+            }
+            let is_author = element.attributes.get("rel").map(String::as_str) == Some("author")
+                || element
+                    .attributes
+                    .get("itemprop")
+                    .is_some_and(|itemprop| itemprop.contains("author"))
+                || contains_any(&class_and_id(element), &["byline", "author"]);
+            let length = text_length(element);
+            is_author && length > 0 && length < 100
+        })
+        .map(text_content)
+    })?;
+
+    let byline = byline.trim();
+    let byline = ["By ", "by ", "BY "]
+        .iter()
+        .find_map(|prefix| byline.strip_prefix(prefix))
+        .unwrap_or(byline);
+    Some(byline.trim().to_string())
+}
+
+// Everything below `element` in document order, whitespace collapsed
+pub(crate) fn text_content(element: &HtmlElement) -> String {
+    let mut text = String::new();
+    collect_text(element, &mut text);
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn collect_text(element: &HtmlElement, text: &mut String) {
+    if element.kind == NodeKind::Text {
+        text.push_str(&element.text);
+        return;
+    }
+    for child in &element.children {
+        if child.is_element() {
+            // Keep words in adjacent blocks apart
+            text.push(' ');
+        }
+        collect_text(child, text);
+    }
+}
+
+fn text_length(element: &HtmlElement) -> usize {
+    text_content(element).chars().count()
+}
+
+// Share of the element's text that's inside links
+fn link_density(element: &HtmlElement) -> f64 {
+    let length = text_length(element);
+    if length == 0 {
+        return 0.0;
+    }
+    link_text_length(element) as f64 / length as f64
+}
+
+fn link_text_length(element: &HtmlElement) -> usize {
+    element
+        .children
+        .iter()
+        .filter(|child| child.is_element())
+        .map(|child| match child.tag_name.as_deref() {
+            Some("a") => text_length(child),
+            _ => link_text_length(child),
+        })
+        .sum()
+}
+
+fn count_elements(element: &HtmlElement, tag_name: &str) -> usize {
+    element
+        .children
+        .iter()
+        .filter(|child| child.is_element())
+        .map(|child| {
+            usize::from(child.tag_name.as_deref() == Some(tag_name))
+                + count_elements(child, tag_name)
+        })
+        .sum()
+}
+
+fn has_media(element: &HtmlElement) -> bool {
+    find_element(element, &|element| {
+        matches!(
+            element.tag_name.as_deref(),
+            Some("img" | "picture" | "video" | "audio" | "figure")
+        )
+    })
+    .is_some()
+}
+
+fn class_and_id(element: &HtmlElement) -> String {
+    ["class", "id"]
+        .iter()
+        .filter_map(|name| element.attributes.get(*name))
+        .map(|value| value.to_ascii_lowercase())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn contains_any(value: &str, fragments: &[&str]) -> bool {
+    fragments.iter().any(|fragment| value.contains(fragment))
+}
+
+fn node_at<'a>(root: &'a HtmlElement, path: &[usize]) -> &'a HtmlElement {
+    path.iter()
+        .fold(root, |element, &index| &element.children[index])
+}
@@ -0,0 +1,319 @@
+use crate::engine::html::{HtmlElement, HtmlParser, NodeKind};
+use std::collections::{HashMap, HashSet};
+
+const DEFAULT_TAGS: &[&str] = &[
+    "a",
+    "abbr",
+    "b",
+    "blockquote",
+    "br",
+    "caption",
+    "cite",
+    "code",
+    "dd",
+    "del",
+    "div",
+    "dl",
+    "dt",
+    "em",
+    "figcaption",
+    "figure",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "hr",
+    "i",
+    "img",
+    "ins",
+    "kbd",
+    "li",
+    "mark",
+    "ol",
+    "p",
+    "pre",
+    "q",
+    "s",
+    "small",
+    "span",
+    "strong",
+    "sub",
+    "sup",
+    "table",
+    "tbody",
+    "td",
+    "tfoot",
+    "th",
+    "thead",
+    "tr",
+    "u",
+    "ul",
+];
+
+const DEFAULT_ATTRIBUTES: &[(&str, &[&str])] = &[
+    ("a", &["href", "title"]),
+    ("abbr", &["title"]),
+    ("blockquote", &["cite"]),
+    ("img", &["src", "alt", "title", "width", "height"]),
+    ("q", &["cite"]),
+    ("td", &["colspan", "rowspan"]),
+    ("th", &["colspan", "rowspan", "scope"]),
+];
+
+// Disallowed elements whose content goes with them instead of being kept
+const DEFAULT_REMOVED: &[&str] = &[
+    "script", "style", "template", "noscript", "iframe", "frame", "frameset", "object", "embed",
+    "applet", "head", "title", "textarea", "select", "svg", "math",
+];
+
+// Attributes holding URLs, checked against the allowed schemes
+const URL_ATTRIBUTES: &[&str] = &[
+    "href",
+    "src",
+    "cite",
+    "action",
+    "formaction",
+    "poster",
+    "background",
+    "longdesc",
+    "xlink:href",
+];
+
+// Cleans untrusted markup down to an allowlist of tags, attributes and URL schemes. Elements that
+// aren't allowed are unwrapped, keeping their content, unless they're listed in
+// `remove_with_content` (scripts, styles, frames, ... by default). Event handler attributes,
+// doctypes and processing instructions never make it through.
+#[derive(Debug, Clone)]
+pub struct Sanitizer {
+    tags: HashSet<String>,
+    // Allowed attributes by tag name, "*" applies to every allowed tag
+    attributes: HashMap<String, HashSet<String>>,
+    url_schemes: HashSet<String>,
+    removed: HashSet<String>,
+    keep_comments: bool,
+}
+
+impl Default for Sanitizer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Sanitizer {
+    // A sanitizer allowing basic formatting, lists, tables, links and images with http(s) and
+    // mailto URLs
+    pub fn new() -> Self {
+        Sanitizer {
+            tags: set(DEFAULT_TAGS),
+            attributes: DEFAULT_ATTRIBUTES
+                .iter()
+                .map(|(tag, attributes)| (tag.to_string(), set(attributes)))
+                .collect(),
+            url_schemes: set(&["http", "https", "mailto"]),
+            removed: set(DEFAULT_REMOVED),
+            keep_comments: false,
+        }
+    }
+
+    // A sanitizer allowing nothing, which leaves only text, to build up from
+    pub fn empty() -> Self {
+        Sanitizer {
+            tags: HashSet::new(),
+            attributes: HashMap::new(),
+            url_schemes: HashSet::new(),
+            ..Sanitizer::new()
+        }
+    }
+
+    pub fn allow_tags(mut self, tags: &[&str]) -> Self {
+        self.tags
+            .extend(tags.iter().map(|tag| tag.to_ascii_lowercase()));
+        self
+    }
+
+    pub fn disallow_tags(mut self, tags: &[&str]) -> Self {
+        for tag in tags {
+            self.tags.remove(&tag.to_ascii_lowercase());
+        }
+        self
+    }
+
+    // Allows `attributes` on `tag`, or on every allowed tag when `tag` is "*"
+    pub fn allow_attributes(mut self, tag: &str, attributes: &[&str]) -> Self {
+        self.attributes
+            .entry(tag.to_ascii_lowercase())
+            .or_default()
+            .extend(attributes.iter().map(|name| name.to_ascii_lowercase()));
+        self
+    }
+
+    pub fn disallow_attributes(mut self, tag: &str, attributes: &[&str]) -> Self {
+        if let Some(allowed) = self.attributes.get_mut(&tag.to_ascii_lowercase()) {
+            for name in attributes {
+                allowed.remove(&name.to_ascii_lowercase());
+            }
+        }
+        self
+    }
+
+    // Schemes allowed in URL attributes like `href` and `src`, relative URLs are always allowed
+    pub fn allow_url_schemes(mut self, schemes: &[&str]) -> Self {
+        self.url_schemes
+            .extend(schemes.iter().map(|scheme| scheme.to_ascii_lowercase()));
+        self
+    }
+
+    pub fn disallow_url_schemes(mut self, schemes: &[&str]) -> Self {
+        for scheme in schemes {
+            self.url_schemes.remove(&scheme.to_ascii_lowercase());
+        }
+        self
+    }
+
+    // Disallowed elements to drop along with their content rather than unwrap
+    pub fn remove_with_content(mut self, tags: &[&str]) -> Self {
+        self.removed
+            .extend(tags.iter().map(|tag| tag.to_ascii_lowercase()));
+        self
+    }
+
+    // Disallowed elements to unwrap even if they're removed with their content by default
+    pub fn unwrap_instead(mut self, tags: &[&str]) -> Self {
+        for tag in tags {
+            self.removed.remove(&tag.to_ascii_lowercase());
+        }
+        self
+    }
+
+    pub fn keep_comments(mut self, keep_comments: bool) -> Self {
+        self.keep_comments = keep_comments;
+        self
+    }
+
+    // Parses `html` as the content of a `<body>` and returns its sanitized markup
+    pub fn clean(&self, html: &str) -> String {
+        let fragment = HtmlParser::new().parse_fragment(html, "body");
+        self.sanitize(&fragment).inner_html()
+    }
+
+    // A sanitized copy of `element`. The same rules apply to `element` itself as to the nodes
+    // below it: when its tag isn't allowed it comes back as a fragment of its unwrapped content,
+    // or an empty one when it's removed with its content. Documents stay documents, so either
+    // can be passed in and the result serialized with `inner_html`.
+    pub fn sanitize(&self, element: &HtmlElement) -> HtmlElement {
+        let node = |kind| HtmlElement {
+            tag_name: None,
+            text: String::new(),
+            children: Vec::new(),
+            attributes: HashMap::new(),
+            kind,
+            namespace: None,
+        };
+        let mut root = match &element.kind {
+            NodeKind::Element => {
+                let tag_name = element
+                    .tag_name
+                    .as_deref()
+                    .unwrap_or_default()
+                    .to_ascii_lowercase();
+                if self.tags.contains(&tag_name) {
+                    let mut root = HtmlElement::new_element(&tag_name);
+                    root.attributes = self.allowed_attributes(element);
+                    root
+                } else if self.removed.contains(&tag_name) {
+                    return node(NodeKind::Fragment);
+                } else {
+                    node(NodeKind::Fragment)
+                }
+            }
+            NodeKind::Text => return HtmlElement::new_text(&element.text),
+            NodeKind::Document => node(NodeKind::Document),
+            NodeKind::Fragment => node(NodeKind::Fragment),
+            // Comments, doctypes and PIs come back as an empty fragment
+            _ => return node(NodeKind::Fragment),
+        };
+        self.sanitize_children(element, &mut root.children);
+        root.normalize_text();
+        root
+    }
+
+    fn sanitize_children(&self, element: &HtmlElement, output: &mut Vec<HtmlElement>) {
+        for child in &element.children {
+            match child.kind {
+                NodeKind::Text => output.push(HtmlElement::new_text(&child.text)),
+                NodeKind::Comment if self.keep_comments => {
+                    // `--` could end the comment early once serialized
+                    output.push(HtmlElement::new_comment(&child.text.replace("--", "- -")))
+                }
+                NodeKind::Element => {
+                    let tag_name = child.tag_name.as_deref().unwrap_or_default();
+                    let tag_name = tag_name.to_ascii_lowercase();
+                    if self.tags.contains(&tag_name) {
+                        output.push(self.sanitize(child));
+                    } else if !self.removed.contains(&tag_name) {
+                        self.sanitize_children(child, output);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn allowed_attributes(&self, element: &HtmlElement) -> HashMap<String, String> {
+        let tag_name = element
+            .tag_name
+            .as_deref()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        let allowed = |name: &str| {
+            [tag_name.as_str(), "*"].iter().any(|tag| {
+                self.attributes
+                    .get(*tag)
+                    .is_some_and(|attributes| attributes.contains(name))
+            })
+        };
+
+        element
+            .attributes
+            .iter()
+            .filter(|(name, value)| {
+                let name = name.to_ascii_lowercase();
+                !name.starts_with("on")
+                    && allowed(&name)
+                    && (!URL_ATTRIBUTES.contains(&name.as_str()) || self.allowed_url(value))
+            })
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect()
+    }
+
+    fn allowed_url(&self, url: &str) -> bool {
+        // Browsers skip whitespace and control characters in URLs, so "java\tscript:" is still
+        // a javascript: URL
+        let url: String = url
+            .chars()
+            .filter(|c| !c.is_whitespace() && !c.is_control())
+            .collect();
+        match url_scheme(&url) {
+            Some(scheme) => self.url_schemes.contains(&scheme.to_ascii_lowercase()),
+            None => true,
+        }
+    }
+}
+
+// The scheme of an absolute URL, None for relative ones
+fn url_scheme(url: &str) -> Option<&str> {
+    let end = url.find([':', '/', '?', '#'])?;
+    let scheme = &url[..end];
+    let is_scheme = url[end..].starts_with(':')
+        && scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+    is_scheme.then_some(scheme)
+}
+
+fn set(values: &[&str]) -> HashSet<String> {
+    values.iter().map(|value| value.to_string()).collect()
+}
@@ -22,9 +22,17 @@ macro_rules! select_by_id {
 }
 
 pub struct Selector {
-    tag_name: Option<String>,
-    class_name: Option<String>,
-    id: Option<String>,
+    pub(crate) tag_name: Option<String>,
+    pub(crate) class_name: Option<String>,
+    pub(crate) id: Option<String>,
+    // Namespace URI and local name, for documents from `XmlParser`
+    pub(crate) namespaced_name: Option<(String, String)>,
+}
+
+impl Default for Selector {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Selector {
@@ -33,6 +41,7 @@ impl Selector {
             tag_name: None,
             class_name: None,
             id: None,
+            namespaced_name: None,
         }
     }
 
@@ -51,6 +60,13 @@ impl Selector {
         self
     }
 
+    // Matches XML elements by namespace URI and local name whatever prefix the document uses,
+    // e.g. ("http://www.w3.org/2005/Atom", "entry")
+    pub fn from_namespaced_name(&mut self, namespace: &str, local_name: &str) -> &mut Self {
+        self.namespaced_name = Some((namespace.to_string(), local_name.to_string()));
+        self
+    }
+
     pub fn select<'a>(&self, element: &'a HtmlElement) -> Selection<'a> {
         let mut selected: Vec<&'a HtmlElement> = Vec::new();
         self.select_recursive(element, &mut selected);
@@ -63,11 +79,16 @@ impl Selector {
         selected.into_iter().next()
     }
 
-    fn select_recursive<'a>(&self, element: &'a HtmlElement, selected: &mut Vec<&'a HtmlElement>) {
-        if self.tag_name.as_ref() == element.tag_name.as_ref()
+    // Whether the element has the selector's tag name, class or id
+    pub fn matches(&self, element: &HtmlElement) -> bool {
+        self.matches_tag_name(element)
             || self.matches_class(element)
             || self.matches_id(element)
-        {
+            || self.matches_namespaced_name(element)
+    }
+
+    fn select_recursive<'a>(&self, element: &'a HtmlElement, selected: &mut Vec<&'a HtmlElement>) {
+        if self.matches(element) {
             selected.push(element);
         }
         for child in &element.children {
@@ -75,9 +96,26 @@ impl Selector {
         }
     }
 
+    fn matches_tag_name(&self, element: &HtmlElement) -> bool {
+        match self.tag_name {
+            Some(ref tag_name) => element.tag_name.as_ref() == Some(tag_name),
+            None => false,
+        }
+    }
+
+    fn matches_namespaced_name(&self, element: &HtmlElement) -> bool {
+        match self.namespaced_name {
+            Some((ref namespace, ref local_name)) => {
+                element.namespace.as_ref() == Some(namespace)
+                    && element.local_name() == Some(local_name.as_str())
+            }
+            None => false,
+        }
+    }
+
     fn matches_class(&self, element: &HtmlElement) -> bool {
         match self.class_name {
-            Some(ref class_name) => element.attributes.get("class").map_or(false, |classes| {
+            Some(ref class_name) => element.attributes.get("class").is_some_and(|classes| {
                 classes.split_whitespace().any(|class| class == class_name)
             }),
             None => false,
@@ -133,6 +171,10 @@ impl<'a> Selection<'a> {
         self.elements.len()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+
     pub fn class(&self) -> Option<String> {
         self.elements
             .iter()
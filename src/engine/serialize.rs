@@ -0,0 +1,302 @@
+use crate::engine::html::{Doctype, HtmlElement, NodeKind};
+use crate::engine::selector::Selection;
+use crate::engine::streaming::VOID_ELEMENTS;
+use serde::de::{self, Deserialize, Deserializer, IgnoredAny, MapAccess, Visitor};
+use serde::ser::{Serialize, SerializeMap, Serializer};
+use std::collections::HashMap;
+use std::fmt;
+
+// Elements whose text is written out as is, the way the parser read it (with scripting on,
+// which is how `HtmlParser` parses `<noscript>`)
+const RAW_TEXT_ELEMENTS: &[&str] = &[
+    "script",
+    "style",
+    "xmp",
+    "iframe",
+    "noembed",
+    "noframes",
+    "plaintext",
+    "noscript",
+];
+
+// Serialization following the HTML fragment serialization algorithm, so parsing the output gives
+// back the same tree. Attributes are written sorted by name as their source order isn't kept.
+impl HtmlElement {
+    // Markup for this node including its own tag, or its children for documents and fragments
+    pub fn to_html(&self) -> String {
+        let mut html = String::new();
+        self.write_html(&mut html, None);
+        html
+    }
+
+    // Markup for the children of this node, like `innerHTML`
+    pub fn inner_html(&self) -> String {
+        let mut html = String::new();
+        for child in &self.children {
+            child.write_html(&mut html, self.tag_name.as_deref());
+        }
+        html
+    }
+
+    fn write_html(&self, html: &mut String, parent: Option<&str>) {
+        match self.kind {
+            NodeKind::Document | NodeKind::Fragment => html.push_str(&self.inner_html()),
+            NodeKind::Element => {
+                let tag_name = self.tag_name.as_deref().unwrap_or_default();
+                html.push('<');
+                html.push_str(tag_name);
+                let mut attributes: Vec<(&String, &String)> = self.attributes.iter().collect();
+                attributes.sort();
+                for (name, value) in attributes {
+                    html.push(' ');
+                    html.push_str(name);
+                    html.push_str("=\"");
+                    html.push_str(&escape(value, true));
+                    html.push('"');
+                }
+                html.push('>');
+                if VOID_ELEMENTS.contains(&tag_name) {
+                    return;
+                }
+                html.push_str(&self.inner_html());
+                html.push_str("</");
+                html.push_str(tag_name);
+                html.push('>');
+            }
+            NodeKind::Text => match parent {
+                Some(parent) if RAW_TEXT_ELEMENTS.contains(&parent) => html.push_str(&self.text),
+                _ => html.push_str(&escape(&self.text, false)),
+            },
+            NodeKind::Comment => {
+                html.push_str("<!--");
+                html.push_str(&self.text);
+                html.push_str("-->");
+            }
+            NodeKind::Doctype(ref doctype) => {
+                html.push_str("<!DOCTYPE ");
+                html.push_str(&doctype.name);
+                if !doctype.public_id.is_empty() {
+                    html.push_str(&format!(" PUBLIC \"{}\"", doctype.public_id));
+                    if !doctype.system_id.is_empty() {
+                        html.push_str(&format!(" \"{}\"", doctype.system_id));
+                    }
+                } else if !doctype.system_id.is_empty() {
+                    html.push_str(&format!(" SYSTEM \"{}\"", doctype.system_id));
+                }
+                html.push('>');
+            }
+            NodeKind::ProcessingInstruction { ref target } => {
+                html.push_str("<?");
+                html.push_str(target);
+                if !self.text.is_empty() {
+                    html.push(' ');
+                    html.push_str(&self.text);
+                }
+                html.push_str("?>");
+            }
+        }
+    }
+}
+
+fn escape(text: &str, attribute: bool) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '\u{a0}' => escaped.push_str("&nbsp;"),
+            '"' if attribute => escaped.push_str("&quot;"),
+            '<' if !attribute => escaped.push_str("&lt;"),
+            '>' if !attribute => escaped.push_str("&gt;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// Serde support in a compact form made for JSON. Text nodes are plain strings and other nodes are
+// objects told apart by their keys:
+//
+//   {"tag": "a", "attributes": {"href": "/"}, "children": ["Home"]}
+//   {"comment": " note "}
+//   {"doctype": "html"}
+//   {"target": "xml-stylesheet", "data": "href=\"style.css\""}
+//   {"document": [...]} and {"fragment": [...]}
+//
+// Empty attributes and children and a missing namespace are left out, and attributes are written
+// sorted by name so the output is stable. `text` isn't stored, it's rebuilt from the children.
+impl Serialize for HtmlElement {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if self.kind == NodeKind::Text {
+            return serializer.serialize_str(&self.text);
+        }
+        let mut map = serializer.serialize_map(None)?;
+        match &self.kind {
+            NodeKind::Element => {
+                map.serialize_entry("tag", self.tag_name.as_deref().unwrap_or_default())?;
+                if !self.attributes.is_empty() {
+                    let mut attributes: Vec<_> = self.attributes.iter().collect();
+                    attributes.sort();
+                    map.serialize_entry("attributes", &SortedAttributes(&attributes))?;
+                }
+                if let Some(namespace) = &self.namespace {
+                    map.serialize_entry("namespace", namespace)?;
+                }
+                if !self.children.is_empty() {
+                    map.serialize_entry("children", &self.children)?;
+                }
+            }
+            NodeKind::Document => map.serialize_entry("document", &self.children)?,
+            NodeKind::Fragment => map.serialize_entry("fragment", &self.children)?,
+            NodeKind::Comment => map.serialize_entry("comment", &self.text)?,
+            NodeKind::Doctype(doctype) => {
+                map.serialize_entry("doctype", &doctype.name)?;
+                if !doctype.public_id.is_empty() {
+                    map.serialize_entry("public_id", &doctype.public_id)?;
+                }
+                if !doctype.system_id.is_empty() {
+                    map.serialize_entry("system_id", &doctype.system_id)?;
+                }
+            }
+            NodeKind::ProcessingInstruction { target } => {
+                map.serialize_entry("target", target)?;
+                map.serialize_entry("data", &self.text)?;
+            }
+            // Written as a plain string above
+            NodeKind::Text => {}
+        }
+        map.end()
+    }
+}
+
+struct SortedAttributes<'a>(&'a [(&'a String, &'a String)]);
+
+impl Serialize for SortedAttributes<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.0.iter().copied())
+    }
+}
+
+// Deserialization reads the node's keys first and works out its kind from them afterwards, so the
+// keys may come in any order and unknown ones are skipped. Each element nests two levels deep in
+// JSON, its object and its children list, and serde_json stops at 128 levels by default, so
+// `serde_json::from_str` gives up on documents more than about 60 elements deep. For those, use a
+// `serde_json::Deserializer` with `disable_recursion_limit` (behind serde_json's `unbounded_depth`
+// feature), wrapped in `serde_stacker::Deserializer` when the tree is deep enough to run out of
+// stack.
+impl<'de> Deserialize<'de> for HtmlElement {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(NodeVisitor)
+    }
+}
+
+struct NodeVisitor;
+
+impl<'de> Visitor<'de> for NodeVisitor {
+    type Value = HtmlElement;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a string or a node object")
+    }
+
+    fn visit_str<E: de::Error>(self, text: &str) -> Result<Self::Value, E> {
+        Ok(node(NodeKind::Text, text.to_string(), Vec::new()))
+    }
+
+    fn visit_string<E: de::Error>(self, text: String) -> Result<Self::Value, E> {
+        Ok(node(NodeKind::Text, text, Vec::new()))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut tag = None;
+        let mut attributes = None;
+        let mut namespace = None;
+        let mut children = None;
+        let mut comment = None;
+        let mut doctype = None;
+        let mut public_id = None;
+        let mut system_id = None;
+        let mut target = None;
+        let mut data = None;
+        let mut document = None;
+        let mut fragment = None;
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "tag" => tag = Some(map.next_value::<String>()?),
+                "attributes" => attributes = Some(map.next_value::<HashMap<String, String>>()?),
+                "namespace" => namespace = map.next_value::<Option<String>>()?,
+                "children" => children = Some(map.next_value::<Vec<HtmlElement>>()?),
+                "comment" => comment = Some(map.next_value::<String>()?),
+                "doctype" => doctype = Some(map.next_value::<String>()?),
+                "public_id" => public_id = Some(map.next_value::<String>()?),
+                "system_id" => system_id = Some(map.next_value::<String>()?),
+                "target" => target = Some(map.next_value::<String>()?),
+                "data" => data = Some(map.next_value::<String>()?),
+                "document" => document = Some(map.next_value::<Vec<HtmlElement>>()?),
+                "fragment" => fragment = Some(map.next_value::<Vec<HtmlElement>>()?),
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+
+        let mut element = if let Some(tag) = tag {
+            HtmlElement {
+                tag_name: Some(tag),
+                attributes: attributes.unwrap_or_default(),
+                namespace,
+                ..node(
+                    NodeKind::Element,
+                    String::new(),
+                    children.unwrap_or_default(),
+                )
+            }
+        } else if let Some(comment) = comment {
+            node(NodeKind::Comment, comment, Vec::new())
+        } else if let Some(doctype) = doctype {
+            node(
+                NodeKind::Doctype(Doctype {
+                    name: doctype,
+                    public_id: public_id.unwrap_or_default(),
+                    system_id: system_id.unwrap_or_default(),
+                }),
+                String::new(),
+                Vec::new(),
+            )
+        } else if let Some(target) = target {
+            node(
+                NodeKind::ProcessingInstruction { target },
+                data.unwrap_or_default(),
+                Vec::new(),
+            )
+        } else if let Some(document) = document {
+            node(NodeKind::Document, String::new(), document)
+        } else if let Some(fragment) = fragment {
+            node(NodeKind::Fragment, String::new(), fragment)
+        } else {
+            return Err(de::Error::custom(
+                "node object without a tag, comment, doctype, target, document or fragment key",
+            ));
+        };
+        element.normalize_text();
+        Ok(element)
+    }
+}
+
+fn node(kind: NodeKind, text: String, children: Vec<HtmlElement>) -> HtmlElement {
+    HtmlElement {
+        tag_name: None,
+        text,
+        children,
+        attributes: HashMap::new(),
+        kind,
+        namespace: None,
+    }
+}
+
+// Selections serialize as a list of the selected elements, which deserializes back into a
+// `Vec<HtmlElement>`
+impl Serialize for Selection<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(&self.elements)
+    }
+}
@@ -0,0 +1,351 @@
+use crate::engine::client::{WappuError, WappuStream};
+use crate::engine::html::{HtmlElement, NodeKind};
+use crate::engine::selector::Selector;
+use html5ever::tendril::StrTendril;
+use html5ever::tokenizer::states::RawKind;
+use html5ever::tokenizer::{
+    BufferQueue, TagKind, Token, TokenSink, TokenSinkResult, Tokenizer, TokenizerOpts,
+};
+use std::collections::HashMap;
+
+pub(crate) const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "keygen", "link", "meta", "param",
+    "source", "track", "wbr",
+];
+
+// Elements a closing search for implied end tags doesn't look past
+const SCOPE_BOUNDARIES: &[&str] = &[
+    "html", "body", "table", "template", "td", "th", "caption", "ul", "ol", "dl", "select",
+    "button", "object", "applet", "marquee",
+];
+
+const CLOSES_P: &[&str] = &[
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "details",
+    "div",
+    "dl",
+    "fieldset",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hr",
+    "main",
+    "menu",
+    "nav",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "table",
+    "ul",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum HtmlEvent {
+    Start {
+        tag_name: String,
+        attributes: HashMap<String, String>,
+    },
+    End {
+        tag_name: String,
+    },
+    Text(String),
+    Comment(String),
+}
+
+// Parses HTML fed to it in chunks into start/end/text events without building a document, so
+// memory use stays flat however large the page is. Missing end tags are implied with the common
+// HTML rules (`<li>`, `<p>`, table cells, ...), so every Start gets a matching End; unlike
+// `HtmlParser` it doesn't move misnested elements around or add `html`/`head`/`body`.
+// The parser holds non-thread-safe buffers, keep it on one task.
+pub struct StreamingParser {
+    tokenizer: Tokenizer<EventSink>,
+    input: BufferQueue,
+    // Bytes of a UTF-8 character split across chunks
+    partial: Vec<u8>,
+}
+
+impl Default for StreamingParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StreamingParser {
+    pub fn new() -> Self {
+        let sink = EventSink {
+            events: Vec::new(),
+            open: Vec::new(),
+        };
+        StreamingParser {
+            tokenizer: Tokenizer::new(sink, TokenizerOpts::default()),
+            input: BufferQueue::new(),
+            partial: Vec::new(),
+        }
+    }
+
+    // Parses the next chunk of the document, returning the events it completed
+    pub fn feed(&mut self, chunk: &str) -> Vec<HtmlEvent> {
+        if !chunk.is_empty() {
+            self.input.push_back(StrTendril::from_slice(chunk));
+            let _ = self.tokenizer.feed(&mut self.input);
+        }
+        std::mem::take(&mut self.tokenizer.sink.events)
+    }
+
+    // Like `feed` for raw UTF-8 bytes, a character split between two chunks is kept until the
+    // rest of it arrives and invalid bytes become U+FFFD
+    pub fn feed_bytes(&mut self, chunk: &[u8]) -> Vec<HtmlEvent> {
+        self.partial.extend_from_slice(chunk);
+        let mut text = String::new();
+        let mut rest = std::mem::take(&mut self.partial);
+        loop {
+            match std::str::from_utf8(&rest) {
+                Ok(valid) => {
+                    text.push_str(valid);
+                    break;
+                }
+                Err(err) => {
+                    let valid_up_to = err.valid_up_to();
+                    text.push_str(std::str::from_utf8(&rest[..valid_up_to]).unwrap_or_default());
+                    match err.error_len() {
+                        Some(invalid) => {
+                            text.push('\u{fffd}');
+                            rest.drain(..valid_up_to + invalid);
+                        }
+                        None => {
+                            self.partial = rest.split_off(valid_up_to);
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+        self.feed(&text)
+    }
+
+    // Ends the document, closing whatever is still open
+    pub fn finish(mut self) -> Vec<HtmlEvent> {
+        let mut events = if self.partial.is_empty() {
+            Vec::new()
+        } else {
+            self.feed("\u{fffd}")
+        };
+        self.tokenizer.end();
+        events.append(&mut self.tokenizer.sink.events);
+        events
+    }
+
+    // Parses a streamed response body, e.g. from `WappuClient::get_stream`, handing each
+    // event to `on_event` as soon as it is parsed
+    pub async fn parse_stream(
+        mut self,
+        stream: &mut WappuStream,
+        mut on_event: impl FnMut(HtmlEvent),
+    ) -> Result<(), WappuError> {
+        while let Some(chunk) = stream.chunk().await? {
+            self.feed_bytes(&chunk).into_iter().for_each(&mut on_event);
+        }
+        self.finish().into_iter().for_each(on_event);
+        Ok(())
+    }
+}
+
+// Builds `HtmlElement`s for the parts of an event stream that match a selector, so a huge
+// document can be scanned while only keeping the wanted subtrees. Matches nested inside
+// another match are part of the outer subtree rather than collected on their own.
+pub struct SubtreeCollector<'a> {
+    selector: &'a Selector,
+    open: Vec<HtmlElement>,
+}
+
+impl<'a> SubtreeCollector<'a> {
+    pub fn new(selector: &'a Selector) -> Self {
+        SubtreeCollector {
+            selector,
+            open: Vec::new(),
+        }
+    }
+
+    // Takes the next event, returning the subtree it completes if any
+    pub fn push(&mut self, event: HtmlEvent) -> Option<HtmlElement> {
+        match event {
+            HtmlEvent::Start {
+                tag_name,
+                attributes,
+            } => {
+                let mut element = HtmlElement::new_element(&tag_name);
+                element.attributes = attributes;
+                if !self.open.is_empty() || self.selector.matches(&element) {
+                    self.open.push(element);
+                }
+                None
+            }
+            HtmlEvent::End { .. } => {
+                let element = self.open.pop()?;
+                match self.open.last_mut() {
+                    Some(parent) => {
+                        parent.children.push(element);
+                        None
+                    }
+                    None => Some(element),
+                }
+            }
+            HtmlEvent::Text(text) => {
+                if let Some(element) = self.open.last_mut() {
+                    element.text.push_str(&text);
+                    // Text split across chunks arrives in pieces, keep it one node
+                    match element.children.last_mut() {
+                        Some(last) if last.kind == NodeKind::Text => last.text.push_str(&text),
+                        _ => element.children.push(HtmlElement::new_text(&text)),
+                    }
+                }
+                None
+            }
+            HtmlEvent::Comment(comment) => {
+                if let Some(element) = self.open.last_mut() {
+                    element.children.push(HtmlElement::new_comment(&comment));
+                }
+                None
+            }
+        }
+    }
+}
+
+// Streams the body of `stream` and hands every subtree matching `selector` to `on_element`
+pub async fn select_stream(
+    stream: &mut WappuStream,
+    selector: &Selector,
+    mut on_element: impl FnMut(HtmlElement),
+) -> Result<(), WappuError> {
+    let mut collector = SubtreeCollector::new(selector);
+    StreamingParser::new()
+        .parse_stream(stream, |event| {
+            if let Some(element) = collector.push(event) {
+                on_element(element);
+            }
+        })
+        .await
+}
+
+struct EventSink {
+    events: Vec<HtmlEvent>,
+    // Names of the elements currently open, outermost first
+    open: Vec<String>,
+}
+
+impl EventSink {
+    fn close_last(&mut self, count: usize) {
+        for _ in 0..count {
+            if let Some(tag_name) = self.open.pop() {
+                self.events.push(HtmlEvent::End { tag_name });
+            }
+        }
+    }
+
+    // Closes the elements a new `tag_name` element implicitly ends, like an open `<li>` when
+    // the next `<li>` starts
+    fn close_implied(&mut self, tag_name: &str) {
+        let closes: &[&str] = match tag_name {
+            "li" => &["li"],
+            "dt" | "dd" => &["dt", "dd"],
+            "option" => &["option"],
+            "optgroup" => &["option", "optgroup"],
+            "tr" => &["tr", "td", "th"],
+            "td" | "th" => &["td", "th"],
+            "thead" | "tbody" | "tfoot" => &["thead", "tbody", "tfoot", "tr", "td", "th"],
+            tag if CLOSES_P.contains(&tag) => &["p"],
+            _ => return,
+        };
+
+        let mut outermost = None;
+        for (index, open) in self.open.iter().enumerate().rev() {
+            if closes.contains(&open.as_str()) {
+                outermost = Some(index);
+            } else if SCOPE_BOUNDARIES.contains(&open.as_str()) {
+                break;
+            }
+        }
+        if let Some(index) = outermost {
+            self.close_last(self.open.len() - index);
+        }
+    }
+
+    fn in_foreign_content(&self) -> bool {
+        self.open.iter().any(|tag| tag == "svg" || tag == "math")
+    }
+}
+
+impl TokenSink for EventSink {
+    type Handle = ();
+
+    fn process_token(&mut self, token: Token, _line_number: u64) -> TokenSinkResult<()> {
+        match token {
+            Token::TagToken(tag) => {
+                let tag_name = tag.name.to_string();
+                match tag.kind {
+                    TagKind::StartTag => {
+                        self.close_implied(&tag_name);
+                        let mut attributes = HashMap::new();
+                        for attr in tag.attrs {
+                            // The first of duplicated attributes wins, like in the DOM
+                            attributes
+                                .entry(attr.name.local.to_string())
+                                .or_insert_with(|| attr.value.to_string());
+                        }
+                        self.events.push(HtmlEvent::Start {
+                            tag_name: tag_name.clone(),
+                            attributes,
+                        });
+
+                        if VOID_ELEMENTS.contains(&tag_name.as_str())
+                            || (tag.self_closing && self.in_foreign_content())
+                        {
+                            self.events.push(HtmlEvent::End { tag_name });
+                            return TokenSinkResult::Continue;
+                        }
+                        let raw_kind = match tag_name.as_str() {
+                            "script" => Some(RawKind::ScriptData),
+                            "style" | "xmp" | "iframe" | "noembed" | "noframes" | "noscript" => {
+                                Some(RawKind::Rawtext)
+                            }
+                            "title" | "textarea" => Some(RawKind::Rcdata),
+                            _ => None,
+                        };
+                        self.open.push(tag_name);
+                        if let Some(raw_kind) = raw_kind {
+                            return TokenSinkResult::RawData(raw_kind);
+                        }
+                    }
+                    TagKind::EndTag => {
+                        // Stray end tags are dropped, others close what was left open inside
+                        if let Some(index) = self.open.iter().rposition(|open| *open == tag_name) {
+                            self.close_last(self.open.len() - index);
+                        }
+                    }
+                }
+            }
+            Token::CharacterTokens(text) => match self.events.last_mut() {
+                Some(HtmlEvent::Text(previous)) => previous.push_str(&text),
+                _ => self.events.push(HtmlEvent::Text(text.to_string())),
+            },
+            Token::CommentToken(comment) => {
+                self.events.push(HtmlEvent::Comment(comment.to_string()))
+            }
+            Token::EOFToken => self.close_last(self.open.len()),
+            Token::DoctypeToken(_) | Token::NullCharacterToken | Token::ParseError(_) => {}
+        }
+        TokenSinkResult::Continue
+    }
+}
@@ -0,0 +1,307 @@
+use crate::engine::html::{HtmlElement, NodeKind};
+use crate::engine::markdown::BLOCK_ELEMENTS;
+use crate::engine::selector::Selection;
+
+// Elements that aren't displayed, along with everything inside them
+const HIDDEN_ELEMENTS: &[&str] = &[
+    "head", "script", "style", "noscript", "template", "iframe", "object", "embed", "svg", "math",
+    "canvas", "select", "textarea", "input", "datalist", "area", "map", "audio", "video",
+];
+
+// Blocks set off by a blank line rather than a single line break
+const SPACED_ELEMENTS: &[&str] = &[
+    "p",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "blockquote",
+    "pre",
+    "figure",
+    "table",
+    "hr",
+];
+
+// Renders the text of a page roughly the way a browser lays it out: hidden elements are left out,
+// whitespace collapses outside `<pre>`, blocks and `<br>` start new lines, paragraphs are set off
+// by blank lines, list items get bullets and table cells are separated by tabs.
+pub struct TextRenderer {
+    list_bullet: String,
+    cell_separator: String,
+}
+
+impl Default for TextRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TextRenderer {
+    pub fn new() -> Self {
+        TextRenderer {
+            list_bullet: "•".to_string(),
+            cell_separator: "\t".to_string(),
+        }
+    }
+
+    // Marker for items of unordered lists, ordered ones are numbered
+    pub fn list_bullet(mut self, list_bullet: &str) -> Self {
+        self.list_bullet = list_bullet.to_string();
+        self
+    }
+
+    pub fn cell_separator(mut self, cell_separator: &str) -> Self {
+        self.cell_separator = cell_separator.to_string();
+        self
+    }
+
+    pub fn render(&self, element: &HtmlElement) -> String {
+        self.render_all(&[element])
+    }
+
+    // Renders the selected elements as consecutive blocks of one text
+    pub fn render_selection(&self, selection: &Selection) -> String {
+        self.render_all(&selection.elements)
+    }
+
+    fn render_all(&self, elements: &[&HtmlElement]) -> String {
+        let mut output = Output::default();
+        for element in elements {
+            output.line_break(1);
+            self.node(element, &mut output, &Context::default());
+        }
+        output.text
+    }
+
+    fn node(&self, node: &HtmlElement, output: &mut Output, context: &Context) {
+        match node.kind {
+            NodeKind::Text if context.preformatted => output.push(&node.text, true),
+            NodeKind::Text => output.push(&collapse_whitespace(&node.text), false),
+            NodeKind::Element => self.element(node, output, context),
+            NodeKind::Document | NodeKind::Fragment => self.children(node, output, context),
+            _ => {}
+        }
+    }
+
+    fn children(&self, element: &HtmlElement, output: &mut Output, context: &Context) {
+        for child in &element.children {
+            self.node(child, output, context);
+        }
+    }
+
+    fn element(&self, element: &HtmlElement, output: &mut Output, context: &Context) {
+        if is_hidden(element) {
+            return;
+        }
+        let tag_name = element
+            .tag_name
+            .as_deref()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        let breaks = if SPACED_ELEMENTS.contains(&tag_name.as_str()) {
+            2
+        } else {
+            1
+        };
+
+        match tag_name.as_str() {
+            "br" => output.push("\n", true),
+            "hr" => output.line_break(2),
+            "ul" | "ol" | "menu" => {
+                // Only the outermost list is set off by blank lines, nested ones follow their item
+                output.line_break(if context.depth == 0 { 2 } else { 1 });
+                let mut number = element
+                    .attributes
+                    .get("start")
+                    .and_then(|start| start.trim().parse::<i64>().ok())
+                    .unwrap_or(1);
+                let list = Context {
+                    depth: context.depth + 1,
+                    ..context.clone()
+                };
+                for child in &element.children {
+                    if child.tag_name.as_deref() == Some("li") && !is_hidden(child) {
+                        let bullet = match tag_name.as_str() {
+                            "ol" => format!("{}.", number),
+                            _ => self.list_bullet.clone(),
+                        };
+                        number = number.saturating_add(1);
+                        output.line_break(1);
+                        output.push(&format!("{}{} ", "  ".repeat(context.depth), bullet), true);
+                        self.children(child, output, &list);
+                        output.line_break(1);
+                    } else {
+                        self.node(child, output, &list);
+                    }
+                }
+                output.line_break(if context.depth == 0 { 2 } else { 1 });
+            }
+            "tr" => {
+                output.line_break(1);
+                let mut first = true;
+                for cell in &element.children {
+                    if matches!(cell.tag_name.as_deref(), Some("td" | "th")) && !is_hidden(cell) {
+                        if !first {
+                            output.push(&self.cell_separator, true);
+                        }
+                        first = false;
+                        self.children(cell, output, context);
+                    } else {
+                        self.node(cell, output, context);
+                    }
+                }
+                output.line_break(1);
+            }
+            "details" if !element.attributes.contains_key("open") => {
+                // A closed disclosure shows only its summary
+                output.line_break(1);
+                for child in &element.children {
+                    if child.tag_name.as_deref() == Some("summary") {
+                        self.node(child, output, context);
+                    }
+                }
+                output.line_break(1);
+            }
+            "pre" | "listing" | "plaintext" | "xmp" => {
+                output.line_break(breaks);
+                let preformatted = Context {
+                    preformatted: true,
+                    ..context.clone()
+                };
+                self.children(element, output, &preformatted);
+                output.line_break(breaks);
+            }
+            "caption" | "thead" | "tbody" | "tfoot" => {
+                output.line_break(1);
+                self.children(element, output, context);
+                output.line_break(1);
+            }
+            tag_name if BLOCK_ELEMENTS.contains(&tag_name) => {
+                output.line_break(breaks);
+                self.children(element, output, context);
+                output.line_break(breaks);
+            }
+            _ => self.children(element, output, context),
+        }
+    }
+}
+
+impl HtmlElement {
+    // The element's text laid out the way a browser displays it, see `TextRenderer`
+    pub fn rendered_text(&self) -> String {
+        TextRenderer::new().render(self)
+    }
+}
+
+impl Selection<'_> {
+    // The selected elements' text laid out the way a browser displays it, see `TextRenderer`
+    pub fn rendered_text(&self) -> String {
+        TextRenderer::new().render_selection(self)
+    }
+}
+
+#[derive(Clone, Default)]
+struct Context {
+    preformatted: bool,
+    // Nesting level of lists
+    depth: usize,
+}
+
+#[derive(Default)]
+struct Output {
+    text: String,
+    // Line breaks still owed before the next text, blank lines are two. They're only written
+    // once more text follows, so nothing dangles at the start or end.
+    pending_breaks: usize,
+}
+
+impl Output {
+    fn line_break(&mut self, count: usize) {
+        self.pending_breaks = self.pending_breaks.max(count);
+    }
+
+    fn push(&mut self, text: &str, preformatted: bool) {
+        // Whitespace alone at the start of a line would be dropped anyway
+        let line_start =
+            self.pending_breaks > 0 || self.text.is_empty() || self.text.ends_with('\n');
+        if text.is_empty()
+            || (!preformatted && line_start && text.trim_start_matches(' ').is_empty())
+        {
+            return;
+        }
+        if self.pending_breaks > 0 {
+            if !self.text.is_empty() {
+                self.trim_line_end();
+                // A `<br>` right before the block already ended one line
+                let written = self.text.len() - self.text.trim_end_matches('\n').len();
+                let missing = self.pending_breaks.saturating_sub(written);
+                self.text.push_str(&"\n".repeat(missing));
+            }
+            self.pending_breaks = 0;
+        }
+
+        if preformatted {
+            if text == "\n" {
+                self.trim_line_end();
+            }
+            self.text.push_str(text);
+            return;
+        }
+        // Collapsed whitespace is dropped at the start of a line and after other whitespace
+        let text = if self.text.is_empty() || self.text.ends_with(char::is_whitespace) {
+            text.trim_start_matches(' ')
+        } else {
+            text
+        };
+        self.text.push_str(text);
+    }
+
+    fn trim_line_end(&mut self) {
+        let trimmed = self.text.trim_end_matches(' ').len();
+        self.text.truncate(trimmed);
+    }
+}
+
+pub(crate) fn is_hidden(element: &HtmlElement) -> bool {
+    let tag_name = element.tag_name.as_deref().unwrap_or_default();
+    if HIDDEN_ELEMENTS
+        .iter()
+        .any(|hidden| hidden.eq_ignore_ascii_case(tag_name))
+    {
+        return true;
+    }
+    if element.attributes.contains_key("hidden") {
+        return true;
+    }
+    element.attributes.get("style").is_some_and(|style| {
+        style.split(';').any(|declaration| {
+            let declaration: String = declaration
+                .chars()
+                .filter(|c| !c.is_whitespace())
+                .collect::<String>()
+                .to_ascii_lowercase();
+            let declaration = declaration.trim_end_matches("!important");
+            declaration == "display:none" || declaration == "visibility:hidden"
+        })
+    })
+}
+
+// Runs of whitespace become single spaces, as in normal HTML flow
+fn collapse_whitespace(text: &str) -> String {
+    let mut collapsed = String::with_capacity(text.len());
+    let mut in_whitespace = false;
+    for c in text.chars() {
+        if c.is_ascii_whitespace() {
+            if !in_whitespace {
+                collapsed.push(' ');
+            }
+            in_whitespace = true;
+        } else {
+            collapsed.push(c);
+            in_whitespace = false;
+        }
+    }
+    collapsed
+}
@@ -0,0 +1,30 @@
+use crate::engine::html::HtmlElement;
+use markup5ever_rcdom::RcDom;
+use xml5ever::driver::parse_document;
+use xml5ever::tendril::TendrilSink;
+
+// Parses XML documents like RSS and Atom feeds, sitemaps, SOAP responses and XHTML into the same
+// tree `HtmlParser` builds. Names keep their case and prefix as written (`atom:link`), elements
+// carry their resolved namespace URI and `<tag/>` closes the element, so `Selector` queries work
+// the way they read in the document. Malformed input never fails: like a feed reader, the parser
+// closes unclosed elements and keeps what it could make sense of. Entities declared in a DTD are
+// left as written rather than expanded.
+pub struct XmlParser;
+
+impl Default for XmlParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl XmlParser {
+    pub fn new() -> Self {
+        XmlParser {}
+    }
+
+    pub fn parse_xml(&self, input: &str) -> HtmlElement {
+        let dom = parse_document(RcDom::default(), Default::default()).one(input);
+
+        HtmlElement::from_xml_dom(&dom.document)
+    }
+}
@@ -5,3 +5,4 @@ mod utils;
 
 
 pub use engine::*;
+pub use browser::*;
\ No newline at end of file
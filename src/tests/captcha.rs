@@ -0,0 +1,423 @@
+#[cfg(test)]
+mod captcha_tests {
+    use crate::captcha::capmonster::CaptchaClient;
+    use crate::client::{ProxyConfig, ProxyType, WappuClient};
+
+    #[test]
+    fn test_proxyless_task() {
+        let captcha = CaptchaClient::new("key".to_string(), "NoCaptchaTaskProxyless".to_string());
+        let task = captcha.build_task(
+            "NoCaptchaTaskProxyless",
+            "https://example.com".to_string(),
+            "site-key".to_string(),
+            None,
+        );
+        let json = serde_json::to_value(&task).unwrap();
+
+        assert_eq!(json["type"], "NoCaptchaTaskProxyless");
+        assert_eq!(json["websiteURL"], "https://example.com");
+        assert_eq!(json["websiteKey"], "site-key");
+        assert!(json.get("proxyType").is_none());
+        assert!(json.get("userAgent").is_none());
+        assert!(json.get("cookies").is_none());
+    }
+
+    #[test]
+    fn test_task_with_configured_proxy() {
+        let proxy = ProxyConfig::new(ProxyType::Http, "10.0.0.1", 8080).credentials("user", "pass");
+        let captcha = CaptchaClient::new("key".to_string(), "NoCaptchaTaskProxyless".to_string())
+            .proxy(proxy)
+            .user_agent("WappuClient/1.0")
+            .cookies("session=abc; theme=dark");
+        let task = captcha.build_task(
+            "NoCaptchaTaskProxyless",
+            "https://example.com".to_string(),
+            "site-key".to_string(),
+            None,
+        );
+        let json = serde_json::to_value(&task).unwrap();
+
+        assert_eq!(json["type"], "NoCaptchaTask");
+        assert_eq!(json["proxyType"], "http");
+        assert_eq!(json["proxyAddress"], "10.0.0.1");
+        assert_eq!(json["proxyPort"], 8080);
+        assert_eq!(json["proxyLogin"], "user");
+        assert_eq!(json["proxyPassword"], "pass");
+        assert_eq!(json["userAgent"], "WappuClient/1.0");
+        assert_eq!(json["cookies"], "session=abc; theme=dark");
+    }
+
+    #[test]
+    fn test_task_derived_from_session() {
+        let session = WappuClient::new()
+            .proxy(ProxyConfig::new(ProxyType::Https, "proxy.local", 3128))
+            .unwrap()
+            .user_agent("SessionAgent/2.0");
+        let captcha = CaptchaClient::new("key".to_string(), "HCaptchaTaskProxyless".to_string())
+            .user_agent("IgnoredAgent/1.0");
+        let task = captcha.build_task(
+            "HCaptchaTaskProxyless",
+            "https://example.com".to_string(),
+            "site-key".to_string(),
+            Some(&session),
+        );
+        let json = serde_json::to_value(&task).unwrap();
+
+        assert_eq!(json["type"], "HCaptchaTask");
+        assert_eq!(json["proxyType"], "https");
+        assert_eq!(json["proxyAddress"], "proxy.local");
+        assert_eq!(json["proxyPort"], 3128);
+        assert!(json.get("proxyLogin").is_none());
+        assert_eq!(json["userAgent"], "SessionAgent/2.0");
+    }
+}
+
+#[cfg(test)]
+mod captcha_detect_tests {
+    use crate::captcha::detect::{detect_captcha, inject_token, CaptchaKind};
+    use crate::html::HtmlParser;
+    use crate::selector::Selector;
+
+    #[test]
+    fn test_detect_recaptcha_widget() {
+        let html = r#"
+            <form action="/login" method="post">
+                <input name="user">
+                <div class="g-recaptcha" data-sitekey="6Lc-recaptcha-key"></div>
+            </form>
+        "#;
+        let document = HtmlParser::new().parse_html(html);
+        let captcha = detect_captcha(&document).expect("captcha not detected");
+
+        assert_eq!(captcha.kind, CaptchaKind::ReCaptcha);
+        assert_eq!(captcha.site_key, "6Lc-recaptcha-key");
+    }
+
+    #[test]
+    fn test_detect_kind_from_script() {
+        let html = r#"
+            <script src="https://challenges.cloudflare.com/turnstile/v0/api.js" async></script>
+            <form><div id="widget" data-sitekey="0x4AAA-turnstile"></div></form>
+        "#;
+        let document = HtmlParser::new().parse_html(html);
+        let captcha = detect_captcha(&document).expect("captcha not detected");
+
+        assert_eq!(captcha.kind, CaptchaKind::Turnstile);
+        assert_eq!(captcha.site_key, "0x4AAA-turnstile");
+    }
+
+    #[test]
+    fn test_no_captcha_detected() {
+        let document = HtmlParser::new().parse_html("<form><input name=\"q\"></form>");
+        assert!(detect_captcha(&document).is_none());
+    }
+
+    #[test]
+    fn test_inject_into_existing_field() {
+        let html = r#"
+            <form id="search"><input name="q"></form>
+            <form id="login">
+                <div class="g-recaptcha" data-sitekey="key"></div>
+                <textarea name="g-recaptcha-response"></textarea>
+            </form>
+        "#;
+        let mut document = HtmlParser::new().parse_html(html);
+
+        assert!(inject_token(
+            &mut document,
+            CaptchaKind::ReCaptcha,
+            "solved-token"
+        ));
+
+        let textarea = Selector::new()
+            .from_tag_name("textarea")
+            .select_first(&document)
+            .unwrap();
+        assert_eq!(textarea.text, "solved-token");
+    }
+
+    #[test]
+    fn test_inject_adds_missing_fields() {
+        let html = r#"
+            <form id="signup">
+                <div class="h-captcha" data-sitekey="key"></div>
+            </form>
+        "#;
+        let mut document = HtmlParser::new().parse_html(html);
+
+        assert!(inject_token(
+            &mut document,
+            CaptchaKind::HCaptcha,
+            "solved-token"
+        ));
+
+        let form = Selector::new()
+            .from_tag_name("form")
+            .select_first(&document)
+            .unwrap();
+        let fields: Vec<(&str, &str)> = form
+            .children
+            .iter()
+            .filter(|child| child.tag_name.as_deref() == Some("textarea"))
+            .map(|child| (child.attributes["name"].as_str(), child.text.as_str()))
+            .collect();
+        assert_eq!(
+            fields,
+            vec![
+                ("h-captcha-response", "solved-token"),
+                ("g-recaptcha-response", "solved-token"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_inject_without_form() {
+        let mut document = HtmlParser::new().parse_html(r#"<div data-sitekey="key"></div>"#);
+        assert!(!inject_token(
+            &mut document,
+            CaptchaKind::ReCaptcha,
+            "token"
+        ));
+    }
+}
+
+#[cfg(test)]
+mod captcha_pool_tests {
+    use crate::captcha::capmonster::CaptchaClient;
+    use crate::captcha::pool::{PoolMetrics, TokenPool};
+    use crate::client::WappuError;
+    use crate::html::HtmlParser;
+    use crate::select_by_tag_name;
+    use crate::selector::Selector;
+    use crate::tests::server::{serve, MockRequest, MockResponse};
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    // Stands in for the Capmonster API, solving every task immediately with a numbered token
+    async fn capmonster_mock() -> (CaptchaClient, Arc<AtomicU64>) {
+        let created = Arc::new(AtomicU64::new(0));
+        let counter = created.clone();
+        let url = serve(move |request: MockRequest| {
+            if request.path == "/createTask" {
+                let task_id = counter.fetch_add(1, Ordering::SeqCst) + 1;
+                MockResponse::ok(&format!(r#"{{"errorId":0,"taskId":{}}}"#, task_id))
+            } else {
+                let body: serde_json::Value = serde_json::from_str(&request.body).unwrap();
+                MockResponse::ok(&format!(
+                    r#"{{"status":"ready","solution":{{"gRecaptchaResponse":"token-{}"}}}}"#,
+                    body["taskId"]
+                ))
+            }
+        })
+        .await;
+
+        let client = CaptchaClient::new("key".to_string(), "NoCaptchaTaskProxyless".to_string())
+            .api_url(&url);
+        (client, created)
+    }
+
+    async fn wait_for_tokens(pool: &TokenPool, count: usize) {
+        for _ in 0..100 {
+            if pool.available("https://example.com", "site-key") >= count {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        panic!("pool never reached {} tokens", count);
+    }
+
+    #[tokio::test]
+    async fn test_solve_captcha_with_api_url() {
+        let (client, _) = capmonster_mock().await;
+        let token = client
+            .solve_captcha("https://example.com".to_string(), "site-key".to_string())
+            .await
+            .unwrap();
+        assert_eq!(token, "token-1");
+    }
+
+    #[tokio::test]
+    async fn test_solve_page_checks_for_a_form_first() {
+        let (client, created) = capmonster_mock().await;
+
+        let mut document = HtmlParser::new()
+            .parse_html(r#"<div class="g-recaptcha" data-sitekey="site-key"></div>"#);
+        let result = client
+            .solve_page(&mut document, "https://example.com")
+            .await;
+        assert!(matches!(result, Err(WappuError::CapmonsterError(_))));
+        // Nothing was paid for
+        assert_eq!(created.load(Ordering::SeqCst), 0);
+
+        let mut document = HtmlParser::new()
+            .parse_html(r#"<form><div class="g-recaptcha" data-sitekey="site-key"></div></form>"#);
+        let captcha = client
+            .solve_page(&mut document, "https://example.com")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(captcha.site_key, "site-key");
+        assert_eq!(created.load(Ordering::SeqCst), 1);
+        let field = select_by_tag_name!(&document, "textarea");
+        assert_eq!(field.elements[0].text, "token-1");
+    }
+
+    #[tokio::test]
+    async fn test_solve_page_skips_recaptcha_v3() {
+        let (client, created) = capmonster_mock().await;
+        let mut document = HtmlParser::new().parse_html(
+            r#"<script src="https://www.google.com/recaptcha/api.js?render=site-key"></script>
+            <form><button>Send</button></form>"#,
+        );
+        let result = client
+            .solve_page(&mut document, "https://example.com")
+            .await;
+        assert!(matches!(result, Ok(None)));
+        assert_eq!(created.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_pool_hits_after_warm() {
+        let (client, _) = capmonster_mock().await;
+        let pool = TokenPool::new(client, 2, Duration::from_secs(60));
+
+        pool.warm("https://example.com", "site-key");
+        wait_for_tokens(&pool, 2).await;
+
+        let token = pool.token("https://example.com", "site-key").await.unwrap();
+        assert!(token.starts_with("token-"));
+        assert_eq!(
+            pool.metrics(),
+            PoolMetrics {
+                hits: 1,
+                misses: 0,
+                wasted: 0,
+                solved: 2,
+                failed: 0,
+            }
+        );
+
+        // The handed out token is replaced in the background
+        wait_for_tokens(&pool, 2).await;
+    }
+
+    #[tokio::test]
+    async fn test_pool_miss_waits_for_background_solve() {
+        let (client, created) = capmonster_mock().await;
+        let pool = TokenPool::new(client, 1, Duration::from_secs(60));
+
+        let token = pool.token("https://example.com", "site-key").await.unwrap();
+        assert_eq!(token, "token-1");
+
+        let metrics = pool.metrics();
+        assert_eq!(metrics.hits, 0);
+        assert_eq!(metrics.misses, 1);
+
+        // The caller took the background solve's token instead of paying for one of its own,
+        // and a second solve fills the pool again
+        wait_for_tokens(&pool, 1).await;
+        assert_eq!(created.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_pool_backs_off_after_failures() {
+        let created = Arc::new(AtomicU64::new(0));
+        let counter = created.clone();
+        let url = serve(move |_: MockRequest| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            MockResponse::ok(r#"{"errorId":1,"taskId":0}"#)
+        })
+        .await;
+        let client =
+            CaptchaClient::new("bad-key".to_string(), "NoCaptchaTaskProxyless".to_string())
+                .api_url(&url);
+        let pool = TokenPool::new(client, 3, Duration::from_secs(60));
+
+        // Waiting callers get the error instead of hanging
+        let result = pool.token("https://example.com", "site-key").await;
+        assert!(matches!(result, Err(WappuError::CapmonsterError(_))));
+
+        // The first round of solves failed, nothing more is tried while backing off
+        tokio::time::sleep(Duration::from_millis(1500)).await;
+        assert_eq!(created.load(Ordering::SeqCst), 3);
+        assert_eq!(pool.metrics().failed, 3);
+    }
+
+    #[tokio::test]
+    async fn test_pool_keeps_a_minimum_lifetime() {
+        let (client, _) = capmonster_mock().await;
+        // Refilled once a second, so nothing replaces the token before it's checked
+        let pool = TokenPool::new(client, 1, Duration::from_secs(4))
+            .min_lifetime(Duration::from_millis(3700));
+
+        pool.warm("https://example.com", "site-key");
+        wait_for_tokens(&pool, 1).await;
+        // Still valid for about 3.6 seconds, too little to hand out
+        tokio::time::sleep(Duration::from_millis(400)).await;
+        assert_eq!(pool.available("https://example.com", "site-key"), 0);
+        assert!(pool.metrics().wasted >= 1);
+    }
+
+    #[tokio::test]
+    async fn test_pool_discards_expired_tokens() {
+        let (client, _) = capmonster_mock().await;
+        let pool = TokenPool::new(client, 1, Duration::from_millis(200));
+
+        pool.warm("https://example.com", "site-key");
+        wait_for_tokens(&pool, 1).await;
+        tokio::time::sleep(Duration::from_millis(400)).await;
+
+        // The expired token was thrown away and a new one solved in its place
+        assert!(pool.metrics().wasted >= 1);
+        wait_for_tokens(&pool, 1).await;
+        assert!(pool.metrics().solved >= 2);
+    }
+
+    #[tokio::test]
+    async fn test_pool_recovers_after_inline_solve() {
+        // The first task fails, the ones after it are solved
+        let created = Arc::new(AtomicU64::new(0));
+        let counter = created.clone();
+        let url = serve(move |request: MockRequest| {
+            if request.path == "/createTask" {
+                match counter.fetch_add(1, Ordering::SeqCst) + 1 {
+                    1 => MockResponse::ok(r#"{"errorId":1,"taskId":0}"#),
+                    task_id => {
+                        MockResponse::ok(&format!(r#"{{"errorId":0,"taskId":{}}}"#, task_id))
+                    }
+                }
+            } else {
+                let body: serde_json::Value = serde_json::from_str(&request.body).unwrap();
+                MockResponse::ok(&format!(
+                    r#"{{"status":"ready","solution":{{"gRecaptchaResponse":"token-{}"}}}}"#,
+                    body["taskId"]
+                ))
+            }
+        })
+        .await;
+        let client = CaptchaClient::new("key".to_string(), "NoCaptchaTaskProxyless".to_string())
+            .api_url(&url);
+        let pool = TokenPool::new(client, 1, Duration::from_secs(60));
+
+        let result = pool.token("https://example.com", "site-key").await;
+        assert!(matches!(result, Err(WappuError::CapmonsterError(_))));
+
+        // Backing off, so the caller's token is solved right away and counted
+        let token = pool.token("https://example.com", "site-key").await.unwrap();
+        assert_eq!(token, "token-2");
+        let metrics = pool.metrics();
+        assert_eq!((metrics.solved, metrics.failed), (1, 1));
+
+        // The success ended the backoff, the pool is refilled without waiting it out
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert_eq!(pool.available("https://example.com", "site-key"), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "shorter than the token TTL")]
+    fn test_pool_rejects_min_lifetime_past_ttl() {
+        let client = CaptchaClient::new("key".to_string(), "NoCaptchaTaskProxyless".to_string());
+        TokenPool::new(client, 1, Duration::from_secs(60)).min_lifetime(Duration::from_secs(60));
+    }
+}
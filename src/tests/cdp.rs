@@ -0,0 +1,452 @@
+#[cfg(test)]
+mod cdp_tests {
+    use crate::cdp::CdpBrowser;
+    use crate::client::{WappuClient, WappuError};
+    use crate::cookies::{Cookie, SameSite};
+    use crate::select_by_class;
+    use crate::selector::Selector;
+    use crate::tests::server::{serve, MockRequest, MockResponse};
+    use futures_util::{SinkExt, StreamExt};
+    use serde_json::{json, Value};
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::tungstenite::Message;
+
+    const SESSION: &str = "5F3A9C0B1E";
+
+    // One recorded command: what the browser answered and the events it sent right after
+    struct Exchange {
+        method: &'static str,
+        result: Value,
+        events: Vec<Value>,
+    }
+
+    fn exchange(method: &'static str, result: Value) -> Exchange {
+        Exchange {
+            method,
+            result,
+            events: Vec::new(),
+        }
+    }
+
+    impl Exchange {
+        fn then(mut self, method: &str, params: Value) -> Self {
+            self.events
+                .push(json!({ "method": method, "params": params, "sessionId": SESSION }));
+            self
+        }
+    }
+
+    // Stand-in for the browser: answers each command with the next recorded exchange and
+    // returns its ws:// URL along with every command it received
+    async fn replay(exchanges: Vec<Exchange>) -> (String, Arc<Mutex<Vec<Value>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!(
+            "ws://{}/devtools/browser/replay",
+            listener.local_addr().unwrap()
+        );
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let log = received.clone();
+
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+            let mut exchanges = exchanges.into_iter();
+            while let Some(Ok(Message::Text(text))) = socket.next().await {
+                let command: Value = serde_json::from_str(&text).unwrap();
+                log.lock().unwrap().push(command.clone());
+
+                let (mut answer, events) = match exchanges.next() {
+                    Some(exchange) if exchange.method == command["method"] => (
+                        json!({ "id": command["id"], "result": exchange.result }),
+                        exchange.events,
+                    ),
+                    _ => (
+                        json!({
+                            "id": command["id"],
+                            "error": { "code": -32601, "message": "Unexpected command" }
+                        }),
+                        Vec::new(),
+                    ),
+                };
+                if let Some(session) = command.get("sessionId") {
+                    answer["sessionId"] = session.clone();
+                }
+                // Events go out after the answer, like the browser does
+                for message in std::iter::once(answer).chain(events) {
+                    socket
+                        .send(Message::Text(message.to_string()))
+                        .await
+                        .unwrap();
+                }
+            }
+        });
+        (url, received)
+    }
+
+    fn page_setup() -> Vec<Exchange> {
+        vec![
+            exchange("Target.createTarget", json!({ "targetId": "TARGET1" })),
+            exchange("Target.attachToTarget", json!({ "sessionId": SESSION })),
+            exchange("Page.enable", json!({})),
+            exchange("Runtime.enable", json!({})),
+            exchange("Network.enable", json!({})),
+        ]
+    }
+
+    fn value(value: Value) -> Value {
+        json!({ "result": { "type": "string", "value": value } })
+    }
+
+    #[tokio::test]
+    async fn test_navigate_render_and_share_cookies() {
+        let html = r#"<html><head></head><body><div class="item">Rendered</div></body></html>"#;
+        let mut exchanges = page_setup();
+        exchanges.extend(vec![
+            exchange("Network.setCookies", json!({})),
+            exchange(
+                "Page.navigate",
+                json!({ "frameId": "FRAME1", "loaderId": "LOADER1" }),
+            )
+            .then("Page.frameStartedLoading", json!({ "frameId": "FRAME1" }))
+            .then("Page.loadEventFired", json!({ "timestamp": 1.5 })),
+            exchange("Runtime.evaluate", value(json!(false))),
+            exchange("Runtime.evaluate", value(json!(true))),
+            exchange("Runtime.evaluate", value(json!(html))),
+            exchange(
+                "Network.getCookies",
+                json!({ "cookies": [
+                    { "name": "session", "value": "abc", "domain": "shop.test", "path": "/", "secure": false },
+                    { "name": "consent", "value": "yes", "domain": ".shop.test", "path": "/", "secure": false }
+                ] }),
+            ),
+            exchange(
+                "Runtime.evaluate",
+                json!({
+                    "result": { "type": "object" },
+                    "exceptionDetails": {
+                        "text": "Uncaught",
+                        "exception": { "description": "ReferenceError: missing is not defined" }
+                    }
+                }),
+            ),
+        ]);
+        let (ws_url, received) = replay(exchanges).await;
+
+        // The debugging endpoint points at the WebSocket, as Chromium's /json/version does
+        let endpoint = serve(move |request: MockRequest| match request.path.as_str() {
+            "/json/version" => MockResponse::ok(
+                &json!({ "Browser": "Chrome/122.0", "webSocketDebuggerUrl": ws_url }).to_string(),
+            ),
+            _ => MockResponse::status(404, "not found"),
+        })
+        .await;
+
+        let browser = CdpBrowser::connect(&endpoint)
+            .await
+            .unwrap()
+            .timeout(Duration::from_secs(5));
+        let page = browser.new_page().await.unwrap();
+        assert_eq!(page.target_id(), "TARGET1");
+
+        let client = WappuClient::new().cookie_store(true);
+        client.add_cookie("visitor=42; Path=/", "http://shop.test/");
+        page.import_cookies(&client, "http://shop.test/")
+            .await
+            .unwrap();
+        page.navigate("http://shop.test/").await.unwrap();
+        page.wait_for_selector(".item").await.unwrap();
+
+        let document = page.document().await.unwrap();
+        assert_eq!(select_by_class!(&document, "item").text(), "Rendered");
+
+        page.export_cookies(&client, "http://shop.test/")
+            .await
+            .unwrap();
+        let cookies = client.cookies_for("http://shop.test/cart").unwrap();
+        assert!(cookies.contains("session=abc"));
+        assert!(cookies.contains("consent=yes"));
+        assert!(cookies.contains("visitor=42"));
+
+        match page.evaluate("missing()").await {
+            Err(WappuError::ScriptError(message)) => assert!(message.contains("ReferenceError")),
+            other => panic!("unexpected result {:?}", other.map(|_| ())),
+        }
+
+        let received = received.lock().unwrap();
+        let methods: Vec<&str> = received
+            .iter()
+            .map(|command| command["method"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            methods[..6],
+            [
+                "Target.createTarget",
+                "Target.attachToTarget",
+                "Page.enable",
+                "Runtime.enable",
+                "Network.enable",
+                "Network.setCookies"
+            ]
+        );
+        assert!(received[1].get("sessionId").is_none());
+        assert_eq!(received[2]["sessionId"], SESSION);
+        assert_eq!(
+            received[5]["params"]["cookies"][0],
+            json!({
+                "name": "visitor",
+                "value": "42",
+                "path": "/",
+                "secure": false,
+                "httpOnly": false,
+                "url": "http://shop.test/"
+            })
+        );
+        assert_eq!(
+            received[7]["params"]["expression"],
+            "document.querySelector(\".item\") !== null"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_intercept_requests() {
+        let paused = json!({
+            "requestId": "interception-1",
+            "resourceType": "Fetch",
+            "request": {
+                "url": "http://shop.test/api/prices",
+                "method": "POST",
+                "headers": { "Content-Type": "application/json" },
+                "postData": "{\"ids\":[1]}"
+            }
+        });
+        let mut exchanges = page_setup();
+        exchanges.extend(vec![
+            exchange("Fetch.enable", json!({})),
+            exchange("Runtime.evaluate", value(json!(null)))
+                .then("Fetch.requestPaused", paused.clone()),
+            exchange("Fetch.fulfillRequest", json!({})),
+            exchange(
+                "Page.navigate",
+                json!({ "errorText": "net::ERR_NAME_NOT_RESOLVED" }),
+            ),
+            exchange("Target.closeTarget", json!({ "success": true })),
+        ]);
+        let (ws_url, received) = replay(exchanges).await;
+
+        let browser = CdpBrowser::connect(&ws_url).await.unwrap();
+        let page = browser.new_page().await.unwrap();
+        let mut events = page.events();
+        page.intercept(&["*/api/*"]).await.unwrap();
+        page.evaluate("fetch('/api/prices', { method: 'POST' })")
+            .await
+            .unwrap();
+
+        let request = page.paused_request(&mut events).await.unwrap();
+        assert_eq!(request.request.url, "http://shop.test/api/prices");
+        assert_eq!(request.request.post_data.as_deref(), Some("{\"ids\":[1]}"));
+        page.fulfill_request(
+            &request,
+            200,
+            &[("Content-Type", "application/json")],
+            "[9.5]",
+        )
+        .await
+        .unwrap();
+
+        let error = page.navigate("http://missing.test/").await.unwrap_err();
+        assert!(error.to_string().contains("ERR_NAME_NOT_RESOLVED"));
+        page.close().await.unwrap();
+
+        let received = received.lock().unwrap();
+        assert_eq!(
+            received[5]["params"]["patterns"],
+            json!([{ "urlPattern": "*/api/*" }])
+        );
+        let fulfill = &received[7]["params"];
+        assert_eq!(fulfill["requestId"], "interception-1");
+        assert_eq!(fulfill["responseCode"], 200);
+        assert_eq!(fulfill["body"], "WzkuNV0=");
+    }
+
+    #[tokio::test]
+    async fn test_cookie_attributes_round_trip() {
+        let expires = UNIX_EPOCH + Duration::from_secs(4_102_444_800);
+        let session = Cookie {
+            name: "session".to_string(),
+            value: "abc".to_string(),
+            domain: "shop.test".to_string(),
+            host_only: true,
+            path: "/account".to_string(),
+            secure: true,
+            http_only: true,
+            expires: Some(expires),
+            same_site: Some(SameSite::Strict),
+        };
+        let consent = Cookie {
+            name: "consent".to_string(),
+            value: "yes".to_string(),
+            domain: "shop.test".to_string(),
+            host_only: false,
+            path: "/".to_string(),
+            secure: false,
+            http_only: false,
+            expires: None,
+            same_site: None,
+        };
+        let client = WappuClient::new().cookie_store(true);
+        client.set_cookie(&session, "https://shop.test/account");
+        client.set_cookie(&consent, "https://shop.test/account");
+
+        let mut exchanges = page_setup();
+        exchanges.extend(vec![
+            exchange("Network.setCookies", json!({})),
+            // What the browser reports for the cookies it was given
+            exchange(
+                "Network.getCookies",
+                json!({ "cookies": [
+                    {
+                        "name": "session", "value": "abc", "domain": "shop.test",
+                        "path": "/account", "expires": 4102444800.0, "size": 10,
+                        "httpOnly": true, "secure": true, "session": false, "sameSite": "Strict"
+                    },
+                    {
+                        "name": "consent", "value": "yes", "domain": ".shop.test",
+                        "path": "/", "expires": -1, "size": 10,
+                        "httpOnly": false, "secure": false, "session": true
+                    }
+                ] }),
+            ),
+        ]);
+        let (ws_url, received) = replay(exchanges).await;
+        let browser = CdpBrowser::connect(&ws_url).await.unwrap();
+        let page = browser.new_page().await.unwrap();
+
+        page.import_cookies(&client, "https://shop.test/account")
+            .await
+            .unwrap();
+        let copy = WappuClient::new().cookie_store(true);
+        page.export_cookies(&copy, "https://shop.test/account")
+            .await
+            .unwrap();
+
+        let sent = received.lock().unwrap()[5]["params"]["cookies"].clone();
+        let sent = sent.as_array().unwrap();
+        let sent_session = sent.iter().find(|cookie| cookie["name"] == "session");
+        assert_eq!(
+            sent_session.unwrap(),
+            &json!({
+                "name": "session",
+                "value": "abc",
+                "path": "/account",
+                "secure": true,
+                "httpOnly": true,
+                "url": "https://shop.test/account",
+                "expires": 4102444800.0,
+                "sameSite": "Strict"
+            })
+        );
+        let sent_consent = sent.iter().find(|cookie| cookie["name"] == "consent");
+        assert_eq!(sent_consent.unwrap()["domain"], ".shop.test");
+        assert!(sent_consent.unwrap().get("expires").is_none());
+        assert!(sent_consent.unwrap().get("sameSite").is_none());
+
+        let mut cookies = copy.cookies("https://shop.test/account");
+        cookies.sort_by(|a, b| b.name.cmp(&a.name));
+        assert_eq!(cookies, [session, consent]);
+        // The attributes still decide where the cookies go
+        assert_eq!(
+            copy.cookies_for("http://shop.test/").unwrap(),
+            "consent=yes"
+        );
+        assert_eq!(
+            copy.cookies_for("https://www.shop.test/").unwrap(),
+            "consent=yes"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cookies_are_optional() {
+        let (ws_url, received) = replay(page_setup()).await;
+        let browser = CdpBrowser::connect(&ws_url).await.unwrap();
+        let page = browser.new_page().await.unwrap();
+
+        // Nothing to send without a cookie store, or for expired cookies
+        page.import_cookies(&WappuClient::new(), "http://shop.test/")
+            .await
+            .unwrap();
+        let client = WappuClient::new().cookie_store(true);
+        client.set_cookie(
+            &Cookie {
+                name: "old".to_string(),
+                value: "1".to_string(),
+                domain: "shop.test".to_string(),
+                host_only: true,
+                path: "/".to_string(),
+                secure: false,
+                http_only: false,
+                expires: Some(SystemTime::now() - Duration::from_secs(60)),
+                same_site: None,
+            },
+            "http://shop.test/",
+        );
+        page.import_cookies(&client, "http://shop.test/")
+            .await
+            .unwrap();
+        assert!(client.cookies("http://shop.test/").is_empty());
+        assert_eq!(received.lock().unwrap().len(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_navigate_within_document() {
+        let mut exchanges = page_setup();
+        exchanges.extend(vec![
+            // Same-document navigations have no loader and never fire a load event
+            exchange("Page.navigate", json!({ "frameId": "FRAME1" })).then(
+                "Page.navigatedWithinDocument",
+                json!({ "frameId": "FRAME1", "url": "http://shop.test/#reviews" }),
+            ),
+            exchange(
+                "Page.navigate",
+                json!({ "frameId": "FRAME1", "loaderId": "LOADER2" }),
+            )
+            .then("Page.frameStartedLoading", json!({ "frameId": "FRAME1" }))
+            // A child frame finishing doesn't finish the navigation
+            .then("Page.frameStoppedLoading", json!({ "frameId": "CHILD" }))
+            .then("Page.frameStoppedLoading", json!({ "frameId": "FRAME1" })),
+        ]);
+        let (ws_url, _) = replay(exchanges).await;
+        let browser = CdpBrowser::connect(&ws_url)
+            .await
+            .unwrap()
+            .timeout(Duration::from_secs(5));
+        let page = browser.new_page().await.unwrap();
+
+        page.navigate("http://shop.test/#reviews").await.unwrap();
+        page.navigate("http://shop.test/report.pdf").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_navigate_times_out() {
+        let mut exchanges = page_setup();
+        exchanges.push(
+            exchange(
+                "Page.navigate",
+                json!({ "frameId": "FRAME1", "loaderId": "LOADER1" }),
+            )
+            .then("Page.frameStoppedLoading", json!({ "frameId": "CHILD" })),
+        );
+        let (ws_url, _) = replay(exchanges).await;
+        let browser = CdpBrowser::connect(&ws_url)
+            .await
+            .unwrap()
+            .timeout(Duration::from_millis(300));
+        let page = browser.new_page().await.unwrap();
+
+        let error = page.navigate("http://shop.test/slow").await.unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("timed out waiting for the page to load"));
+    }
+}
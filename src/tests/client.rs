@@ -47,7 +47,7 @@ mod client_tests {
     async fn test_wappu_client_head() {
         let client = WappuClient::new();
         let result = client.head("https://httpbin.org/get", None).await;
-        assert!(matches!(result, Ok(_)));
+        assert!(result.is_ok());
     }
 }
 
@@ -304,4 +304,26 @@ mod client_error_tests {
         assert!(body.contains(r#""macroTest": "passed""#));
         assert!(body.contains(r#""anotherParam": "12345""#));
     }
+
+    #[test]
+    fn test_cookie_store_is_opt_in() {
+        let client = WappuClient::new();
+        assert!(!client.has_cookie_store());
+        client.add_cookie("session=abc; Path=/", "https://shop.test/");
+        assert_eq!(client.cookies_for("https://shop.test/"), None);
+
+        let client = client.cookie_store(true);
+        client.add_cookie("session=abc; Path=/", "https://shop.test/");
+        // Clones share the store
+        let clone = client.clone();
+        clone.add_cookie("theme=dark; Path=/", "https://shop.test/");
+        let cookies = client.cookies_for("https://shop.test/cart").unwrap();
+        assert!(cookies.contains("session=abc"));
+        assert!(cookies.contains("theme=dark"));
+        // Turning it on again keeps the cookies, turning it off drops them
+        let client = client.cookie_store(true);
+        assert!(client.cookies_for("https://shop.test/").is_some());
+        let client = client.cookie_store(false);
+        assert_eq!(client.cookies_for("https://shop.test/"), None);
+    }
 }
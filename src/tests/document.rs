@@ -0,0 +1,182 @@
+#[cfg(test)]
+mod document_tests {
+    use crate::document::{Document, NodeHandle, NodeType};
+    use crate::html::{HtmlElement, HtmlParser};
+    use crate::selector::Selector;
+    use crate::xml::XmlParser;
+
+    const PAGE: &str = r#"<!DOCTYPE html><html lang="en"><head><title>Shop</title></head><body>
+        <!-- listing -->
+        <div id="products">
+            <div class="card product" data-id="1"><h2>First</h2><span class="price">$1</span></div>
+            <div class="card product" data-id="2"><h2>Second <b>item</b></h2><span class="price">$2</span></div>
+        </div>
+        <p class="price-note">Prices include tax</p>
+    </body></html>"#;
+
+    #[test]
+    fn test_round_trip() {
+        let element = HtmlParser::new().parse_html(PAGE);
+        let document = Document::parse_html(PAGE);
+        assert_eq!(document.to_element(), element);
+        assert_eq!(Document::from_element(&element).to_element(), element);
+
+        let fragment = HtmlParser::new().parse_fragment("<li>One</li><li>Two</li>", "ul");
+        let document = Document::from_element(&fragment);
+        assert_eq!(document.root().node_type(), NodeType::Fragment);
+        assert_eq!(document.to_element(), fragment);
+    }
+
+    #[test]
+    fn test_select_matches_html_element() {
+        let element = HtmlParser::new().parse_html(PAGE);
+        let document = Document::parse_html(PAGE);
+        let mut selectors = [Selector::new(), Selector::new(), Selector::new()];
+        selectors[0].from_class_name("price");
+        selectors[1].from_tag_name("h2");
+        selectors[2].from_id("products").from_tag_name("b");
+
+        for selector in &selectors {
+            let expected: Vec<_> = selector
+                .select(&element)
+                .elements
+                .into_iter()
+                .cloned()
+                .collect();
+            let selected: Vec<_> = document
+                .select(selector)
+                .iter()
+                .map(|node| node.to_element())
+                .collect();
+            assert_eq!(selected, expected);
+        }
+    }
+
+    #[test]
+    fn test_navigation() {
+        let document = Document::parse_html(PAGE);
+        let root = document.root();
+        assert_eq!(root.node_type(), NodeType::Document);
+        assert_eq!(
+            root.children().next().unwrap().node_type(),
+            NodeType::Doctype
+        );
+
+        let mut by_id = Selector::new();
+        by_id.from_id("products");
+        let products = document.select(&by_id)[0];
+        assert_eq!(products.tag_name(), Some("div"));
+        assert_eq!(products.parent().unwrap().tag_name(), Some("body"));
+
+        let cards: Vec<_> = products
+            .children()
+            .filter(|child| child.is_element())
+            .collect();
+        assert_eq!(cards.len(), 2);
+        assert_eq!(cards[1].attribute("data-id"), Some("2"));
+        assert_eq!(cards[1].attribute("class"), Some("card product"));
+        assert_eq!(cards[1].attribute("missing"), None);
+        assert_eq!(cards[1].text_content(), "Second item$2");
+
+        let heading = cards[1].children().next().unwrap();
+        assert_eq!(heading.text(), "Second ");
+        assert_eq!(document.get(heading.id()).unwrap().tag_name(), Some("h2"));
+
+        let mut by_tag_name = Selector::new();
+        by_tag_name.from_tag_name("h2");
+        assert_eq!(cards[0].select(&by_tag_name).len(), 1);
+
+        let comment = root
+            .descendants()
+            .find(|node| node.node_type() == NodeType::Comment)
+            .unwrap();
+        assert_eq!(comment.value(), Some(" listing "));
+    }
+
+    #[test]
+    fn test_namespaced_elements() {
+        let feed = XmlParser::new().parse_xml(
+            r#"<feed xmlns="http://www.w3.org/2005/Atom"><entry><title>One</title></entry><entry><title>Two</title></entry></feed>"#,
+        );
+        let document = Document::from_element(&feed);
+        let mut selector = Selector::new();
+        selector.from_namespaced_name("http://www.w3.org/2005/Atom", "entry");
+
+        let entries = document.select(&selector);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].namespace(), Some("http://www.w3.org/2005/Atom"));
+        assert_eq!(entries[1].text_content(), "Two");
+        assert_eq!(document.to_element(), feed);
+    }
+
+    #[test]
+    fn test_thread_safe() {
+        fn assert_send_sync<T: Send + Sync + 'static>() {}
+        assert_send_sync::<Document>();
+        assert_send_sync::<NodeHandle>();
+        assert_send_sync::<HtmlElement>();
+
+        let document = Document::parse_html(PAGE);
+        let mut selector = Selector::new();
+        selector.from_class_name("price");
+        let counts: Vec<usize> = std::thread::scope(|scope| {
+            let workers: Vec<_> = (0..4)
+                .map(|_| {
+                    let document = document.clone();
+                    let selector = &selector;
+                    scope.spawn(move || document.select(selector).len())
+                })
+                .collect();
+            workers
+                .into_iter()
+                .map(|worker| worker.join().unwrap())
+                .collect()
+        });
+        assert_eq!(counts, [2, 2, 2, 2]);
+    }
+
+    #[tokio::test]
+    async fn test_handles_across_tasks() {
+        let document = Document::parse_html(PAGE);
+        let mut selector = Selector::new();
+        selector.from_tag_name("h2");
+        let headings: Vec<NodeHandle> = document
+            .select(&selector)
+            .iter()
+            .map(|node| node.to_handle())
+            .collect();
+        drop(document);
+
+        let tasks: Vec<_> = headings
+            .into_iter()
+            .map(|heading| tokio::spawn(async move { heading.node().text_content() }))
+            .collect();
+        let mut texts = Vec::new();
+        for task in tasks {
+            texts.push(task.await.unwrap());
+        }
+        assert_eq!(texts, ["First", "Second item"]);
+    }
+
+    #[test]
+    fn test_selection_to_handles() {
+        let handles = {
+            let root = HtmlParser::new().parse_html(PAGE);
+            let mut selector = Selector::new();
+            selector.from_class_name("product");
+            let selection = selector.select(&root);
+            // Elements from another tree have no handle in this one
+            assert!(selection
+                .to_handles(&HtmlElement::new_element("div"))
+                .is_empty());
+            selection.to_handles(&root)
+        };
+        assert_eq!(handles.len(), 2);
+        assert_eq!(handles[1].node().attribute("data-id"), Some("2"));
+        assert_eq!(
+            handles[1].node().parent().unwrap().attribute("id"),
+            Some("products")
+        );
+        assert_eq!(handles[0].node().text_content(), "First$1");
+    }
+}
@@ -0,0 +1,86 @@
+#[cfg(test)]
+mod edit_tests {
+    use crate::html::{HtmlElement, HtmlParser};
+    use crate::selector::Selector;
+    use crate::{select_by_class, select_by_id, select_by_tag_name};
+
+    const PAGE: &str = r#"<html><head><script>track()</script></head><body>
+<nav class="menu"><a href="/">Home</a></nav>
+<div id="story" onclick="x()">Hello <font color="red">big <b>wide</b></font> world<div class="ad">Buy!</div>.</div>
+</body></html>"#;
+
+    #[test]
+    fn test_remove_matching() {
+        let mut document = HtmlParser::new().parse_html(PAGE);
+        let removed = document.remove(Selector::new().from_tag_name("script"))
+            + document.remove(Selector::new().from_tag_name("nav"))
+            + document.remove(Selector::new().from_class_name("ad"));
+        assert_eq!(removed, 3);
+
+        assert!(select_by_tag_name!(&document, "script").is_empty());
+        assert!(select_by_class!(&document, "menu").is_empty());
+        let story = select_by_id!(&document, "story").elements[0];
+        // The text around the removed element joins up again
+        assert_eq!(story.text, "Hello  world.");
+        assert_eq!(
+            story.inner_html(),
+            r#"Hello <font color="red">big <b>wide</b></font> world."#
+        );
+    }
+
+    #[test]
+    fn test_unwrap_and_attributes() {
+        let mut document = HtmlParser::new().parse_html(PAGE);
+        assert_eq!(document.unwrap(Selector::new().from_tag_name("font")), 1);
+
+        let edited = document.edit(Selector::new().from_id("story"), |story| {
+            story.remove_attribute("onclick");
+            story.set_attribute("data-clean", "\"yes\" & done");
+        });
+        assert_eq!(edited, 1);
+
+        let story = select_by_id!(&document, "story").elements[0];
+        assert_eq!(story.text, "Hello big  world.");
+        assert_eq!(
+            story.to_html(),
+            r#"<div data-clean="&quot;yes&quot; &amp; done" id="story">Hello big <b>wide</b> world<div class="ad">Buy!</div>.</div>"#
+        );
+    }
+
+    #[test]
+    fn test_insert_and_replace_text() {
+        let mut document = HtmlParser::new().parse_html(PAGE);
+        document.edit(Selector::new().from_tag_name("b"), |bold| {
+            bold.set_text("<narrow>")
+        });
+        document.edit(Selector::new().from_id("story"), |story| {
+            let mut note = HtmlElement::new_element("p");
+            note.append_child(HtmlElement::new_text("Edited"));
+            story.insert_child(0, note);
+            story.append_child(HtmlElement::new_comment(" cleaned "));
+            story.append_child(HtmlElement::new_text(" Bye"));
+        });
+
+        let story = select_by_id!(&document, "story").elements[0];
+        assert_eq!(story.text, "Hello  world. Bye");
+        assert!(story.inner_html().starts_with(
+            "<p>Edited</p>Hello <font color=\"red\">big <b>&lt;narrow&gt;</b></font>"
+        ));
+        assert!(story.inner_html().ends_with(".<!-- cleaned --> Bye"));
+
+        let mut story = HtmlParser::new().parse_fragment(&story.inner_html(), "div");
+        assert_eq!(story.remove_child(0).unwrap().text, "Edited");
+        assert!(story.remove_child(99).is_none());
+        assert_eq!(story.children[0].text, "Hello ");
+    }
+
+    #[test]
+    fn test_serialize_document() {
+        let html = "<!DOCTYPE html><html><head><style>a > b { color: red }</style></head><body><p>1 &lt; 2&nbsp;&amp; 3<br>next</p><!--note--></body></html>";
+        let document = HtmlParser::new().parse_html(html);
+        assert_eq!(document.to_html(), html);
+        // Parsing the output gives the same tree back
+        let reparsed = HtmlParser::new().parse_html(&document.to_html());
+        assert_eq!(reparsed.to_html(), html);
+    }
+}
@@ -0,0 +1,133 @@
+#[cfg(test)]
+mod embedded_tests {
+    use crate::embedded::parse_js_literal;
+    use crate::html::HtmlParser;
+    use serde::Deserialize;
+    use serde_json::{json, Value};
+
+    const PAGE: &str = r#"
+        <html><head>
+            <script type="application/ld+json">{"@type": "Product", "name": "Tea"}</script>
+            <script type="application/json" id="broken">{not json</script>
+            <script src="/app.js"></script>
+            <script>
+                if (window.__APP_STATE__ == null) { console.log("no state yet"); }
+                window.__APP_STATE__ = {
+                    user: { id: 42, name: 'Mia', 'last-seen': undefined },
+                    cart: [ { sku: "T-1", price: .5e1, }, ],
+                    flags: { beta: !0, legacy: !1 }, // minified
+                };
+                var config = JSON.parse("{\"locale\":\"fi-FI\",\"currency\":\"EUR\"}");
+            </script>
+        </head><body>
+            <script id="__NEXT_DATA__" type="application/json">
+                {"props": {"pageProps": {"product": {"id": 7, "title": "Green tea"}}}, "page": "/p/[id]"}
+            </script>
+        </body></html>
+    "#;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct NextData {
+        page: String,
+        props: Props,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    #[serde(rename_all = "camelCase")]
+    struct Props {
+        page_props: Value,
+    }
+
+    #[test]
+    fn test_next_data_and_json_scripts() {
+        let document = HtmlParser::new().parse_html(PAGE);
+
+        let next: NextData = document.next_data().unwrap();
+        assert_eq!(next.page, "/p/[id]");
+        assert_eq!(next.props.page_props["product"]["title"], "Green tea");
+
+        let scripts = document.json_scripts();
+        assert_eq!(scripts.len(), 2);
+        assert_eq!(scripts[0]["@type"], "Product");
+
+        assert!(document.script_json::<Value>("broken").is_err());
+        assert!(document.script_json::<Value>("missing").is_err());
+    }
+
+    #[test]
+    fn test_script_variables() {
+        let document = HtmlParser::new().parse_html(PAGE);
+
+        let state: Value = document.script_variable("__APP_STATE__").unwrap();
+        assert_eq!(
+            state,
+            json!({
+                "user": { "id": 42, "name": "Mia", "last-seen": null },
+                "cart": [ { "sku": "T-1", "price": 5.0 } ],
+                "flags": { "beta": true, "legacy": false }
+            })
+        );
+        assert_eq!(
+            document
+                .script_variable::<Value>("window.__APP_STATE__")
+                .unwrap()["user"]["id"],
+            42
+        );
+
+        #[derive(Deserialize)]
+        struct Config {
+            locale: String,
+            currency: String,
+        }
+        let config: Config = document.script_variable("config").unwrap();
+        assert_eq!(
+            (config.locale.as_str(), config.currency.as_str()),
+            ("fi-FI", "EUR")
+        );
+
+        assert!(document.script_variable::<Value>("APP_STATE").is_err());
+    }
+
+    #[test]
+    fn test_tolerant_literal_parser() {
+        let value = parse_js_literal(
+            r#"{
+                /* block comment */ $key: 'it\'s', 0: `template`, "quoted": "é\x41\u{1F600}😀",
+                hex: 0xFF, negative: -1.5, infinity: -Infinity, nan: NaN, holes: [1,,2],
+                nested: { empty: {}, list: [], gone: void 0, },
+            };"#,
+        )
+        .unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "$key": "it's", "0": "template", "quoted": "éA😀😀",
+                "hex": 255, "negative": -1.5, "infinity": null, "nan": null, "holes": [1, null, 2],
+                "nested": { "empty": {}, "list": [], "gone": null }
+            })
+        );
+
+        assert!(parse_js_literal("{ a: someVariable }").is_err());
+        assert!(parse_js_literal("`hello ${name}`").is_err());
+        assert!(parse_js_literal("{ a: 1 } extra").is_err());
+        assert!(parse_js_literal("[1, 2").is_err());
+    }
+
+    #[test]
+    fn test_nesting_limit() {
+        let nested = |depth: usize| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+        assert!(parse_js_literal(&nested(128)).is_ok());
+        let err = parse_js_literal(&nested(129)).unwrap_err();
+        assert!(err.to_string().contains("nested too deeply"));
+
+        // Deeply nested page scripts fail to parse instead of overflowing the stack
+        let deep = "[".repeat(100_000);
+        assert!(parse_js_literal(&deep).is_err());
+        let document = HtmlParser::new().parse_html(&format!(
+            r#"<script>window.__STATE__ = {0}</script><script type="application/ld+json">{{"@type": "Thing"}} {0}</script>"#,
+            deep
+        ));
+        assert!(document.script_variable::<Value>("__STATE__").is_err());
+        assert!(document.json_ld().nodes.is_empty());
+    }
+}
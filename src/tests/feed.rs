@@ -0,0 +1,295 @@
+#[cfg(test)]
+mod feed_tests {
+    use crate::client::WappuClient;
+    use crate::feed::{CacheValidators, Feed, FeedFormat, FeedReader, Person};
+    use crate::tests::server::{serve, MockRequest, MockResponse};
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    fn at(seconds: u64) -> Option<SystemTime> {
+        Some(UNIX_EPOCH + Duration::from_secs(seconds))
+    }
+
+    const RSS: &str = r#"
+<?xml version="1.0" encoding="utf-8"?>
+<rss version="2.0" xmlns:content="http://purl.org/rss/1.0/modules/content/" xmlns:atom="http://www.w3.org/2005/Atom">
+  <channel>
+    <title>Uutiset</title>
+    <link>/news</link>
+    <atom:link href="https://example.com/feed.xml" rel="self" type="application/rss+xml"/>
+    <description>Latest news</description>
+    <lastBuildDate>Mon, 19 Oct 2026 08:00:00 GMT</lastBuildDate>
+    <item>
+      <title><![CDATA[Kahvi & pulla]]></title>
+      <link> https://example.com/news/1 </link>
+      <guid isPermaLink="false">news-1</guid>
+      <description>&lt;p&gt;Short&lt;/p&gt;</description>
+      <content:encoded><![CDATA[<p>Full <b>story</b></p>]]></content:encoded>
+      <author>editor@example.com (Maija Meikäläinen)</author>
+      <category>Food</category>
+      <category>Finland</category>
+      <enclosure url="/audio/1.mp3" type="audio/mpeg" length="12345"/>
+      <pubDate>19 Oct 26 10:00 +0500</pubDate>
+    </item>
+    <item>
+      <title>No link</title>
+      <guid>https://example.com/news/2</guid>
+      <dc:creator>Matti</dc:creator>
+      <PubDate>Sunday, 18 October 2026 07:00:00 EST</PubDate>
+    </item>
+  </channel>
+</rss>"#;
+
+    #[test]
+    fn test_parse_rss() {
+        let feed = Feed::parse(RSS, "https://example.com/feed.xml").unwrap();
+        assert_eq!(feed.format, FeedFormat::Rss("2.0".to_string()));
+        assert_eq!(feed.title.as_deref(), Some("Uutiset"));
+        // The channel link, not the atom:link next to it, resolved against the feed
+        assert_eq!(feed.link.as_deref(), Some("https://example.com/news"));
+        assert_eq!(
+            feed.self_link.as_deref(),
+            Some("https://example.com/feed.xml")
+        );
+        assert_eq!(feed.description.as_deref(), Some("Latest news"));
+        assert_eq!(feed.updated, at(1792396800));
+        assert_eq!(feed.entries.len(), 2);
+
+        let first = &feed.entries[0];
+        assert_eq!(first.id.as_deref(), Some("news-1"));
+        assert_eq!(first.title.as_deref(), Some("Kahvi & pulla"));
+        assert_eq!(first.link.as_deref(), Some("https://example.com/news/1"));
+        assert_eq!(first.summary.as_deref(), Some("<p>Short</p>"));
+        assert_eq!(first.content.as_deref(), Some("<p>Full <b>story</b></p>"));
+        assert_eq!(
+            first.authors,
+            vec![Person {
+                name: Some("Maija Meikäläinen".to_string()),
+                email: Some("editor@example.com".to_string()),
+                uri: None,
+            }]
+        );
+        assert_eq!(first.categories, vec!["Food", "Finland"]);
+        assert_eq!(first.enclosures[0].url, "https://example.com/audio/1.mp3");
+        assert_eq!(first.enclosures[0].media_type.as_deref(), Some("audio/mpeg"));
+        assert_eq!(first.enclosures[0].length, Some(12345));
+        assert_eq!(first.published, at(1792386000));
+
+        // A permalink guid stands in for the link, the undeclared dc prefix still works
+        let second = &feed.entries[1];
+        assert_eq!(second.link.as_deref(), Some("https://example.com/news/2"));
+        assert_eq!(second.authors[0].name.as_deref(), Some("Matti"));
+        assert_eq!(second.published, at(1792324800));
+    }
+
+    // WordPress puts the atom:link before the channel link and gives each item a comments feed
+    #[test]
+    fn test_parse_wordpress_rss() {
+        let wordpress = r#"<?xml version="1.0" encoding="UTF-8"?><rss version="2.0"
+	xmlns:content="http://purl.org/rss/1.0/modules/content/"
+	xmlns:wfw="http://wellformedweb.org/CommentAPI/"
+	xmlns:dc="http://purl.org/dc/elements/1.1/"
+	xmlns:atom="http://www.w3.org/2005/Atom"
+	xmlns:sy="http://purl.org/rss/1.0/modules/syndication/"
+	xmlns:slash="http://purl.org/rss/1.0/modules/slash/"
+	>
+
+<channel>
+	<title>Kahvila</title>
+	<atom:link href="https://kahvila.example/feed/" rel="self" type="application/rss+xml" />
+	<link>https://kahvila.example</link>
+	<description>Coffee and more</description>
+	<lastBuildDate>Mon, 19 Oct 2026 08:00:00 +0000</lastBuildDate>
+	<language>fi</language>
+	<sy:updatePeriod>hourly</sy:updatePeriod>
+	<generator>https://wordpress.org/?v=6.6</generator>
+	<item>
+		<title>Opening hours</title>
+		<link>https://kahvila.example/2026/10/opening-hours/</link>
+		<comments>https://kahvila.example/2026/10/opening-hours/#respond</comments>
+		<dc:creator><![CDATA[maija]]></dc:creator>
+		<pubDate>Mon, 19 Oct 2026 07:30:00 +0000</pubDate>
+		<category><![CDATA[News]]></category>
+		<guid isPermaLink="false">https://kahvila.example/?p=42</guid>
+		<description><![CDATA[We open at eight]]></description>
+		<wfw:commentRss>https://kahvila.example/2026/10/opening-hours/feed/</wfw:commentRss>
+		<slash:comments>0</slash:comments>
+	</item>
+	<item>
+		<title>Only an atom link</title>
+		<atom:link href="https://kahvila.example/feed/" rel="self"/>
+		<atom:link href="/2026/10/atom-only/"/>
+		<guid isPermaLink="false">https://kahvila.example/?p=43</guid>
+	</item>
+</channel>
+</rss>
+"#;
+        let feed = Feed::parse(wordpress, "https://kahvila.example/feed/").unwrap();
+        assert_eq!(feed.link.as_deref(), Some("https://kahvila.example/"));
+        assert_eq!(
+            feed.self_link.as_deref(),
+            Some("https://kahvila.example/feed/")
+        );
+        assert_eq!(feed.title.as_deref(), Some("Kahvila"));
+        assert_eq!(feed.entries.len(), 2);
+
+        let first = &feed.entries[0];
+        assert_eq!(
+            first.link.as_deref(),
+            Some("https://kahvila.example/2026/10/opening-hours/")
+        );
+        assert_eq!(first.id.as_deref(), Some("https://kahvila.example/?p=42"));
+        assert_eq!(first.authors[0].name.as_deref(), Some("maija"));
+        assert_eq!(first.categories, ["News"]);
+
+        // A self link is never the item's link, an alternate one is
+        assert_eq!(
+            feed.entries[1].link.as_deref(),
+            Some("https://kahvila.example/2026/10/atom-only/")
+        );
+
+        // Without a channel link the alternate atom:link stands in, never the self link
+        let self_only = wordpress.replace("<link>https://kahvila.example</link>", "");
+        let feed = Feed::parse(&self_only, "https://kahvila.example/feed/").unwrap();
+        assert_eq!(feed.link, None);
+        let alternate = wordpress.replace(
+            "<link>https://kahvila.example</link>",
+            r#"<atom:link rel="alternate" href="https://kahvila.example/blog/"/>"#,
+        );
+        let feed = Feed::parse(&alternate, "https://kahvila.example/feed/").unwrap();
+        assert_eq!(feed.link.as_deref(), Some("https://kahvila.example/blog/"));
+    }
+
+    #[test]
+    fn test_parse_rss_10() {
+        let rdf = r#"<?xml version="1.0"?>
+<rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#" xmlns="http://purl.org/rss/1.0/" xmlns:dc="http://purl.org/dc/elements/1.1/">
+  <channel rdf:about="https://example.com/">
+    <title>Example</title>
+    <link>https://example.com/</link>
+  </channel>
+  <item rdf:about="https://example.com/a">
+    <title>A</title>
+    <link>https://example.com/a</link>
+    <dc:date>2003-12-13T18:30:02Z</dc:date>
+    <dc:subject>Testing</dc:subject>
+  </item>
+</rdf:RDF>"#;
+        let feed = Feed::parse(rdf, "https://example.com/index.rdf").unwrap();
+        assert_eq!(feed.format, FeedFormat::Rss("1.0".to_string()));
+        assert_eq!(feed.title.as_deref(), Some("Example"));
+        assert_eq!(feed.entries.len(), 1);
+        assert_eq!(feed.entries[0].link.as_deref(), Some("https://example.com/a"));
+        assert_eq!(feed.entries[0].published, at(1071340202));
+        assert_eq!(feed.entries[0].categories, vec!["Testing"]);
+    }
+
+    #[test]
+    fn test_parse_atom() {
+        let atom = "\u{feff}<?xml version=\"1.0\" encoding=\"utf-8\"?>
+<feed xmlns=\"http://www.w3.org/2005/Atom\">
+  <title>Example Feed</title>
+  <subtitle>All the news</subtitle>
+  <link href=\"https://example.com/atom.xml\" rel=\"self\"/>
+  <link href=\"https://example.com/\"/>
+  <updated>2003-12-13T18:30:02Z</updated>
+  <author><name>John Doe</name><email>john@example.com</email></author>
+  <entry>
+    <title type=\"html\">Atom-Powered &amp;lt;Robots&amp;gt;</title>
+    <link rel=\"alternate\" href=\"/2003/12/13/atom03\"/>
+    <link rel=\"enclosure\" href=\"/robots.mp4\" type=\"video/mp4\" length=\"1000\"/>
+    <id>urn:uuid:1225c695-cfb8-4ebb-aaaa-80da344efa6a</id>
+    <published>2003-12-13T20:30:02+02:00</published>
+    <updated>2003-12-13T18:30:02.25Z</updated>
+    <summary>Some text.</summary>
+    <content type=\"xhtml\"><div xmlns=\"http://www.w3.org/1999/xhtml\"><p>Hello <b>robots</b></p></div></content>
+    <category term=\"robots\"/>
+  </entry>
+</feed>";
+        let feed = Feed::parse(atom, "https://example.com/atom.xml").unwrap();
+        assert_eq!(feed.format, FeedFormat::Atom);
+        assert_eq!(feed.title.as_deref(), Some("Example Feed"));
+        assert_eq!(feed.description.as_deref(), Some("All the news"));
+        assert_eq!(feed.link.as_deref(), Some("https://example.com/"));
+        assert_eq!(
+            feed.self_link.as_deref(),
+            Some("https://example.com/atom.xml")
+        );
+        assert_eq!(feed.updated, at(1071340202));
+
+        let entry = &feed.entries[0];
+        assert_eq!(entry.title.as_deref(), Some("Atom-Powered &lt;Robots&gt;"));
+        assert_eq!(
+            entry.link.as_deref(),
+            Some("https://example.com/2003/12/13/atom03")
+        );
+        assert_eq!(entry.published, at(1071340202));
+        assert_eq!(entry.updated, at(1071340202));
+        assert_eq!(entry.content.as_deref(), Some("Hello robots"));
+        assert_eq!(entry.categories, vec!["robots"]);
+        assert_eq!(entry.enclosures[0].url, "https://example.com/robots.mp4");
+        // The entry has no author of its own and gets the feed's
+        assert_eq!(entry.authors[0].email.as_deref(), Some("john@example.com"));
+
+        assert!(Feed::parse("<html><body></body></html>", "https://example.com/").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_conditional_get() {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let recorded = requests.clone();
+        let url = serve(move |request: MockRequest| {
+            recorded.lock().unwrap().push(request.clone());
+            if request.headers.get("if-none-match").map(String::as_str) == Some("\"v1\"") {
+                return MockResponse::status(304, "");
+            }
+            MockResponse::ok(RSS)
+                .header("Content-Type", "application/rss+xml")
+                .header("ETag", "\"v1\"")
+                .header("Last-Modified", "Mon, 19 Oct 2026 08:00:00 GMT")
+        })
+        .await;
+
+        let reader = FeedReader::new(WappuClient::new());
+        let feed = reader.fetch(&format!("{}/feed.xml", url)).await.unwrap();
+        assert_eq!(feed.entries.len(), 2);
+        assert_eq!(
+            feed.validators,
+            CacheValidators {
+                etag: Some("\"v1\"".to_string()),
+                last_modified: Some("Mon, 19 Oct 2026 08:00:00 GMT".to_string()),
+            }
+        );
+
+        let unchanged = reader
+            .fetch_if_modified(&format!("{}/feed.xml", url), &feed.validators)
+            .await
+            .unwrap();
+        assert!(unchanged.is_none());
+
+        let requests = requests.lock().unwrap();
+        assert!(!requests[0].headers.contains_key("if-none-match"));
+        assert_eq!(
+            requests[1].headers.get("if-modified-since").map(String::as_str),
+            Some("Mon, 19 Oct 2026 08:00:00 GMT")
+        );
+    }
+
+    #[test]
+    fn test_out_of_range_dates() {
+        let rss = r#"<rss version="2.0"><channel><title>Dates</title>
+<item><pubDate>Mon, 19 Oct 9999999999999999 08:00:00 GMT</pubDate></item>
+<item><pubDate>-9999999999999999-10-19T08:00:00Z</pubDate></item>
+<item><pubDate>Mon, 30 Feb 2026 08:00:00 GMT</pubDate></item>
+<item><pubDate>Mon, 19 Oct 2026 10:00:00 +0200</pubDate></item>
+<item><pubDate>Mon, 19 Oct 2026 07:59:60 GMT</pubDate></item>
+</channel></rss>"#;
+        let feed = Feed::parse(rss, "https://example.com/feed.xml").unwrap();
+        let dates: Vec<Option<SystemTime>> =
+            feed.entries.iter().map(|entry| entry.published).collect();
+        assert_eq!(
+            dates,
+            vec![None, None, None, at(1792396800), at(1792396799)]
+        );
+    }
+}
@@ -0,0 +1,274 @@
+#[cfg(test)]
+mod form_tests {
+    use crate::client::WappuClient;
+    use crate::form::{ControlKind, Form, FormEnctype, FormMethod};
+    use crate::html::HtmlParser;
+    use crate::selector::Selector;
+    use crate::tests::server::{serve, MockRequest, MockResponse};
+    use std::sync::{Arc, Mutex};
+
+    const PAGE: &str = r#"
+        <html><head><base href="https://example.com/app/"></head><body>
+            <form id="signup" action="register?step=1" method="POST">
+                <input name="email" type="email" value="a@example.com">
+                <input type="hidden" name="_charset_">
+                <input type="hidden" name="token" value="t0k3n">
+                <input name="disabled" value="skipped" disabled>
+                <input value="no name">
+                <input type="checkbox" name="news" checked>
+                <input type="checkbox" name="tags" value="rust">
+                <input type="checkbox" name="tags" value="web" checked>
+                <input type="radio" name="plan" value="free" checked>
+                <input type="radio" name="plan" value="pro">
+                <select name="country">
+                    <option value="fi">Finland</option>
+                    <option disabled>Unavailable</option>
+                    <option selected>  New
+                        Zealand </option>
+                </select>
+                <select name="langs" multiple>
+                    <option value="en" selected>English</option>
+                    <optgroup label="Other" disabled><option value="fi" selected>Finnish</option></optgroup>
+                </select>
+                <textarea name="bio">line one
+line two</textarea>
+                <fieldset disabled>
+                    <legend><input name="in-legend" value="kept"></legend>
+                    <input name="in-fieldset" value="dropped">
+                </fieldset>
+                <datalist><option value="suggestion"></datalist>
+                <input type="reset" name="reset">
+                <button type="button" name="noop">Noop</button>
+                <button name="action" value="save">Save</button>
+                <input type="submit" name="action" value="publish" formaction="/publish" formenctype="multipart/form-data">
+            </form>
+            <input name="outside" value="associated" form="signup">
+            <input name="stray" value="ignored">
+            <form id="search"><input name="q"></form>
+        </body></html>
+    "#;
+
+    fn signup() -> Form {
+        let document = HtmlParser::new().parse_html(PAGE);
+        Form::select(
+            &document,
+            Selector::new().from_id("signup"),
+            "https://example.com/app/page",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_form_discovery() {
+        let document = HtmlParser::new().parse_html(PAGE);
+        let forms = Form::all(&document, "https://example.com/app/page");
+        assert_eq!(forms.len(), 2);
+        assert_eq!(forms[1].id.as_deref(), Some("search"));
+        assert_eq!(forms[1].action, "https://example.com/app/page");
+        assert_eq!(forms[1].method, FormMethod::Get);
+
+        let form = &forms[0];
+        assert_eq!(form.action, "https://example.com/app/register?step=1");
+        assert_eq!(form.method, FormMethod::Post);
+        assert_eq!(form.enctype, FormEnctype::UrlEncoded);
+
+        let select = form.control("country").unwrap();
+        match &select.kind {
+            ControlKind::Select { multiple, options } => {
+                assert!(!multiple);
+                assert_eq!(options[2].value, "New Zealand");
+                assert!(options[2].selected);
+            }
+            kind => panic!("unexpected control {:?}", kind),
+        }
+        assert!(form.control("stray").is_none());
+        assert!(form.control("reset").is_none());
+        assert!(form.control("noop").is_none());
+    }
+
+    #[test]
+    fn test_default_entries() {
+        let entries = signup().entries();
+        let expected = vec![
+            ("email", "a@example.com"),
+            ("_charset_", "UTF-8"),
+            ("token", "t0k3n"),
+            ("news", "on"),
+            ("tags", "web"),
+            ("plan", "free"),
+            ("country", "New Zealand"),
+            ("langs", "en"),
+            ("bio", "line one\r\nline two"),
+            ("in-legend", "kept"),
+            ("action", "save"),
+            ("outside", "associated"),
+        ];
+        let entries: Vec<(&str, &str)> = entries
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+            .collect();
+        assert_eq!(entries, expected);
+    }
+
+    #[test]
+    fn test_setting_values() {
+        let mut form = signup();
+        form.set("email", "b@example.com")
+            .unwrap()
+            .set("plan", "pro")
+            .unwrap()
+            .set("tags", "rust")
+            .unwrap()
+            .set("country", "fi")
+            .unwrap()
+            .set("langs", "fi")
+            .unwrap()
+            .set_checked("news", "on", false)
+            .unwrap()
+            .add("extra", "1");
+
+        let entries = form.entries();
+        let value_of = |name: &str| -> Vec<&str> {
+            entries
+                .iter()
+                .filter(|(entry, _)| entry == name)
+                .map(|(_, value)| value.as_str())
+                .collect()
+        };
+        assert_eq!(value_of("email"), vec!["b@example.com"]);
+        assert_eq!(value_of("plan"), vec!["pro"]);
+        assert_eq!(value_of("tags"), vec!["rust", "web"]);
+        assert_eq!(value_of("country"), vec!["fi"]);
+        // The Finnish option sits in a disabled optgroup so it is never sent
+        assert_eq!(value_of("langs"), vec!["en"]);
+        assert!(value_of("news").is_empty());
+        assert_eq!(value_of("extra"), vec!["1"]);
+
+        assert!(form.set("missing", "x").is_err());
+        assert!(form.set("country", "se").is_err());
+        assert!(form.set("action", "x").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_submit_urlencoded_and_multipart() {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let recorded = requests.clone();
+        let url = serve(move |request: MockRequest| {
+            recorded.lock().unwrap().push(request);
+            MockResponse::html("<p>ok</p>")
+        })
+        .await;
+
+        let page = r#"<form action="/register" method="post">
+                <input name="user" value="wappu">
+                <input type="file" name="avatar">
+                <input type="image" name="go">
+                <button name="action" value="publish" formmethod="get" formaction="/search?old=1">Find</button>
+            </form>"#;
+        let document = HtmlParser::new().parse_html(page);
+        let mut form = Form::all(&document, &format!("{}/new", url)).remove(0);
+        let client = WappuClient::new();
+
+        let response = form.submit(&client).await.unwrap();
+        assert_eq!(response.url(), format!("{}/register", url));
+        {
+            let requests = requests.lock().unwrap();
+            let request = requests.last().unwrap();
+            assert_eq!(request.method, "POST");
+            assert_eq!(
+                request.headers.get("content-type").map(String::as_str),
+                Some("application/x-www-form-urlencoded")
+            );
+            assert_eq!(request.body, "user=wappu&avatar=&go.x=0&go.y=0");
+        }
+
+        form.submit_with(&client, "action").await.unwrap();
+        assert_eq!(
+            requests.lock().unwrap().last().unwrap().path,
+            "/search?user=wappu&avatar=&action=publish"
+        );
+
+        form.enctype = FormEnctype::Multipart;
+        form.set_file("avatar", "me.txt", "text/plain", b"hello\nworld")
+            .unwrap();
+        form.submit(&client).await.unwrap();
+        let requests = requests.lock().unwrap();
+        let request = requests.last().unwrap();
+        let content_type = request.headers.get("content-type").unwrap();
+        let boundary = content_type
+            .strip_prefix("multipart/form-data; boundary=")
+            .unwrap();
+        assert_eq!(
+            request.body,
+            format!(
+                "--{b}\r\nContent-Disposition: form-data; name=\"user\"\r\n\r\nwappu\r\n\
+                 --{b}\r\nContent-Disposition: form-data; name=\"avatar\"; filename=\"me.txt\"\r\n\
+                 Content-Type: text/plain\r\n\r\nhello\nworld\r\n\
+                 --{b}\r\nContent-Disposition: form-data; name=\"go.x\"\r\n\r\n0\r\n\
+                 --{b}\r\nContent-Disposition: form-data; name=\"go.y\"\r\n\r\n0\r\n\
+                 --{b}--\r\n",
+                b = boundary
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn test_binary_upload() {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let recorded = requests.clone();
+        let url = serve(move |request: MockRequest| {
+            recorded.lock().unwrap().push(request);
+            MockResponse::html("<p>ok</p>")
+        })
+        .await;
+
+        let page = r#"<form action="/upload" method="post" enctype="multipart/form-data">
+                <input type="file" name="image">
+            </form>"#;
+        let document = HtmlParser::new().parse_html(page);
+        let mut form = Form::all(&document, &url).remove(0);
+        // Not valid UTF-8, so any lossy conversion along the way would change it
+        let png = [
+            0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0xff, 0xfe,
+        ];
+        form.set_file("image", "pixel.png", "image/png", &png)
+            .unwrap();
+        form.submit(&WappuClient::new()).await.unwrap();
+
+        let requests = requests.lock().unwrap();
+        let request = requests.last().unwrap();
+        let boundary = request.headers["content-type"]
+            .strip_prefix("multipart/form-data; boundary=")
+            .unwrap();
+        let mut expected = format!(
+            "--{b}\r\nContent-Disposition: form-data; name=\"image\"; filename=\"pixel.png\"\r\n\
+             Content-Type: image/png\r\n\r\n",
+            b = boundary
+        )
+        .into_bytes();
+        expected.extend_from_slice(&png);
+        expected.extend_from_slice(format!("\r\n--{}--\r\n", boundary).as_bytes());
+        assert_eq!(request.raw_body, expected);
+    }
+
+    #[test]
+    fn test_unnamed_submit_buttons() {
+        let page = r#"<form action="/form" method="post">
+                <input name="q" value="x">
+                <button formaction="/search">Go</button>
+                <input type="submit" name="delete" value="Delete" formaction="/delete">
+            </form>"#;
+        let document = HtmlParser::new().parse_html(page);
+        let form = Form::all(&document, "https://example.com/").remove(0);
+
+        // The unnamed button is still the default one, it just adds no entry
+        let request = form.request();
+        assert_eq!(request.url, "https://example.com/search");
+        assert_eq!(request.body.as_deref(), Some(&b"q=x"[..]));
+        assert_eq!(form.entries(), vec![("q".to_string(), "x".to_string())]);
+
+        let request = form.request_with("delete").unwrap();
+        assert_eq!(request.url, "https://example.com/delete");
+        assert_eq!(request.body.as_deref(), Some(&b"q=x&delete=Delete"[..]));
+    }
+}
@@ -0,0 +1,222 @@
+#[cfg(test)]
+mod frames_tests {
+    use crate::client::WappuClient;
+    use crate::frames::FrameLoader;
+    use crate::selector::Selector;
+    use crate::tests::server::{serve, MockRequest, MockResponse};
+    use std::sync::{Arc, Mutex};
+
+    // A page with a same-origin iframe that nests another frame, a srcdoc iframe and an
+    // iframe from another origin
+    async fn sites() -> (String, Arc<Mutex<Vec<MockRequest>>>) {
+        let other = serve(|request: MockRequest| match request.path.as_str() {
+            "/widget" => MockResponse::html(r#"<p class="content">Widget</p>"#),
+            _ => MockResponse::status(404, "not found"),
+        })
+        .await;
+
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let recorded = requests.clone();
+        let url = serve(move |request: MockRequest| {
+            recorded.lock().unwrap().push(request.clone());
+            match request.path.as_str() {
+                "/" => MockResponse::html(&format!(
+                    r#"<html><body>
+                        <p class="content">Top</p>
+                        <iframe name="outer" src="/frames/outer"></iframe>
+                        <iframe name="inline" srcdoc="<p class='content'>Srcdoc</p><iframe src='missing'></iframe>"></iframe>
+                        <iframe name="widget" src="{}/widget"></iframe>
+                        <iframe src="javascript:void(0)"></iframe>
+                    </body></html>"#,
+                    other
+                )),
+                "/frames/outer" => MockResponse::html(
+                    r#"<frameset><frame name="inner" src="inner"></frameset>"#,
+                ),
+                "/frames/inner" => MockResponse::html(r#"<p class="content">Inner</p>"#),
+                _ => MockResponse::status(404, "not found"),
+            }
+        })
+        .await;
+        (url, requests)
+    }
+
+    #[tokio::test]
+    async fn test_frame_tree() {
+        let (url, requests) = sites().await;
+        let top = FrameLoader::new(WappuClient::new())
+            .load(&format!("{}/", url))
+            .await
+            .unwrap();
+
+        let names: Vec<Option<&str>> = top
+            .all_frames()
+            .iter()
+            .map(|frame| frame.name.as_deref())
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                None,
+                Some("outer"),
+                Some("inner"),
+                Some("inline"),
+                None,
+                Some("widget")
+            ]
+        );
+
+        let content = top.select_to_depth(Selector::new().from_class_name("content"), 0);
+        assert_eq!(content.text(), "Top");
+        let content = top.select(Selector::new().from_class_name("content"));
+        assert_eq!(content.text(), "Top Inner Srcdoc Widget");
+
+        let inline = top.frame("inline").unwrap();
+        assert_eq!(inline.url, "about:srcdoc");
+        // The srcdoc frame's own iframe resolves against the page and fails to load
+        assert_eq!(inline.frames[0].url, format!("{}/missing", url));
+        assert!(inline.frames[0].error.is_some());
+
+        let requests = requests.lock().unwrap();
+        let inner = requests
+            .iter()
+            .find(|request| request.path == "/frames/inner")
+            .unwrap();
+        assert_eq!(
+            inner.headers.get("referer"),
+            Some(&format!("{}/frames/outer", url))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_depth_and_origin_limits() {
+        let (url, _) = sites().await;
+        let top = FrameLoader::default()
+            .max_depth(1)
+            .same_origin_only(true)
+            .load(&format!("{}/", url))
+            .await
+            .unwrap();
+
+        let names: Vec<Option<&str>> = top
+            .frames
+            .iter()
+            .map(|frame| frame.name.as_deref())
+            .collect();
+        assert_eq!(names, vec![Some("outer"), Some("inline")]);
+        assert!(top.frame("inner").is_none());
+        assert_eq!(
+            top.select(Selector::new().from_class_name("content"))
+                .text(),
+            "Top Srcdoc"
+        );
+
+        let top = FrameLoader::default()
+            .max_frames(1)
+            .load(&format!("{}/", url))
+            .await
+            .unwrap();
+        assert_eq!(top.all_frames().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_failed_frames() {
+        // A port nothing listens on any more
+        let closed = {
+            let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            format!("http://{}", listener.local_addr().unwrap())
+        };
+        let url = serve(move |request: MockRequest| match request.path.as_str() {
+            "/" => MockResponse::html(&format!(
+                r#"<p class="content">Top</p>
+                   <iframe name="broken" src="/broken"></iframe>
+                   <iframe name="offline" src="{}/offline"></iframe>
+                   <iframe name="ok" src="/ok"></iframe>"#,
+                closed
+            )),
+            "/broken" => MockResponse::status(
+                500,
+                r#"<p class="content">Error page</p><iframe src="/ok"></iframe>"#,
+            ),
+            "/ok" => MockResponse::html(r#"<p class="content">Fine</p>"#),
+            _ => MockResponse::status(404, "not found"),
+        })
+        .await;
+
+        let top = FrameLoader::default()
+            .load(&format!("{}/", url))
+            .await
+            .unwrap();
+        let broken = top.frame("broken").unwrap();
+        assert_eq!(
+            broken.error.as_deref(),
+            Some("Unexpected status code: 500 Internal Server Error")
+        );
+        // The error page is neither kept nor searched for frames
+        assert!(broken.frames.is_empty());
+        assert!(top.frame("offline").unwrap().error.is_some());
+        assert!(top.frame("ok").unwrap().error.is_none());
+        assert_eq!(
+            top.select(Selector::new().from_class_name("content"))
+                .text(),
+            "Top Fine"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cross_origin_frames() {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let recorded = requests.clone();
+        let other = serve(move |request: MockRequest| {
+            recorded.lock().unwrap().push(request.clone());
+            match request.path.as_str() {
+                "/ad" => MockResponse::html(
+                    r#"<p class="content">Ad</p><iframe name="tracker" src="/tracker"></iframe>"#,
+                ),
+                "/tracker" => MockResponse::html(r#"<p class="content">Tracker</p>"#),
+                _ => MockResponse::status(404, "not found"),
+            }
+        })
+        .await;
+        let page = other.clone();
+        let url = serve(move |request: MockRequest| match request.path.as_str() {
+            "/" => MockResponse::html(&format!(
+                r#"<iframe name="ad" src="{}/ad"></iframe>
+                   <iframe name="local" srcdoc="<iframe name='nested' src='{}/ad'></iframe>"></iframe>"#,
+                page, page
+            )),
+            _ => MockResponse::status(404, "not found"),
+        })
+        .await;
+
+        // Frames from other origins load by default, and their own frames resolve against them
+        let top = FrameLoader::default()
+            .load(&format!("{}/", url))
+            .await
+            .unwrap();
+        assert_eq!(
+            top.frame("tracker").unwrap().url,
+            format!("{}/tracker", other)
+        );
+        assert_eq!(
+            requests.lock().unwrap()[0].headers.get("referer"),
+            Some(&format!("{}/", url))
+        );
+
+        // Only the top-level page's origin counts, so srcdoc frames stay but what they load
+        // from elsewhere doesn't
+        requests.lock().unwrap().clear();
+        let top = FrameLoader::default()
+            .same_origin_only(true)
+            .load(&format!("{}/", url))
+            .await
+            .unwrap();
+        let names: Vec<Option<&str>> = top
+            .all_frames()
+            .iter()
+            .map(|frame| frame.name.as_deref())
+            .collect();
+        assert_eq!(names, vec![None, Some("local")]);
+        assert!(requests.lock().unwrap().is_empty());
+    }
+}
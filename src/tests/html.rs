@@ -38,3 +38,115 @@ mod tests {
 
 }
 }
+
+#[cfg(test)]
+mod fragment_tests {
+    use crate::html::{HtmlParser, NodeKind};
+    use crate::select_by_tag_name;
+    use crate::selector::Selector;
+
+    #[test]
+    fn test_table_rows_keep_their_context() {
+        let parser = HtmlParser::new();
+        let html = r#"<tr class="row"><td>1</td><td>Tea</td></tr><tr class="row"><td>2</td><td>Coffee</td></tr>"#;
+
+        let fragment = parser.parse_fragment(html, "tbody");
+        assert_eq!(fragment.tag_name, None);
+        assert_eq!(fragment.children.len(), 2);
+        assert_eq!(fragment.children[0].tag_name.as_deref(), Some("tr"));
+        assert_eq!(select_by_tag_name!(&fragment, "td").text(), "1 Tea 2 Coffee");
+        assert!(select_by_tag_name!(&fragment, "tbody").elements.is_empty());
+        assert!(select_by_tag_name!(&fragment, "html").elements.is_empty());
+
+        // Parsed as a document the rows have nowhere to go and only their text is left
+        let document = parser.parse_html(html);
+        assert!(select_by_tag_name!(&document, "tr").elements.is_empty());
+    }
+
+    #[test]
+    fn test_context_changes_parsing() {
+        let parser = HtmlParser::new();
+
+        let options = parser.parse_fragment("<option>One<option>Two", "SELECT");
+        assert_eq!(select_by_tag_name!(&options, "option").text(), "One Two");
+
+        // Inside a textarea markup is just text
+        let text = parser.parse_fragment("<b>not bold</b>", "textarea");
+        assert_eq!(text.children.len(), 1);
+        assert_eq!(text.children[0].kind, NodeKind::Text);
+        assert_eq!(text.text, "<b>not bold</b>");
+
+        let mixed = parser.parse_fragment("Hello <b>there</b>", "div");
+        assert_eq!(mixed.text, "Hello ");
+        assert_eq!(mixed.children[0].text, "Hello ");
+        assert_eq!(mixed.children[1].text, "there");
+    }
+}
+
+#[cfg(test)]
+mod node_kind_tests {
+    use crate::html::{HtmlParser, NodeKind};
+    use crate::select_by_id;
+    use crate::selector::Selector;
+
+    const PAGE: &str = r#"<!DOCTYPE html PUBLIC "-//W3C//DTD XHTML 1.0 Strict//EN" "http://www.w3.org/TR/xhtml1/DTD/xhtml1-strict.dtd">
+<html><body>
+<div id="main">Before<!-- hidden <span class="price">42</span> -->After<p>Text</p></div>
+<!--[if lt IE 9]><script src="html5shiv.js"></script><![endif]-->
+</body></html>"#;
+
+    #[test]
+    fn test_doctype_and_comments_are_kept() {
+        let document = HtmlParser::new().parse_html(PAGE);
+        assert_eq!(document.kind, NodeKind::Document);
+
+        let doctype = document.doctype().unwrap();
+        assert_eq!(doctype.name, "html");
+        assert_eq!(doctype.public_id, "-//W3C//DTD XHTML 1.0 Strict//EN");
+        assert_eq!(
+            doctype.system_id,
+            "http://www.w3.org/TR/xhtml1/DTD/xhtml1-strict.dtd"
+        );
+
+        let comments = document.comments();
+        assert_eq!(comments.len(), 2);
+        assert_eq!(comments[0].text, r#" hidden <span class="price">42</span> "#);
+
+        // Comments sit between the text and elements but don't end up in the element's text
+        let main = select_by_id!(&document, "main").elements[0];
+        assert_eq!(main.text, "BeforeAfter");
+        let kinds: Vec<&NodeKind> = main.children.iter().map(|child| &child.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                &NodeKind::Text,
+                &NodeKind::Comment,
+                &NodeKind::Text,
+                &NodeKind::Element
+            ]
+        );
+        assert!(main.children[3].is_element());
+        assert!(document.processing_instructions().is_empty());
+    }
+
+    #[test]
+    fn test_parse_comment_contents() {
+        let document = HtmlParser::new().parse_html(PAGE);
+        let comments = document.comments();
+
+        let hidden = comments[0].parse_comment().unwrap();
+        assert_eq!(
+            Selector::new()
+                .from_class_name("price")
+                .select(&hidden)
+                .text(),
+            "42"
+        );
+
+        let conditional = comments[1].parse_comment().unwrap();
+        let script = Selector::new().from_tag_name("script").select(&conditional);
+        assert_eq!(script.src().as_deref(), Some("html5shiv.js"));
+
+        assert!(document.parse_comment().is_none());
+    }
+}
@@ -0,0 +1,107 @@
+#[cfg(test)]
+mod jsonld_tests {
+    use crate::html::HtmlParser;
+    use crate::jsonld::{Article, ListItem, Product};
+    use serde::Deserialize;
+
+    const PAGE: &str = r#"<html><head>
+<script type="application/ld+json">
+<!--
+{
+  "@context": "https://schema.org",
+  "@type": "Product",
+  "name": "Sauna stove",
+  "sku": 1234,
+  "brand": {"@type": "Brand", "name": "Harvia"},
+  "image": ["https://example.com/1.jpg", {"@type": "ImageObject", "url": "https://example.com/2.jpg"}],
+  "offers": [
+    {"@type": "Offer", "price": 599.0, "priceCurrency": "EUR", "availability": "https://schema.org/InStock",
+     "seller": {"@type": "Organization", "name": "Example Shop"}},
+    "not an offer",
+    {"@type": "AggregateOffer", "lowPrice": "499", "highPrice": "899", "priceCurrency": "EUR"},
+  ],
+  // Reviews are pending moderation
+  "aggregateRating": {"@type": "AggregateRating", "ratingValue": "4.5", "reviewCount": 12}
+}
+-->
+</script>
+<script type="application/ld+json">
+{"@context": "https://schema.org", "@graph": [
+  {"@type": ["NewsArticle", "Thing"], "headline": "Stoves compared", "author": [{"@type": "Person", "name": "Maija"}, "Matti"],
+   "datePublished": "2026-10-19T08:00:00+03:00", "publisher": {"@type": "Organization", "name": "Example"}},
+  {"@type": "BreadcrumbList", "itemListElement": [
+    {"@type": "ListItem", "position": "2", "item": {"@id": "https://example.com/stoves", "name": "Stoves"}},
+    {"@type": "ListItem", "position": 1, "name": "Home", "item": "https://example.com/"}
+  ]}
+]}
+{"@type": "http://schema.org/Event", "name": "Sauna day"}
+</script>
+<script type="application/ld+json">{ broken</script>
+<script type="application/json">{"@type": "Product", "name": "Not JSON-LD"}</script>
+</head><body></body></html>"#;
+
+    #[derive(Deserialize)]
+    struct Event {
+        name: String,
+    }
+
+    #[test]
+    fn test_json_ld_lookups() {
+        let document = HtmlParser::new().parse_html(PAGE);
+        let json_ld = document.json_ld();
+        assert_eq!(json_ld.nodes.len(), 4);
+        assert_eq!(json_ld.of_type("Thing").len(), 1);
+        assert_eq!(json_ld.first::<Event>("Event").unwrap().name, "Sauna day");
+        assert!(json_ld.first::<Event>("Recipe").is_err());
+
+        let products: Vec<Product> = json_ld.products().unwrap();
+        let product = &products[0];
+        assert_eq!(product.name.as_deref(), Some("Sauna stove"));
+        assert_eq!(product.sku.as_deref(), Some("1234"));
+        assert_eq!(product.brand.as_deref(), Some("Harvia"));
+        assert_eq!(
+            product.image,
+            vec!["https://example.com/1.jpg", "https://example.com/2.jpg"]
+        );
+        assert_eq!(product.offers.len(), 2);
+        assert_eq!(product.offers[0].price.as_deref(), Some("599.0"));
+        assert_eq!(product.offers[0].availability.as_deref(), Some("InStock"));
+        assert_eq!(product.offers[0].seller.as_deref(), Some("Example Shop"));
+        assert_eq!(product.offers[1].low_price.as_deref(), Some("499"));
+        let rating = product.aggregate_rating.as_ref().unwrap();
+        assert_eq!(rating.review_count.as_deref(), Some("12"));
+    }
+
+    #[test]
+    fn test_articles_and_breadcrumbs() {
+        let document = HtmlParser::new().parse_html(PAGE);
+        let json_ld = document.json_ld();
+
+        let articles: Vec<Article> = json_ld.articles().unwrap();
+        assert_eq!(articles.len(), 1);
+        assert_eq!(articles[0].headline.as_deref(), Some("Stoves compared"));
+        assert_eq!(articles[0].author, vec!["Maija", "Matti"]);
+        assert_eq!(articles[0].publisher.as_deref(), Some("Example"));
+        assert_eq!(
+            articles[0].date_published.as_deref(),
+            Some("2026-10-19T08:00:00+03:00")
+        );
+
+        let breadcrumbs = json_ld.breadcrumbs().unwrap();
+        assert_eq!(
+            breadcrumbs[0].item_list_element,
+            vec![
+                ListItem {
+                    position: Some(1),
+                    name: Some("Home".to_string()),
+                    item: Some("https://example.com/".to_string()),
+                },
+                ListItem {
+                    position: Some(2),
+                    name: Some("Stoves".to_string()),
+                    item: Some("https://example.com/stoves".to_string()),
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,174 @@
+#[cfg(test)]
+mod markdown_tests {
+    use crate::html::HtmlParser;
+    use crate::markdown::{LinkStyle, MarkdownConverter};
+    use crate::select_by_class;
+    use crate::selector::Selector;
+
+    const ARTICLE: &str = r#"<html><head><title>Ignored</title><style>p { color: red }</style></head><body>
+<article>
+  <h1>Saunan   <em>lämpö</em></h1>
+  <p>Read the <a href="/guide" title="The guide">guide</a> or <a href="https://example.com/faq">FAQ</a>.<br>
+     New line with <strong>bold</strong>, <i> italic</i>, <del>old</del> and <code>a_b</code>.</p>
+  <img src="img/sauna.jpg" alt="A sauna">
+  <ul>
+    <li>First</li>
+    <li>Second
+      <ol start="3"><li>Nested <b>three</b></li><li>Nested four</li></ol>
+    </li>
+    <li><input type="checkbox" checked> Done</li>
+  </ul>
+  <blockquote><p>Quoted</p><p>Twice</p></blockquote>
+  <pre><code class="language-rust">fn main() {
+    println!("*not emphasis*");
+}
+</code></pre>
+  <table>
+    <thead><tr><th>Name</th><th align="right">Price</th></tr></thead>
+    <tbody><tr><td>Coffee | tea</td><td>1.90</td></tr><tr><td>Bun</td></tr></tbody>
+  </table>
+  <hr>
+  <script>ignored()</script>
+  <p>Snake_case and *stars*</p>
+</article>
+</body></html>"#;
+
+    #[test]
+    fn test_convert_document() {
+        let document = HtmlParser::new().parse_html(ARTICLE);
+        let markdown = MarkdownConverter::new()
+            .base_url("https://example.com/docs/")
+            .convert(&document);
+
+        assert_eq!(
+            markdown,
+            r#"# Saunan *lämpö*
+
+Read the [guide](https://example.com/guide "The guide") or [FAQ](https://example.com/faq).\
+New line with **bold**, *italic*, ~~old~~ and `a_b`.
+
+![A sauna](https://example.com/docs/img/sauna.jpg)
+
+- First
+- Second
+  3. Nested **three**
+  4. Nested four
+- [x] Done
+
+> Quoted
+>
+> Twice
+
+```rust
+fn main() {
+    println!("*not emphasis*");
+}
+```
+
+| Name | Price |
+| --- | ---: |
+| Coffee \| tea | 1.90 |
+| Bun |  |
+
+---
+
+Snake\_case and \*stars\*"#
+        );
+    }
+
+    #[test]
+    fn test_referenced_links_and_stripped_images() {
+        let html = r#"<div class="post"><p><a href="https://example.com/a">One</a>, <a href="https://example.com/b">two</a> and <a href="https://example.com/a">one again</a><img src="x.png" alt="x"></p><a href="javascript:void(0)">Menu</a></div>
+<div class="post"><p>Second post</p></div>"#;
+        let document = HtmlParser::new().parse_html(html);
+        let converter = MarkdownConverter::new()
+            .link_style(LinkStyle::Referenced)
+            .strip_images(true);
+
+        assert_eq!(
+            converter.convert_selection(&select_by_class!(&document, "post")),
+            "[One][1], [two][2] and [one again][1]\n\nMenu\n\nSecond post\n\n[1]: https://example.com/a\n[2]: https://example.com/b"
+        );
+    }
+
+    #[test]
+    fn test_script_links_are_dropped() {
+        // Browsers ignore tabs, newlines and leading control characters in URLs, and decode
+        // entities in attributes, so all of these would run script
+        let html = r#"<p><a href=" JavaScript:alert(1)">a</a> <a href="java&#x09;script:alert(1)">b</a> <a href="java&#10;script:alert(1)">c</a> <a href="&#1;javascript:alert(1)">d</a> <a href="JAVASCRIPT&colon;alert(1)">e</a> <a href="vbscript:msgbox(1)">f</a> <a href="data:text/html,<script>alert(1)</script>">g</a></p>
+<p><img src="javascript:alert(1)" alt="script"><img src="data:image/png;base64,iVBORw0KGgo=" alt="inline"></p>
+<p><a href="/javascript:guide">Relative</a> <a href="https://example.com/?next=javascript:x">Query</a></p>"#;
+        let document = HtmlParser::new().parse_html(html);
+        let markdown = MarkdownConverter::new()
+            .base_url("https://example.com/docs/")
+            .convert(&document);
+
+        assert_eq!(
+            markdown,
+            "a b c d e f g\n\n\
+             ![inline](data:image/png;base64,iVBORw0KGgo=)\n\n\
+             [Relative](https://example.com/javascript:guide) [Query](https://example.com/?next=javascript:x)"
+        );
+    }
+
+    #[test]
+    fn test_nested_tables() {
+        let html = r#"<table>
+  <tr><th>Product</th><th>Sizes</th></tr>
+  <tr>
+    <td>Coffee</td>
+    <td><table><tr><td>Small</td><td>1.90</td></tr><tr><td>Large</td><td>2|50</td></tr></table></td>
+  </tr>
+  <tr><td><ul><li>Hot</li><li>Iced</li></ul></td><td><p>Ask</p><p>staff</p></td></tr>
+</table>
+<table><tr><td><table><tr><th>Only</th></tr><tr><td>inner</td></tr></table></td></tr></table>"#;
+        let document = HtmlParser::new().parse_html(html);
+
+        // GFM has no nested tables or blocks in cells, their text is kept on the cell's line
+        assert_eq!(
+            MarkdownConverter::new().convert(&document),
+            "| Product | Sizes |\n\
+             | --- | --- |\n\
+             | Coffee | Small 1.90 Large 2\\|50 |\n\
+             | Hot Iced | Ask staff |\n\
+             \n\
+             | Only inner |\n\
+             | --- |"
+        );
+    }
+
+    #[test]
+    fn test_heading_line_breaks() {
+        let document = HtmlParser::new()
+            .parse_html(r"<h1>Title<br>Sub</h1><h2>One<br><br>Two</h2><h3>C:\</h3>");
+        assert_eq!(
+            MarkdownConverter::new().convert(&document),
+            "# Title Sub\n\n## One Two\n\n### C:\\\\"
+        );
+    }
+
+    #[test]
+    fn test_block_markers_are_escaped() {
+        let html = r#"<p># not heading</p><p>1. not list</p><p>2) nor this</p><p>- x</p>
+<p>+ y</p><p>* z</p><p>&gt; no quote</p><p>Text<br>===</p><p>1234567890. too long to be a list</p>
+<p>42 is fine, <em>emphasis</em> too</p><p><em>Starting</em> emphasis</p>
+<ol start="9223372036854775807"><li>a</li><li>b</li></ol>"#;
+        let document = HtmlParser::new().parse_html(html);
+        assert_eq!(
+            MarkdownConverter::new().convert(&document),
+            "\\# not heading\n\n\
+             1\\. not list\n\n\
+             2\\) nor this\n\n\
+             \\- x\n\n\
+             \\+ y\n\n\
+             \\* z\n\n\
+             \\> no quote\n\n\
+             Text\\\n\\===\n\n\
+             1234567890. too long to be a list\n\n\
+             42 is fine, *emphasis* too\n\n\
+             *Starting* emphasis\n\n\
+             999999999. a\n\
+             999999999. b"
+        );
+    }
+}
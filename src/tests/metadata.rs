@@ -0,0 +1,134 @@
+#[cfg(test)]
+mod metadata_tests {
+    use crate::client::WappuClient;
+    use crate::html::HtmlParser;
+    use crate::metadata::{OpenGraphMedia, PageLink, PageMetadata};
+    use crate::tests::server::{serve, MockResponse};
+
+    const PAGE: &str = r#"<!DOCTYPE html>
+<html lang="fi"><head>
+  <base href="/blog/">
+  <title>  Saunan
+     lämpö | Esimerkki </title>
+  <meta name="Description" content="Kaikki löylyistä">
+  <meta name="keywords" content="sauna, löyly, ,kiuas">
+  <meta name="robots" content="index, follow">
+  <meta property="og:title" content="Saunan lämpö">
+  <meta property="og:type" content="article">
+  <meta property="og:url" content="posts/sauna">
+  <meta property="og:image" content="/img/1.jpg">
+  <meta property="og:image:width" content="1200">
+  <meta property="og:image:alt" content="Kiuas">
+  <meta property="og:image:url" content="/img/2.jpg">
+  <meta property="og:image:type" content="image/png">
+  <meta name="twitter:card" content="summary_large_image">
+  <meta name="twitter:site" content="@esimerkki">
+  <meta name="twitter:image:src" content="card.png">
+  <link rel="canonical" href="https://example.com/blog/posts/sauna">
+  <link rel="apple-touch-icon" href="/apple.png" sizes="180x180">
+  <link rel="shortcut icon" href="/favicon.png" type="image/png">
+  <link rel="alternate" hreflang="en" href="https://example.com/en/blog/sauna">
+  <link rel="alternate" type="application/rss+xml" title="Blogi" href="feed.xml">
+  <link rel="prev" href="?page=1">
+</head><body>
+  <a rel="next" href="?page=3">Next</a>
+  <svg><title>Icon</title></svg>
+</body></html>"#;
+
+    #[test]
+    fn test_page_metadata() {
+        let document = HtmlParser::new().parse_html(PAGE);
+        let metadata =
+            PageMetadata::from_document(&document, "https://example.com/blog/posts/sauna?page=2");
+
+        assert_eq!(metadata.title.as_deref(), Some("Saunan lämpö | Esimerkki"));
+        assert_eq!(metadata.description.as_deref(), Some("Kaikki löylyistä"));
+        assert_eq!(metadata.keywords, vec!["sauna", "löyly", "kiuas"]);
+        assert_eq!(metadata.robots.as_deref(), Some("index, follow"));
+        assert_eq!(metadata.language.as_deref(), Some("fi"));
+        assert_eq!(
+            metadata.canonical_url.as_deref(),
+            Some("https://example.com/blog/posts/sauna")
+        );
+        assert_eq!(
+            metadata.favicon.as_deref(),
+            Some("https://example.com/favicon.png")
+        );
+        assert_eq!(metadata.icons.len(), 2);
+        assert_eq!(metadata.icons[0].sizes.as_deref(), Some("180x180"));
+        assert_eq!(metadata.alternates[0].hreflang.as_deref(), Some("en"));
+        assert_eq!(
+            metadata.feeds,
+            vec![PageLink {
+                url: "https://example.com/blog/feed.xml".to_string(),
+                media_type: Some("application/rss+xml".to_string()),
+                hreflang: None,
+                title: Some("Blogi".to_string()),
+                sizes: None,
+            }]
+        );
+        assert_eq!(
+            metadata.prev.as_deref(),
+            Some("https://example.com/blog/?page=1")
+        );
+        assert_eq!(
+            metadata.next.as_deref(),
+            Some("https://example.com/blog/?page=3")
+        );
+
+        let open_graph = &metadata.open_graph;
+        assert_eq!(open_graph.title.as_deref(), Some("Saunan lämpö"));
+        assert_eq!(open_graph.object_type.as_deref(), Some("article"));
+        assert_eq!(
+            open_graph.url.as_deref(),
+            Some("https://example.com/blog/posts/sauna")
+        );
+        assert_eq!(
+            open_graph.images,
+            vec![
+                OpenGraphMedia {
+                    url: "https://example.com/img/1.jpg".to_string(),
+                    width: Some(1200),
+                    alt: Some("Kiuas".to_string()),
+                    ..OpenGraphMedia::default()
+                },
+                OpenGraphMedia {
+                    url: "https://example.com/img/2.jpg".to_string(),
+                    media_type: Some("image/png".to_string()),
+                    ..OpenGraphMedia::default()
+                },
+            ]
+        );
+
+        assert_eq!(
+            metadata.twitter.card.as_deref(),
+            Some("summary_large_image")
+        );
+        assert_eq!(metadata.twitter.site.as_deref(), Some("@esimerkki"));
+        assert_eq!(
+            metadata.twitter.image.as_deref(),
+            Some("https://example.com/blog/card.png")
+        );
+        assert_eq!(metadata.meta["og:image:width"], "1200");
+    }
+
+    #[tokio::test]
+    async fn test_response_metadata() {
+        let url = serve(|_| {
+            MockResponse::ok(
+                r#"<meta http-equiv="Content-Language" content="sv"><title>Hej</title>"#,
+            )
+        })
+        .await;
+        let response = WappuClient::new()
+            .get(&format!("{}/sida", url), None)
+            .await
+            .unwrap();
+
+        let metadata = response.metadata();
+        assert_eq!(metadata.title.as_deref(), Some("Hej"));
+        assert_eq!(metadata.language.as_deref(), Some("sv"));
+        // Browsers fall back to the site's favicon.ico
+        assert_eq!(metadata.favicon, Some(format!("{}/favicon.ico", url)));
+    }
+}
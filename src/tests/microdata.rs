@@ -0,0 +1,234 @@
+#[cfg(test)]
+mod microdata_tests {
+    use crate::html::HtmlParser;
+    use crate::microdata::MicrodataParser;
+    use crate::rdfa::RdfaParser;
+    use serde_json::json;
+
+    const MICRODATA: &str = r#"<html><head><base href="/shop/"></head><body>
+<div itemscope itemtype="https://schema.org/Product" itemref="reviews">
+  <h1 itemprop="name">Sauna   stove</h1>
+  <img itemprop="image" src="stove.jpg" alt="">
+  <meta itemprop="sku" content="1234">
+  <div itemprop="offers" itemscope itemtype="https://schema.org/Offer">
+    <data itemprop="price" value="599">599 €</data>
+    <meta itemprop="priceCurrency" content="EUR">
+    <link itemprop="availability" href="https://schema.org/InStock">
+    <time itemprop="priceValidUntil" datetime="2026-12-31">end of year</time>
+  </div>
+  <span itemprop="color">black</span> <span itemprop="color">steel</span>
+</div>
+<section id="reviews">
+  <div itemprop="aggregateRating" itemscope itemtype="https://schema.org/AggregateRating">
+    <span itemprop="ratingValue">4.5</span> from <span itemprop="reviewCount">12</span> reviews
+  </div>
+</section>
+<p itemscope itemid="urn:isbn:0-330-34032-8"><span itemprop="title http://example.com/name">The Reality Dysfunction</span></p>
+</body></html>"#;
+
+    #[test]
+    fn test_microdata() {
+        let document = HtmlParser::new().parse_html(MICRODATA);
+        let items = MicrodataParser::new()
+            .base_url("https://example.com/products/1")
+            .parse_microdata(&document);
+
+        assert_eq!(
+            items.nodes,
+            vec![
+                json!({
+                    "@context": "https://schema.org/",
+                    "@type": "Product",
+                    "name": "Sauna stove",
+                    "image": "https://example.com/shop/stove.jpg",
+                    "sku": "1234",
+                    "offers": {
+                        "@type": "Offer",
+                        "price": "599",
+                        "priceCurrency": "EUR",
+                        "availability": "https://schema.org/InStock",
+                        "priceValidUntil": "2026-12-31"
+                    },
+                    "color": ["black", "steel"],
+                    "aggregateRating": {
+                        "@type": "AggregateRating",
+                        "ratingValue": "4.5",
+                        "reviewCount": "12"
+                    }
+                }),
+                json!({
+                    "@id": "urn:isbn:0-330-34032-8",
+                    "title": "The Reality Dysfunction",
+                    "http://example.com/name": "The Reality Dysfunction"
+                }),
+            ]
+        );
+
+        // The same lookups as JSON-LD work on microdata
+        let products = document.microdata().products().unwrap();
+        assert_eq!(products[0].image, vec!["stove.jpg"]);
+        assert_eq!(
+            products[0].offers[0].availability.as_deref(),
+            Some("InStock")
+        );
+        assert_eq!(
+            products[0]
+                .aggregate_rating
+                .as_ref()
+                .unwrap()
+                .rating_value
+                .as_deref(),
+            Some("4.5")
+        );
+    }
+
+    #[test]
+    fn test_item_reference_cycles() {
+        let document = HtmlParser::new().parse_html(
+            r#"<div id="a" itemscope itemref="b"><span itemprop="name">A</span></div>
+<div id="b" itemprop="self" itemscope itemref="a"><span itemprop="name">B</span></div>"#,
+        );
+        assert_eq!(
+            document.microdata().nodes,
+            vec![json!({"name": "A", "self": {"name": "B"}})]
+        );
+    }
+
+    #[test]
+    fn test_rdfa_lite() {
+        let document = HtmlParser::new().parse_html(
+            r##"<html prefix="og: https://ogp.me/ns#"><head>
+<meta property="og:title" content="Sauna news">
+<meta property="og:image" content="/og.png">
+</head><body vocab="https://schema.org/">
+<article typeof="NewsArticle" resource="#article">
+  <h1 property="headline">Stoves compared</h1>
+  <time property="datePublished" datetime="2026-10-19">Monday</time>
+  <div property="author" typeof="Person"><span property="name">Maija</span>, <a property="url" href="/maija">profile</a></div>
+  <a property="image" href="lead.jpg">Lead image</a>
+</article>
+<ol typeof="BreadcrumbList">
+  <li property="itemListElement" typeof="ListItem"><a property="item" href="/">Home</a><meta property="name" content="Home"><meta property="position" content="1"></li>
+</ol>
+</body></html>"##,
+        );
+        let items = RdfaParser::new()
+            .base_url("https://example.com/news/stoves")
+            .parse_rdfa(&document);
+
+        assert_eq!(
+            items.nodes,
+            vec![
+                json!({
+                    "@id": "https://example.com/news/stoves",
+                    "og:title": "Sauna news",
+                    "og:image": "/og.png"
+                }),
+                json!({
+                    "@context": "https://schema.org/",
+                    "@type": "NewsArticle",
+                    "@id": "https://example.com/news/stoves#article",
+                    "headline": "Stoves compared",
+                    "datePublished": "2026-10-19",
+                    "author": {
+                        "@type": "Person",
+                        "name": "Maija",
+                        "url": "https://example.com/maija"
+                    },
+                    "image": "https://example.com/news/lead.jpg"
+                }),
+                json!({
+                    "@context": "https://schema.org/",
+                    "@type": "BreadcrumbList",
+                    "itemListElement": {
+                        "@type": "ListItem",
+                        "item": "https://example.com/",
+                        "name": "Home",
+                        "position": "1"
+                    }
+                }),
+            ]
+        );
+
+        let articles = items.articles().unwrap();
+        assert_eq!(articles[0].author, vec!["Maija"]);
+        let breadcrumbs = document.structured_data().breadcrumbs().unwrap();
+        assert_eq!(
+            breadcrumbs[0].item_list_element[0].name.as_deref(),
+            Some("Home")
+        );
+    }
+
+    #[test]
+    fn test_vocabulary_changes() {
+        let document = HtmlParser::new().parse_html(
+            r#"<div itemscope itemtype="https://schema.org/Recipe">
+  <span itemprop="name">Pulla</span>
+  <div itemprop="author" itemscope itemtype="https://schema.org/Person"><span itemprop="name">Maija</span></div>
+  <div itemprop="review" itemscope itemtype="http://data-vocabulary.org/Review">
+    <span itemprop="rating">5</span>
+  </div>
+</div>"#,
+        );
+        assert_eq!(
+            document.microdata().nodes,
+            vec![json!({
+                "@context": "https://schema.org/",
+                "@type": "Recipe",
+                "name": "Pulla",
+                "author": { "@type": "Person", "name": "Maija" },
+                "review": {
+                    "@context": "http://data-vocabulary.org/",
+                    "@type": "Review",
+                    "rating": "5"
+                }
+            })]
+        );
+
+        let document = HtmlParser::new().parse_html(
+            r##"<html prefix="dc: http://purl.org/dc/terms/"><body vocab="https://schema.org/">
+<div typeof="Recipe">
+  <span property="name">Pulla</span>
+  <span property="dc:creator">Maija</span>
+  <div property="review" vocab="http://data-vocabulary.org/" typeof="Review"><span property="rating">5</span></div>
+  <div vocab="http://data-vocabulary.org/"><span property="summary">Sweet</span></div>
+</div>
+<div resource="#bakery"><span property="name">Kahvila</span><a property="sameAs" resource="https://example.org/kahvila">elsewhere</a></div>
+<p property="description">About the page</p>
+</body></html>"##,
+        );
+        assert_eq!(
+            RdfaParser::new()
+                .base_url("https://example.com/pulla")
+                .parse_rdfa(&document)
+                .nodes,
+            vec![
+                json!({
+                    "@id": "https://example.com/pulla",
+                    "https://schema.org/description": "About the page"
+                }),
+                json!({
+                    "@context": "https://schema.org/",
+                    "@type": "Recipe",
+                    "name": "Pulla",
+                    // The prefix isn't expanded with the page's `prefix` attribute
+                    "dc:creator": "Maija",
+                    // An element's own vocab applies to its property too
+                    "http://data-vocabulary.org/review": {
+                        "@context": "http://data-vocabulary.org/",
+                        "@type": "Review",
+                        "rating": "5"
+                    },
+                    "http://data-vocabulary.org/summary": "Sweet"
+                }),
+                // A resource alone names a new subject, next to a property it is the value
+                json!({
+                    "@context": "https://schema.org/",
+                    "@id": "https://example.com/pulla#bakery",
+                    "name": "Kahvila",
+                    "sameAs": "https://example.org/kahvila"
+                }),
+            ]
+        );
+    }
+}
@@ -1,3 +1,28 @@
 pub mod html;
 pub mod client;
-pub mod selector;
\ No newline at end of file
+pub mod selector;
+#[cfg(feature = "captcha")]
+pub mod captcha;
+#[cfg(test)]
+pub mod server;
+#[cfg(feature = "js")]
+pub mod web_engine;
+pub mod session;
+pub mod form;
+#[cfg(feature = "cdp")]
+pub mod cdp;
+pub mod embedded;
+pub mod frames;
+pub mod streaming;
+pub mod xml;
+pub mod feed;
+pub mod edit;
+pub mod serialize;
+pub mod sanitize;
+pub mod markdown;
+pub mod text;
+pub mod readability;
+pub mod jsonld;
+pub mod microdata;
+pub mod metadata;
+pub mod document;
\ No newline at end of file
@@ -0,0 +1,90 @@
+#[cfg(test)]
+mod readability_tests {
+    use crate::html::HtmlParser;
+    use crate::readability::Readability;
+
+    const PAGE: &str = r#"<!DOCTYPE html>
+<html><head>
+  <title>Why saunas are good for you | Example News</title>
+  <meta property="og:image" content="/images/sauna.jpg">
+  <meta name="author" content="By Maija Meikäläinen">
+</head><body>
+<div id="header"><a href="/">Example News</a><ul class="menu"><li><a href="/news">News</a></li><li><a href="/sports">Sports</a></li></ul></div>
+<nav><a href="/a">Home</a> <a href="/b">About</a></nav>
+<div id="main">
+  <div class="post-content">
+    <h1>Why saunas are good for you</h1>
+    <p class="date"><time datetime="2026-10-19T08:00:00Z">19 October</time></p>
+    <p>Sauna bathing has been part of Finnish life for centuries, and research now suggests that regular sessions may lower blood pressure, ease sore muscles and help people sleep.</p>
+    <p>In one long-running study, men who visited a sauna four to seven times a week had fewer heart problems than those who went once a week, even after accounting for age, fitness and smoking.</p>
+    <div class="share"><a href="/share/fb">Facebook</a> <a href="/share/x">X</a></div>
+    <p>Experts still recommend drinking plenty of water, leaving alcohol for later and stepping out whenever the heat becomes uncomfortable.</p>
+  </div>
+  <div id="comments"><h3>Comments</h3><p>Great article, I go every Saturday, and it really helps me relax after a long week.</p></div>
+</div>
+<div class="sidebar"><h3>Related</h3><ul><li><a href="/1">Ice swimming, explained in full detail</a></li><li><a href="/2">Ten best public saunas in Helsinki</a></li></ul></div>
+<footer><p>Copyright Example News, all rights reserved, since 1999.</p></footer>
+</body></html>"#;
+
+    #[test]
+    fn test_extract_article() {
+        let document = HtmlParser::new().parse_html(PAGE);
+        let article = Readability::new()
+            .base_url("https://example.com/2026/10/sauna")
+            .extract(&document)
+            .unwrap();
+
+        assert_eq!(
+            article.title.as_deref(),
+            Some("Why saunas are good for you")
+        );
+        assert_eq!(article.byline.as_deref(), Some("Maija Meikäläinen"));
+        assert_eq!(article.published.as_deref(), Some("2026-10-19T08:00:00Z"));
+        assert_eq!(
+            article.lead_image.as_deref(),
+            Some("https://example.com/images/sauna.jpg")
+        );
+        assert!(article
+            .excerpt
+            .as_deref()
+            .unwrap()
+            .starts_with("Sauna bathing has been part of Finnish life"));
+
+        let text = article.text();
+        assert!(text.starts_with("19 October\n\nSauna bathing"));
+        assert!(text.ends_with("whenever the heat becomes uncomfortable."));
+        for boilerplate in [
+            "Example News",
+            "Home",
+            "Facebook",
+            "Great article",
+            "Ice swimming",
+        ] {
+            assert!(!text.contains(boilerplate), "{}", boilerplate);
+        }
+    }
+
+    #[test]
+    fn test_non_article_pages() {
+        let document = HtmlParser::new().parse_html(
+            r#"<html><head><title>Links</title></head><body><ul><li><a href="/a">A page with a long enough title</a></li></ul><p>Short.</p></body></html>"#,
+        );
+        assert!(Readability::new().extract(&document).is_none());
+
+        // Without metadata the byline and date come from the markup
+        let document = HtmlParser::new().parse_html(
+            r#"<html><body><h1>Headline</h1><span class="byline">by Matti</span><article><p>Plain text paragraph, long enough to count as content, with a comma or two.</p><img src="lead.png"></article></body></html>"#,
+        );
+        let article = Readability::new()
+            .min_text_length(50)
+            .extract(&document)
+            .unwrap();
+        assert_eq!(article.title.as_deref(), Some("Headline"));
+        assert_eq!(article.byline.as_deref(), Some("Matti"));
+        assert_eq!(article.lead_image.as_deref(), Some("lead.png"));
+        assert_eq!(
+            article.content.to_html(),
+            r#"<div><article><p>Plain text paragraph, long enough to count as content, with a comma or two.</p><img src="lead.png"></article></div>"#
+        );
+    }
+}
@@ -0,0 +1,102 @@
+#[cfg(test)]
+mod sanitize_tests {
+    use crate::html::HtmlParser;
+    use crate::sanitize::Sanitizer;
+    use crate::select_by_id;
+    use crate::selector::Selector;
+
+    #[test]
+    fn test_default_allowlist() {
+        let dirty = r#"<p class="intro" onclick="steal()">Hello <b>world</b><script>alert(1)</script></p>
+<a href="javascript:alert(1)" title="x">bad</a><a href=" JaVa&#9;script:alert(1)">tabbed</a>
+<a href="https://example.com/?a=1&amp;b=2" target="_blank">good</a><a href="/relative">relative</a>
+<img src="data:image/png;base64,AAAA" alt="pixel"><font face="Comic Sans">unwrapped <i>text</i></font>
+<style>body { display: none }</style><iframe src="https://evil.example"></iframe><!-- note -->"#;
+
+        let clean = Sanitizer::new().clean(dirty);
+        assert_eq!(
+            clean,
+            r#"<p>Hello <b>world</b></p>
+<a title="x">bad</a><a>tabbed</a>
+<a href="https://example.com/?a=1&amp;b=2">good</a><a href="/relative">relative</a>
+<img alt="pixel">unwrapped <i>text</i>
+"#
+        );
+    }
+
+    #[test]
+    fn test_custom_rules() {
+        let sanitizer = Sanitizer::empty()
+            .allow_tags(&["p", "a", "section"])
+            .allow_attributes("a", &["href"])
+            .allow_attributes("*", &["lang", "onmouseover"])
+            .allow_url_schemes(&["https", "tel"])
+            .remove_with_content(&["aside"])
+            .unwrap_instead(&["noscript"])
+            .keep_comments(true);
+
+        let clean = sanitizer.clean(
+            r#"<section lang="fi" onmouseover="x()"><p>Soita <a href="tel:+358401234567">meille</a> tai <a href="http://example.com">tänne</a></p><aside>Mainos</aside><noscript>Ilman skriptejä</noscript><!-- a -- b --></section>"#,
+        );
+        // Event handlers never get through even when allowed
+        assert_eq!(
+            clean,
+            r#"<section lang="fi"><p>Soita <a href="tel:+358401234567">meille</a> tai <a>tänne</a></p>Ilman skriptejä<!-- a - - b --></section>"#
+        );
+
+        let text_only = Sanitizer::empty().clean("<div><p>One</p><p>Two &lt;3</p></div>");
+        assert_eq!(text_only, "OneTwo &lt;3");
+    }
+
+    #[test]
+    fn test_sanitize_tree() {
+        let document = HtmlParser::new().parse_html(
+            r#"<html><head><title>Page</title></head><body><div id="post"><p>Hi<span onclick="x()"> there</span></p></div></body></html>"#,
+        );
+        let post = select_by_id!(&document, "post").elements[0];
+        let clean = Sanitizer::new().disallow_tags(&["span"]).sanitize(post);
+
+        assert_eq!(clean.to_html(), r#"<div><p>Hi there</p></div>"#);
+        assert_eq!(clean.children[0].text, "Hi there");
+
+        let clean = Sanitizer::new().sanitize(&document);
+        assert_eq!(clean.to_html(), r#"<div><p>Hi<span> there</span></p></div>"#);
+    }
+
+    #[test]
+    fn test_sanitize_disallowed_root() {
+        // Raw text elements write their text unescaped, so the root mustn't stay one when it
+        // isn't allowed
+        for tag in ["xmp", "noembed", "noframes"] {
+            let document = HtmlParser::new()
+                .parse_html(&format!("<{0}><img src=x onerror=alert(2)></{0}>", tag));
+            let root = Selector::new()
+                .from_tag_name(tag)
+                .select(&document)
+                .elements[0];
+            let clean = Sanitizer::new().sanitize(root);
+            assert_eq!(clean.inner_html(), "&lt;img src=x onerror=alert(2)&gt;");
+            assert_eq!(clean.to_html(), "&lt;img src=x onerror=alert(2)&gt;");
+        }
+
+        // Elements removed with their content leave nothing behind, even as the root
+        let document = HtmlParser::new()
+            .parse_html("<iframe><img src=x onerror=alert(2)></iframe><p>After</p>");
+        let iframe = Selector::new()
+            .from_tag_name("iframe")
+            .select(&document)
+            .elements[0];
+        assert_eq!(Sanitizer::new().sanitize(iframe).to_html(), "");
+
+        let document =
+            HtmlParser::new().parse_html("<div><font>Unwrapped <b>bold</b></font></div>");
+        let font = Selector::new()
+            .from_tag_name("font")
+            .select(&document)
+            .elements[0];
+        assert_eq!(
+            Sanitizer::new().sanitize(font).to_html(),
+            "Unwrapped <b>bold</b>"
+        );
+    }
+}
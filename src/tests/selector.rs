@@ -67,7 +67,7 @@ mod tests {
             .get("https://doc.rust-lang.org/book/", None)
             .await
             .unwrap();
-        let html = HtmlParser::new().parse_html(&result.text());
+        let html = HtmlParser::new().parse_html(result.text());
 
         let mut header_selector = Selector::new();
         let header = header_selector.from_class_name("header").select(&html);
@@ -82,7 +82,7 @@ mod tests {
             .get("https://doc.rust-lang.org/book/", None)
             .await
             .unwrap();
-        let html = HtmlParser::new().parse_html(&result.text());
+        let html = HtmlParser::new().parse_html(result.text());
 
         let header = select_by_class!(&html, "header");
 
@@ -97,7 +97,7 @@ mod tests {
             .get("https://doc.rust-lang.org/book/", None)
             .await
             .unwrap();
-        let html = HtmlParser::new().parse_html(&result.text());
+        let html = HtmlParser::new().parse_html(result.text());
 
         let header = select_by_tag_name!(&html, "h1");
 
@@ -178,3 +178,24 @@ mod tests {
     }
 }
 
+
+#[cfg(test)]
+mod selector_match_tests {
+    use crate::{engine::selector::Selector, html::HtmlParser};
+
+    #[test]
+    fn test_class_and_id_selectors_skip_nameless_nodes() {
+        let parsed_html = HtmlParser::new()
+            .parse_html(r#"<p>Intro</p><a class="next" id="next" href="/2">Next</a>"#);
+
+        let by_class = Selector::new()
+            .from_class_name("next")
+            .select_first(&parsed_html)
+            .unwrap();
+        assert_eq!(by_class.tag_name, Some("a".to_string()));
+
+        let by_id = Selector::new().from_id("next").select(&parsed_html);
+        assert_eq!(by_id.len(), 1);
+        assert_eq!(by_id.href(), Some("/2".to_string()));
+    }
+}
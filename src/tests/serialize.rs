@@ -0,0 +1,85 @@
+#[cfg(test)]
+mod serialize_tests {
+    use crate::html::{HtmlElement, HtmlParser};
+    use crate::select_by_class;
+    use crate::selector::Selector;
+    use crate::xml::XmlParser;
+    use serde::Deserialize;
+    use serde_json::json;
+
+    #[test]
+    fn test_compact_json() {
+        let document = HtmlParser::new().parse_html(
+            r#"<!DOCTYPE html><html><head></head><body><!-- note --><p id="a" class="x">Hi <b>there</b>!</p></body></html>"#,
+        );
+        let value = serde_json::to_value(&document).unwrap();
+        assert_eq!(
+            value,
+            json!({"document": [
+                {"doctype": "html"},
+                {"tag": "html", "children": [
+                    {"tag": "head"},
+                    {"tag": "body", "children": [
+                        {"comment": " note "},
+                        {"tag": "p", "attributes": {"class": "x", "id": "a"}, "children": [
+                            "Hi ", {"tag": "b", "children": ["there"]}, "!"
+                        ]}
+                    ]}
+                ]}
+            ]})
+        );
+        // Attributes come out sorted, so the text is stable too
+        assert!(serde_json::to_string(&document)
+            .unwrap()
+            .contains(r#"{"tag":"p","attributes":{"class":"x","id":"a"},"children":["Hi ","#));
+
+        let parsed: HtmlElement = serde_json::from_value(value).unwrap();
+        assert_eq!(parsed, document);
+        let paragraph = select_by_class!(&parsed, "x").elements[0];
+        assert_eq!(paragraph.text, "Hi !");
+    }
+
+    #[test]
+    fn test_round_trip_xml_and_selections() {
+        let document = XmlParser::new().parse_xml(
+            r#"<?xml-stylesheet href="style.css"?><feed xmlns="http://www.w3.org/2005/Atom"><title>T</title></feed>"#,
+        );
+        let json = serde_json::to_string(&document).unwrap();
+        assert!(json.contains(r#"{"target":"xml-stylesheet","data":"href=\"style.css\""}"#));
+        assert!(json.contains(r#""namespace":"http://www.w3.org/2005/Atom""#));
+        let parsed: HtmlElement = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, document);
+
+        let document = HtmlParser::new()
+            .parse_html(r#"<ul><li class="item">One</li><li class="item">Two</li></ul>"#);
+        let json = serde_json::to_string(&select_by_class!(&document, "item")).unwrap();
+        assert_eq!(
+            json,
+            r#"[{"tag":"li","attributes":{"class":"item"},"children":["One"]},{"tag":"li","attributes":{"class":"item"},"children":["Two"]}]"#
+        );
+        let items: Vec<HtmlElement> = serde_json::from_str(&json).unwrap();
+        assert_eq!(items[1].text, "Two");
+
+        assert!(serde_json::from_str::<HtmlElement>(r#"{"unknown": 1}"#).is_err());
+    }
+
+    #[test]
+    fn test_deep_documents() {
+        let depth = 200;
+        let html = format!("{}deep{}", "<div>".repeat(depth), "</div>".repeat(depth));
+        let document = HtmlParser::new().parse_html(&html);
+        let json = serde_json::to_string(&document).unwrap();
+
+        // Past serde_json's default recursion limit
+        let error = serde_json::from_str::<HtmlElement>(&json).unwrap_err();
+        assert!(error.to_string().contains("recursion limit exceeded"));
+
+        let mut deserializer = serde_json::Deserializer::from_str(&json);
+        deserializer.disable_recursion_limit();
+        let parsed = HtmlElement::deserialize(&mut deserializer).unwrap();
+        assert_eq!(parsed, document);
+        let deepest = Selector::new().from_tag_name("div").select(&parsed);
+        assert_eq!(deepest.elements.len(), depth);
+        assert_eq!(deepest.elements[depth - 1].text, "deep");
+    }
+}
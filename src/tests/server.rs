@@ -0,0 +1,130 @@
+// Minimal HTTP/1.1 server for tests that should not depend on external services
+#![allow(dead_code)]
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+#[derive(Debug, Clone)]
+pub struct MockRequest {
+    pub method: String,
+    pub path: String,
+    // Header names are lowercased
+    pub headers: HashMap<String, String>,
+    pub body: String,
+    // The body as it was sent, for bodies that aren't UTF-8
+    pub raw_body: Vec<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MockResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl MockResponse {
+    pub fn ok(body: &str) -> Self {
+        MockResponse::status(200, body)
+    }
+
+    pub fn html(body: &str) -> Self {
+        MockResponse::ok(body).header("Content-Type", "text/html; charset=utf-8")
+    }
+
+    pub fn status(status: u16, body: &str) -> Self {
+        MockResponse {
+            status,
+            headers: Vec::new(),
+            body: body.to_string(),
+        }
+    }
+
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+}
+
+// Serves every request with `handler` and returns the server's base URL, e.g. http://127.0.0.1:1234
+pub async fn serve<F>(handler: F) -> String
+where
+    F: Fn(MockRequest) -> MockResponse + Send + Sync + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    let handler = Arc::new(handler);
+
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let handler = handler.clone();
+            tokio::spawn(async move {
+                let _ = handle_connection(stream, handler.as_ref()).await;
+            });
+        }
+    });
+
+    format!("http://{}", address)
+}
+
+async fn handle_connection<F>(mut stream: TcpStream, handler: &F) -> std::io::Result<()>
+where
+    F: Fn(MockRequest) -> MockResponse,
+{
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 4096];
+    let header_end = loop {
+        let read = stream.read(&mut chunk).await?;
+        if read == 0 {
+            return Ok(());
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+        if let Some(position) = buffer.windows(4).position(|window| window == b"\r\n\r\n") {
+            break position + 4;
+        }
+    };
+
+    let head = String::from_utf8_lossy(&buffer[..header_end]).to_string();
+    let mut lines = head.lines();
+    let mut request_line = lines.next().unwrap_or_default().split_whitespace();
+    let method = request_line.next().unwrap_or_default().to_string();
+    let path = request_line.next().unwrap_or_default().to_string();
+    let headers: HashMap<String, String> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_lowercase(), value.trim().to_string()))
+        .collect();
+
+    let content_length = headers
+        .get("content-length")
+        .and_then(|length| length.parse::<usize>().ok())
+        .unwrap_or(0);
+    while buffer.len() < header_end + content_length {
+        let read = stream.read(&mut chunk).await?;
+        if read == 0 {
+            break;
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+    }
+    let raw_body = buffer[header_end..].to_vec();
+    let body = String::from_utf8_lossy(&raw_body).to_string();
+
+    let response = handler(MockRequest {
+        method,
+        path,
+        headers,
+        body,
+        raw_body,
+    });
+
+    let mut output = format!("HTTP/1.1 {} Mock\r\n", response.status);
+    for (name, value) in &response.headers {
+        output.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    output.push_str(&format!(
+        "Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.body.len(),
+        response.body
+    ));
+    stream.write_all(output.as_bytes()).await?;
+    stream.shutdown().await
+}
@@ -0,0 +1,242 @@
+#[cfg(test)]
+mod session_tests {
+    use crate::selector::Selector;
+    use crate::session::{referer_header, BrowserSession};
+    use crate::tests::server::{serve, MockRequest, MockResponse};
+    use std::sync::{Arc, Mutex};
+
+    // A small site that records every request it receives
+    async fn site() -> (String, Arc<Mutex<Vec<MockRequest>>>) {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let recorded = requests.clone();
+        let url = serve(move |request: MockRequest| {
+            recorded.lock().unwrap().push(request.clone());
+            match request.path.split('?').next().unwrap() {
+                "/" => MockResponse::html(
+                    r#"<html><body>
+                        <a class="nav" href="/about#team">About</a>
+                        <a class="nav" href="/old">Old page</a>
+                        <form id="search" action="/search">
+                            <input name="q" value="default">
+                            <input type="hidden" name="lang" value="en">
+                            <input type="checkbox" name="exact">
+                            <input type="submit" name="go" value="Go">
+                        </form>
+                        <form id="login" method="post" action="/login">
+                            <input name="user">
+                            <textarea name="note">hello</textarea>
+                            <select name="role"><option>guest</option><option value="admin" selected>Admin</option></select>
+                        </form>
+                    </body></html>"#,
+                )
+                .header("Set-Cookie", "session=abc123; Path=/"),
+                "/about" => MockResponse::html("<h1>About</h1>"),
+                "/old" => MockResponse::html(
+                    r#"<meta http-equiv="Refresh" content="0; URL='/new'"><p>Moved</p>"#,
+                ),
+                "/new" => MockResponse::html("<h1>New page</h1>"),
+                "/search" => MockResponse::html("<h1>Results</h1>"),
+                "/login" => MockResponse::html("<h1>Welcome</h1>"),
+                _ => MockResponse::status(404, "not found"),
+            }
+        })
+        .await;
+        (url, requests)
+    }
+
+    #[tokio::test]
+    async fn test_click_sends_referer_and_cookies() {
+        let (url, requests) = site().await;
+        let mut session = BrowserSession::default();
+
+        session.navigate(&format!("{}/", url)).await.unwrap();
+        let page = session
+            .click(Selector::new().from_class_name("nav"))
+            .await
+            .unwrap();
+        assert_eq!(page.url(), format!("{}/about#team", url));
+
+        let requests = requests.lock().unwrap();
+        let about = requests.last().unwrap();
+        assert_eq!(about.path, "/about");
+        assert_eq!(about.headers.get("referer"), Some(&format!("{}/", url)));
+        assert_eq!(
+            about.headers.get("cookie"),
+            Some(&"session=abc123".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_history_back_and_forward() {
+        let (url, _) = site().await;
+        let mut session = BrowserSession::default();
+
+        session.navigate(&format!("{}/", url)).await.unwrap();
+        session.navigate("/about").await.unwrap();
+        assert!(session.can_go_back());
+        assert!(!session.can_go_forward());
+
+        let page = session.back().unwrap();
+        assert_eq!(page.url(), format!("{}/", url));
+        assert!(session.back().is_none());
+
+        let page = session.forward().unwrap();
+        assert_eq!(page.url(), format!("{}/about", url));
+
+        // Navigating from an earlier page drops the forward history
+        session.back();
+        session.navigate("/new").await.unwrap();
+        assert_eq!(
+            session.history(),
+            vec![format!("{}/", url), format!("{}/new", url)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_meta_refresh_is_followed() {
+        let (url, requests) = site().await;
+        let mut session = BrowserSession::default();
+
+        let page = session.navigate(&format!("{}/old", url)).await.unwrap();
+        assert_eq!(page.url(), format!("{}/new", url));
+        assert_eq!(session.history().len(), 1);
+
+        assert_eq!(
+            requests
+                .lock()
+                .unwrap()
+                .last()
+                .unwrap()
+                .headers
+                .get("referer"),
+            Some(&format!("{}/old", url))
+        );
+
+        let mut session = BrowserSession::default().max_refreshes(0);
+        let page = session.navigate(&format!("{}/old", url)).await.unwrap();
+        assert_eq!(page.refresh_url(), Some(format!("{}/new", url)));
+    }
+
+    #[tokio::test]
+    async fn test_submit_get_and_post_forms() {
+        let (url, requests) = site().await;
+        let mut session = BrowserSession::default();
+
+        session.navigate(&format!("{}/", url)).await.unwrap();
+        session
+            .submit(Selector::new().from_id("search"), &[("q", "rust scraping")])
+            .await
+            .unwrap();
+        assert_eq!(
+            requests.lock().unwrap().last().unwrap().path,
+            "/search?q=rust+scraping&lang=en&go=Go"
+        );
+
+        session.back();
+        let page = session
+            .submit(Selector::new().from_id("login"), &[("user", "wappu")])
+            .await
+            .unwrap();
+        assert_eq!(page.url(), format!("{}/login", url));
+
+        let requests = requests.lock().unwrap();
+        let login = requests.last().unwrap();
+        assert_eq!(login.method, "POST");
+        assert_eq!(
+            login.headers.get("content-type"),
+            Some(&"application/x-www-form-urlencoded".to_string())
+        );
+        assert_eq!(login.body, "user=wappu&note=hello&role=admin");
+    }
+
+    #[tokio::test]
+    async fn test_click_without_match() {
+        let (url, _) = site().await;
+        let mut session = BrowserSession::default();
+
+        assert!(session
+            .click(Selector::new().from_tag_name("a"))
+            .await
+            .is_err());
+        session.navigate(&format!("{}/", url)).await.unwrap();
+        assert!(session
+            .click(Selector::new().from_class_name("missing"))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_referer_policy() {
+        let other_requests = Arc::new(Mutex::new(Vec::new()));
+        let recorded = other_requests.clone();
+        let other = serve(move |request: MockRequest| {
+            recorded.lock().unwrap().push(request);
+            MockResponse::html("<h1>Elsewhere</h1>")
+        })
+        .await;
+        let (url, requests) = site().await;
+        let mut session = BrowserSession::default();
+
+        // Within the origin the whole URL is sent, path and query included
+        session
+            .navigate(&format!("{}/search?q=secret#results", url))
+            .await
+            .unwrap();
+        session.navigate("/about").await.unwrap();
+        assert_eq!(
+            requests
+                .lock()
+                .unwrap()
+                .last()
+                .unwrap()
+                .headers
+                .get("referer"),
+            Some(&format!("{}/search?q=secret", url))
+        );
+
+        // Other origins only learn the origin
+        session.navigate("/search?q=secret").await.unwrap();
+        session.navigate(&format!("{}/page", other)).await.unwrap();
+        assert_eq!(
+            other_requests.lock().unwrap()[0].headers.get("referer"),
+            Some(&format!("{}/", url))
+        );
+    }
+
+    #[test]
+    fn test_referer_header() {
+        let referer = |from: &str, to: &str| {
+            referer_header(from, to).map(|value| value.to_str().unwrap().to_string())
+        };
+        assert_eq!(
+            referer(
+                "https://user:pw@example.com/a?b=c#d",
+                "https://example.com/e"
+            ),
+            Some("https://example.com/a?b=c".to_string())
+        );
+        assert_eq!(
+            referer("https://example.com/a?b=c", "https://other.example/"),
+            Some("https://example.com/".to_string())
+        );
+        // A different scheme or port is another origin too
+        assert_eq!(
+            referer("http://example.com/a?b=c", "https://example.com/"),
+            Some("http://example.com/".to_string())
+        );
+        assert_eq!(
+            referer("https://example.com:8443/a", "https://example.com/"),
+            Some("https://example.com:8443/".to_string())
+        );
+        // Nothing on a downgrade from https to http, even to the same host
+        assert_eq!(
+            referer("https://example.com/a", "http://example.com/a"),
+            None
+        );
+        assert_eq!(
+            referer("https://example.com/a", "http://other.example/"),
+            None
+        );
+        assert_eq!(referer("about:blank", "https://example.com/"), None);
+    }
+}
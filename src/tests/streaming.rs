@@ -0,0 +1,132 @@
+#[cfg(test)]
+mod streaming_tests {
+    use crate::client::WappuClient;
+    use crate::selector::Selector;
+    use crate::streaming::{select_stream, HtmlEvent, StreamingParser, SubtreeCollector};
+    use crate::tests::server::{serve, MockRequest, MockResponse};
+    use std::collections::HashMap;
+
+    fn start(tag_name: &str, attributes: &[(&str, &str)]) -> HtmlEvent {
+        HtmlEvent::Start {
+            tag_name: tag_name.to_string(),
+            attributes: attributes
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect::<HashMap<_, _>>(),
+        }
+    }
+
+    fn end(tag_name: &str) -> HtmlEvent {
+        HtmlEvent::End {
+            tag_name: tag_name.to_string(),
+        }
+    }
+
+    fn text(text: &str) -> HtmlEvent {
+        HtmlEvent::Text(text.to_string())
+    }
+
+    fn parse_in_chunks(html: &str, chunk_size: usize) -> Vec<HtmlEvent> {
+        let mut parser = StreamingParser::new();
+        let mut events = Vec::new();
+        for chunk in html.as_bytes().chunks(chunk_size) {
+            events.extend(parser.feed_bytes(chunk));
+        }
+        events.extend(parser.finish());
+
+        // Text split across chunks arrives in pieces, join them to compare
+        let mut merged: Vec<HtmlEvent> = Vec::new();
+        for event in events {
+            match (merged.last_mut(), event) {
+                (Some(HtmlEvent::Text(previous)), HtmlEvent::Text(next)) => {
+                    previous.push_str(&next)
+                }
+                (_, event) => merged.push(event),
+            }
+        }
+        merged
+    }
+
+    #[test]
+    fn test_events_with_implied_end_tags() {
+        let html = r#"<ul id="list"><li>Kahvi &amp; pulla<li>Äänekoski<br></ul><!-- done --><script>if (a < b) {}</script><p>one<div>two</div></p>"#;
+        let expected = vec![
+            start("ul", &[("id", "list")]),
+            start("li", &[]),
+            text("Kahvi & pulla"),
+            end("li"),
+            start("li", &[]),
+            text("Äänekoski"),
+            start("br", &[]),
+            end("br"),
+            end("li"),
+            end("ul"),
+            HtmlEvent::Comment(" done ".to_string()),
+            start("script", &[]),
+            text("if (a < b) {}"),
+            end("script"),
+            start("p", &[]),
+            text("one"),
+            end("p"),
+            start("div", &[]),
+            text("two"),
+            end("div"),
+        ];
+
+        assert_eq!(parse_in_chunks(html, html.len()), expected);
+        // Chunks split tags, entities and multi-byte characters
+        for chunk_size in [1, 2, 3, 7] {
+            assert_eq!(parse_in_chunks(html, chunk_size), expected);
+        }
+    }
+
+    #[test]
+    fn test_collect_matching_subtrees() {
+        let html = r#"
+            <div class="listing"><h2>Sauna</h2><span class="price">120</span></div>
+            <p>Not a listing</p>
+            <div class="listing"><h2>Cabin</h2><div class="listing">nested</div></div>
+        "#;
+        let mut selector = Selector::new();
+        selector.from_class_name("listing");
+        let mut collector = SubtreeCollector::new(&selector);
+        let mut parser = StreamingParser::new();
+
+        let mut listings = Vec::new();
+        for event in parser.feed(html).into_iter().chain(parser.finish()) {
+            listings.extend(collector.push(event));
+        }
+
+        assert_eq!(listings.len(), 2);
+        assert_eq!(listings[0].children[0].text, "Sauna");
+        assert_eq!(listings[0].children[1].attributes["class"], "price");
+        assert_eq!(listings[1].children[1].text, "nested");
+    }
+
+    #[tokio::test]
+    async fn test_select_from_streamed_response() {
+        let url = serve(|_: MockRequest| {
+            let rows: String = (0..500)
+                .map(|i| format!(r#"<tr class="row"><td>{}</td><td>Item ñ{}</td></tr>"#, i, i))
+                .collect();
+            MockResponse::html(&format!("<table>{}</table>", rows))
+        })
+        .await;
+
+        let mut stream = WappuClient::new()
+            .get_stream(&format!("{}/listing", url), None)
+            .await
+            .unwrap();
+        assert!(stream.status_code().is_success());
+
+        let mut rows = Vec::new();
+        select_stream(&mut stream, Selector::new().from_class_name("row"), |row| {
+            rows.push(row)
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(rows.len(), 500);
+        assert_eq!(rows[499].children[1].text, "Item ñ499");
+    }
+}
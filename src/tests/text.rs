@@ -0,0 +1,85 @@
+#[cfg(test)]
+mod text_tests {
+    use crate::html::HtmlParser;
+    use crate::select_by_class;
+    use crate::selector::Selector;
+    use crate::text::TextRenderer;
+
+    const PAGE: &str = r#"<html><head><title>Ignored</title><style>p { color: red }</style></head><body>
+<h1>Saunan   <em>lämpö</em></h1>
+<p>First   line<br>
+   second <b>line</b> </p>
+<p hidden>Hidden</p><p style="DISPLAY : none !important">Also hidden</p><script>ignored()</script>
+<div>A <span style="visibility:hidden">secret</span>block</div>
+<ul>
+  <li>One</li>
+  <li>Two
+    <ol start="3"><li>Three</li><li>Four</li></ol>
+  </li>
+</ul>
+<pre>  keep
+    this</pre>
+<table>
+  <tr><th>Name</th><th>Price</th></tr>
+  <tr><td>Coffee</td><td>1.90</td></tr>
+</table>
+<details><summary>More</summary>Collapsed</details>
+<p>Last</p>
+</body></html>"#;
+
+    #[test]
+    fn test_rendered_text() {
+        let document = HtmlParser::new().parse_html(PAGE);
+        assert_eq!(
+            document.rendered_text(),
+            "Saunan lämpö
+
+First line
+second line
+
+A block
+
+• One
+• Two
+  3. Three
+  4. Four
+
+  keep
+    this
+
+Name\tPrice
+Coffee\t1.90
+
+More
+
+Last"
+        );
+    }
+
+    #[test]
+    fn test_custom_markers_and_selection() {
+        let html = r#"<div class="card"><ul><li>a</li><li>b</li></ul></div><span class="card">inline <i>text</i></span>
+<table class="card"><tr><td>x</td><td>y</td></tr></table>"#;
+        let document = HtmlParser::new().parse_html(html);
+        let renderer = TextRenderer::new().list_bullet("-").cell_separator(" | ");
+
+        assert_eq!(
+            renderer.render_selection(&select_by_class!(&document, "card")),
+            "- a\n- b\n\ninline text\n\nx | y"
+        );
+        assert_eq!(
+            select_by_class!(&document, "card").rendered_text(),
+            "• a\n• b\n\ninline text\n\nx\ty"
+        );
+    }
+
+    #[test]
+    fn test_list_numbers_saturate() {
+        let document = HtmlParser::new()
+            .parse_html(r#"<ol start="9223372036854775807"><li>a</li><li>b</li></ol>"#);
+        assert_eq!(
+            document.rendered_text(),
+            "9223372036854775807. a\n9223372036854775807. b"
+        );
+    }
+}
@@ -0,0 +1,238 @@
+#[cfg(test)]
+mod web_engine_tests {
+    use crate::client::WappuClient;
+    use crate::selector::Selector;
+    use crate::tests::server::{serve, MockRequest, MockResponse};
+    use crate::web_engine::WebEngine;
+    use crate::{select_by_class, select_by_id, select_by_tag_name};
+
+    #[tokio::test]
+    async fn test_inline_scripts_build_content() {
+        let html = r#"
+            <html><body>
+                <ul id="list"></ul>
+                <script>
+                    var list = document.getElementById("list");
+                    ["one", "two", "three"].forEach(function (name) {
+                        var item = document.createElement("li");
+                        item.className = "item";
+                        item.textContent = name;
+                        list.appendChild(item);
+                    });
+                    console.log("items:", list.children.length);
+                </script>
+            </body></html>
+        "#;
+
+        let page = WebEngine::default()
+            .render(html, "https://example.com/")
+            .await
+            .unwrap();
+
+        let items = select_by_class!(&page.document, "item");
+        assert_eq!(items.text(), "one two three");
+        assert_eq!(page.console, vec!["items: 3"]);
+        assert!(page.errors.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_inner_html_and_query_selector() {
+        let html = r##"
+            <div id="app" data-name="wappu"></div>
+            <script>
+                var app = document.querySelector("div#app");
+                app.innerHTML = '<p class="greeting">Hello, <b>' + app.dataset.name + '</b></p>';
+                document.querySelector("#app > p.greeting").setAttribute("data-done", "yes");
+            </script>
+        "##;
+
+        let page = WebEngine::default()
+            .render(html, "https://example.com/")
+            .await
+            .unwrap();
+
+        let greeting = select_by_tag_name!(&page.document, "p");
+        assert_eq!(greeting.class(), Some("greeting".to_string()));
+        assert_eq!(
+            greeting.elements[0].attributes.get("data-done"),
+            Some(&"yes".to_string())
+        );
+        assert_eq!(select_by_tag_name!(&page.document, "b").text(), "wappu");
+    }
+
+    #[tokio::test]
+    async fn test_timers_and_load_events() {
+        let html = r#"
+            <p id="status">loading</p>
+            <script>
+                var status = document.getElementById("status");
+                document.addEventListener("DOMContentLoaded", function () {
+                    status.textContent = "ready";
+                });
+                window.addEventListener("load", function () {
+                    setTimeout(function () {
+                        status.textContent += " and rendered";
+                    }, 2000);
+                });
+                setTimeout(function () {
+                    status.textContent = "too late";
+                }, 60000);
+            </script>
+        "#;
+
+        let page = WebEngine::default()
+            .render(html, "https://example.com/")
+            .await
+            .unwrap();
+
+        assert_eq!(
+            select_by_id!(&page.document, "status").text(),
+            "ready and rendered"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_script_errors_are_collected() {
+        let html = r#"
+            <p id="out"></p>
+            <script>undefinedFunction();</script>
+            <script>document.getElementById("out").textContent = "still running";</script>
+        "#;
+
+        let page = WebEngine::default()
+            .render(html, "https://example.com/")
+            .await
+            .unwrap();
+
+        assert_eq!(page.errors.len(), 1);
+        assert!(page.errors[0].contains("undefinedFunction"));
+        assert_eq!(select_by_id!(&page.document, "out").text(), "still running");
+    }
+
+    #[tokio::test]
+    async fn test_fetch_and_external_scripts_use_client() {
+        let url = serve(|request: MockRequest| match request.path.as_str() {
+            "/" => MockResponse::html(
+                r#"<html><body>
+                    <div id="products"></div>
+                    <script src="/app.js"></script>
+                </body></html>"#,
+            ),
+            "/app.js" => MockResponse::ok(
+                r#"fetch("/api/products", { headers: { "X-Requested-With": "fetch" } })
+                    .then(function (response) { return response.json(); })
+                    .then(function (products) {
+                        var container = document.getElementById("products");
+                        products.forEach(function (product) {
+                            var div = document.createElement("div");
+                            div.className = "product";
+                            div.textContent = product.name;
+                            container.appendChild(div);
+                        });
+                    });"#,
+            ),
+            "/api/products"
+                if request.headers.get("x-requested-with").map(String::as_str) == Some("fetch") =>
+            {
+                MockResponse::ok(r#"[{"name":"Tea"},{"name":"Coffee"}]"#)
+            }
+            _ => MockResponse::status(404, "not found"),
+        })
+        .await;
+
+        let engine = WebEngine::new(WappuClient::new());
+        let page = engine.load(&format!("{}/", url)).await.unwrap();
+
+        assert!(page.errors.is_empty(), "{:?}", page.errors);
+        let products = Selector::new()
+            .from_class_name("product")
+            .select(&page.document);
+        assert_eq!(products.text(), "Tea Coffee");
+    }
+
+    #[tokio::test]
+    async fn test_xml_http_request_and_dynamic_scripts() {
+        let url = serve(|request: MockRequest| match request.path.as_str() {
+            "/data.txt" => MockResponse::ok("from xhr"),
+            "/late.js" => {
+                MockResponse::ok(r#"document.getElementById("late").textContent = "loaded";"#)
+            }
+            _ => MockResponse::status(404, "not found"),
+        })
+        .await;
+
+        let html = r#"
+            <p id="xhr"></p>
+            <p id="late"></p>
+            <script>
+                var request = new XMLHttpRequest();
+                request.open("GET", "/data.txt");
+                request.onload = function () {
+                    document.getElementById("xhr").textContent = request.status + " " + request.responseText;
+                };
+                request.send();
+
+                var script = document.createElement("script");
+                script.src = "/late.js";
+                document.body.appendChild(script);
+            </script>
+        "#;
+
+        let page = WebEngine::default()
+            .render(html, &format!("{}/page", url))
+            .await
+            .unwrap();
+
+        assert_eq!(select_by_id!(&page.document, "xhr").text(), "200 from xhr");
+        assert_eq!(select_by_id!(&page.document, "late").text(), "loaded");
+    }
+
+    #[tokio::test]
+    async fn test_inner_html_parses_in_element_context() {
+        let html = r#"
+            <table><tbody id="rows"></tbody></table>
+            <script>
+                var rows = document.getElementById("rows");
+                rows.innerHTML = "<tr><td>Tea</td></tr>";
+                rows.insertAdjacentHTML("beforeend", "<tr><td>Coffee</td></tr>");
+            </script>
+        "#;
+
+        let page = WebEngine::default()
+            .render(html, "https://example.com/")
+            .await
+            .unwrap();
+
+        assert_eq!(select_by_tag_name!(&page.document, "tr").elements.len(), 2);
+        assert_eq!(
+            select_by_tag_name!(&page.document, "td").text(),
+            "Tea Coffee"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_comments_survive_rendering() {
+        let html = r#"<!DOCTYPE html>
+            <div id="app"><!-- server rendered --></div>
+            <script>
+                var app = document.getElementById("app");
+                app.appendChild(document.createComment("added"));
+                app.innerHTML += "<!-- parsed -->";
+            </script>
+        "#;
+
+        let page = WebEngine::default()
+            .render(html, "https://example.com/")
+            .await
+            .unwrap();
+
+        assert_eq!(page.document.doctype().unwrap().name, "html");
+        let app = select_by_id!(&page.document, "app");
+        let comments: Vec<&str> = app.elements[0]
+            .comments()
+            .iter()
+            .map(|comment| comment.text.as_str())
+            .collect();
+        assert_eq!(comments, vec![" server rendered ", "added", " parsed "]);
+    }
+}
@@ -0,0 +1,171 @@
+#[cfg(test)]
+mod xml_tests {
+    use crate::html::{HtmlParser, NodeKind};
+    use crate::selector::Selector;
+    use crate::xml::XmlParser;
+
+    const ATOM: &str = "http://www.w3.org/2005/Atom";
+
+    #[test]
+    fn test_parse_feed() {
+        let feed = r#"<?xml version="1.0" encoding="utf-8"?>
+<rss version="2.0" xmlns:atom="http://www.w3.org/2005/Atom" xmlns:media="http://search.yahoo.com/mrss/">
+  <channel>
+    <title>Uutiset</title>
+    <atom:link href="https://example.com/feed" rel="self"/>
+    <item>
+      <title><![CDATA[Kahvi & pulla]]></title>
+      <link>https://example.com/1</link>
+      <media:thumbnail url="https://example.com/1.jpg"/>
+      <pubDate>Mon, 19 Oct 2026 08:00:00 GMT</pubDate>
+    </item>
+  </channel>
+</rss>"#;
+        let document = XmlParser::new().parse_xml(feed);
+
+        let declaration = document.processing_instructions();
+        assert_eq!(
+            declaration.first().map(|node| &node.kind),
+            Some(&NodeKind::ProcessingInstruction {
+                target: "xml".to_string()
+            })
+        );
+
+        let links = Selector::new().from_tag_name("link").select(&document);
+        assert_eq!(links.text(), "https://example.com/1");
+
+        let self_link = Selector::new().from_tag_name("atom:link").select(&document);
+        assert_eq!(self_link.href().as_deref(), Some("https://example.com/feed"));
+        assert_eq!(self_link.elements[0].namespace.as_deref(), Some(ATOM));
+        assert_eq!(self_link.elements[0].local_name(), Some("link"));
+
+        // `<media:thumbnail/>` is closed, so pubDate is its sibling and keeps its case
+        let item = Selector::new().from_tag_name("item").select(&document);
+        let names: Vec<&str> = item.elements[0]
+            .children
+            .iter()
+            .filter_map(|child| child.tag_name.as_deref())
+            .collect();
+        assert_eq!(names, vec!["title", "link", "media:thumbnail", "pubDate"]);
+        let title = item.elements[0].children.iter().find(|child| child.is_element());
+        assert_eq!(title.unwrap().text, "Kahvi & pulla");
+
+        // The HTML parser lowercases names and treats `<link>` as void
+        let html = HtmlParser::new().parse_html(feed);
+        assert!(Selector::new().from_tag_name("pubDate").select(&html).is_empty());
+    }
+
+    #[test]
+    fn test_namespaced_names() {
+        let soap = r#"<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/">
+  <soap:Body>
+    <m:GetPriceResponse xmlns:m="https://example.com/prices">
+      <m:Price>1.90</m:Price>
+    </m:GetPriceResponse>
+  </soap:Body>
+</soap:Envelope>"#;
+        let document = XmlParser::new().parse_xml(soap);
+        let price = Selector::new()
+            .from_namespaced_name("https://example.com/prices", "Price")
+            .select(&document);
+        assert_eq!(price.text(), "1.90");
+        assert_eq!(price.tag_name().as_deref(), Some("m:Price"));
+
+        // Atom uses a default namespace, the selector doesn't care about prefixes
+        let feed = format!(
+            r#"<feed xmlns="{}"><entry><title>One</title></entry><x:entry xmlns:x="{}"><x:title>Two</x:title></x:entry></feed>"#,
+            ATOM, ATOM
+        );
+        let document = XmlParser::new().parse_xml(&feed);
+        let titles = Selector::new()
+            .from_namespaced_name(ATOM, "title")
+            .select(&document);
+        assert_eq!(titles.text(), "One Two");
+        assert!(Selector::new()
+            .from_namespaced_name("urn:other", "title")
+            .select(&document)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_malformed_documents() {
+        let parser = XmlParser::new();
+        for input in ["", "not xml at all"] {
+            assert!(parser.parse_xml(input).children.is_empty());
+        }
+
+        // An unclosed element is closed by its parent's end tag
+        let feed = parser
+            .parse_xml("<feed><entry><title>One</entry><entry><title>Two</title></entry></feed>");
+        let entries = Selector::new().from_tag_name("entry").select(&feed);
+        assert_eq!(entries.len(), 2);
+        let titles = Selector::new().from_tag_name("title").select(&feed);
+        assert_eq!(titles.text(), "One Two");
+
+        // Mismatched end tags, bare ampersands, unquoted attributes and a missing root end tag
+        let document = parser
+            .parse_xml(r#"<root version=2><item>Fish & chips &amp; peas</wrong></item><item>Last"#);
+        let items = Selector::new().from_tag_name("item").select(&document);
+        assert_eq!(items.len(), 2);
+        assert_eq!(items.elements[0].text, "Fish & chips & peas");
+        assert_eq!(items.elements[1].text, "Last");
+        assert_eq!(
+            document.children[0]
+                .attributes
+                .get("version")
+                .map(String::as_str),
+            Some("2")
+        );
+
+        // Undeclared prefixes keep the name but resolve to no namespace
+        let document = parser.parse_xml("<root><x:item>Prefixed</x:item></root>");
+        let item = Selector::new().from_tag_name("x:item").select(&document);
+        assert_eq!(item.text(), "Prefixed");
+        assert_eq!(item.elements[0].namespace, None);
+
+        // Anything after the root element is dropped
+        let document = parser.parse_xml("<root><item>1</item></root><extra>2</extra>");
+        assert_eq!(document.children.len(), 1);
+        assert!(Selector::new()
+            .from_tag_name("extra")
+            .select(&document)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_entities_are_not_expanded() {
+        // A billion laughs document stays as small as it was written
+        let laughs = r#"<!DOCTYPE lolz [
+  <!ENTITY lol "lol">
+  <!ENTITY lol2 "&lol;&lol;&lol;&lol;&lol;&lol;&lol;&lol;&lol;&lol;">
+  <!ENTITY lol3 "&lol2;&lol2;&lol2;&lol2;&lol2;&lol2;&lol2;&lol2;&lol2;&lol2;">
+  <!ENTITY secret SYSTEM "file:///etc/passwd">
+]>
+<lolz>&lol3;</lolz>
+<!-- the rest is ignored -->"#;
+        let document = XmlParser::new().parse_xml(laughs);
+        let root = Selector::new().from_tag_name("lolz").select(&document);
+        assert_eq!(root.text(), "&lol3;");
+
+        let document = XmlParser::new()
+            .parse_xml(r#"<!DOCTYPE note [<!ENTITY secret SYSTEM "file:///etc/passwd">]><note>&secret;</note>"#);
+        assert_eq!(
+            Selector::new()
+                .from_tag_name("note")
+                .select(&document)
+                .text(),
+            "&secret;"
+        );
+
+        // CDATA stays text and comments stay comments
+        let document =
+            XmlParser::new().parse_xml("<root><![CDATA[<b>not a tag</b>]]><!-- note --></root>");
+        let root = &document.children[0];
+        assert_eq!(root.text, "<b>not a tag</b>");
+        assert!(Selector::new()
+            .from_tag_name("b")
+            .select(&document)
+            .is_empty());
+        assert_eq!(root.children[1].kind, NodeKind::Comment);
+    }
+}
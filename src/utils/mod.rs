@@ -1 +1,55 @@
-//todo
\ No newline at end of file
+use crate::engine::html::HtmlElement;
+use url::Url;
+
+// Resolves a possibly relative URL found in a page against the page's URL
+pub(crate) fn resolve_url(base: &str, url: &str) -> Option<String> {
+    let url = url.trim();
+    Url::parse(base)
+        .ok()?
+        .join(url)
+        .ok()
+        .map(|resolved| resolved.to_string())
+}
+
+// URL relative links in the document resolve against: the first `<base href>`, or the page URL
+pub(crate) fn document_base_url(document: &HtmlElement, page_url: &str) -> String {
+    find_base_href(document)
+        .and_then(|href| resolve_url(page_url, href))
+        .unwrap_or_else(|| page_url.to_string())
+}
+
+fn find_base_href(element: &HtmlElement) -> Option<&str> {
+    find_element(element, &|element| {
+        element.tag_name.as_deref() == Some("base") && element.attributes.contains_key("href")
+    })
+    .and_then(|base| base.attributes.get("href"))
+    .map(String::as_str)
+}
+
+// First element in document order, starting with `element` itself, matching the predicate
+pub(crate) fn find_element<'a>(
+    element: &'a HtmlElement,
+    predicate: &dyn Fn(&HtmlElement) -> bool,
+) -> Option<&'a HtmlElement> {
+    if predicate(element) {
+        return Some(element);
+    }
+    element
+        .children
+        .iter()
+        .find_map(|child| find_element(child, predicate))
+}
+
+#[cfg(feature = "captcha")]
+pub(crate) fn find_element_mut<'a>(
+    element: &'a mut HtmlElement,
+    predicate: &dyn Fn(&HtmlElement) -> bool,
+) -> Option<&'a mut HtmlElement> {
+    if predicate(element) {
+        return Some(element);
+    }
+    element
+        .children
+        .iter_mut()
+        .find_map(|child| find_element_mut(child, predicate))
+}